@@ -0,0 +1,1268 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use fs2::FileExt;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use time::{
+    Date, Duration, OffsetDateTime, UtcOffset, format_description::well_known::Rfc3339,
+    macros::format_description,
+};
+
+use crate::{metadata::current_timestamp, migrations, workspace::Workspace};
+
+pub const EVENT_INIT: &str = "init";
+pub const EVENT_LOCK_ACQUIRED: &str = "lock.acquired";
+pub const EVENT_LOCK_RELEASED: &str = "lock.released";
+pub const EVENT_RUN_STARTED: &str = "run.started";
+pub const EVENT_RUN_FINISHED: &str = "run.finished";
+pub const EVENT_PATCH_APPLIED: &str = "patch.applied";
+pub const EVENT_CONFIG_CHANGED: &str = "config.changed";
+pub const EVENT_SCHEMA_MIGRATED: &str = "schema.migrated";
+
+const DAILY_EXTENSION: &str = "ndjson";
+const HISTORY_PREFIX: &str = "history-";
+const HISTORY_SUFFIX: &str = ".ndjson.zst";
+
+/// One line of an audit file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub ts: String,
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+    /// SHA-256 of the previous line of the audit trail; unset only for the
+    /// first event ever written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(event: &str) -> Self {
+        Self {
+            ts: current_timestamp(),
+            event: event.to_string(),
+            task_id: None,
+            run_id: None,
+            data: Value::Null,
+            prev: None,
+        }
+    }
+
+    pub fn task(mut self, task_id: &str) -> Self {
+        self.task_id = Some(task_id.to_string());
+        self
+    }
+
+    pub fn run(mut self, run_id: &str) -> Self {
+        self.run_id = Some(run_id.to_string());
+        self
+    }
+
+    pub fn data(mut self, data: Value) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// UTC day the event belongs to, `YYYY-MM-DD`.
+    pub fn day(&self) -> &str {
+        self.ts.get(..10).unwrap_or(&self.ts)
+    }
+}
+
+/// Filters for [`AuditLog::query`]; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub event: Option<String>,
+    pub task_id: Option<String>,
+    pub run_id: Option<String>,
+    /// Inclusive lower bound, an RFC 3339 time or a UTC date; compared with
+    /// `ts` as a point in time.
+    pub since: Option<String>,
+    /// Inclusive upper bound; a date matches the whole UTC day.
+    pub until: Option<String>,
+    /// Keep only the most recent events.
+    pub limit: Option<usize>,
+}
+
+/// First place where the hash chain does not hold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainBreak {
+    pub day: String,
+    /// Byte offset of the offending event; unset when the day as a whole is
+    /// at fault (missing file or mismatching chain head).
+    pub offset: Option<u64>,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.offset {
+            Some(offset) => write!(f, "{} at offset {offset}: {}", self.day, self.reason),
+            None => write!(f, "{}: {}", self.day, self.reason),
+        }
+    }
+}
+
+/// Outcome of [`AuditLog::verify`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainReport {
+    pub days: usize,
+    pub events: usize,
+    pub broken: Option<ChainBreak>,
+}
+
+/// Append-only audit trail: `audit/runs/<day>.ndjson` files for recent days,
+/// `audit/history-<day>.ndjson.zst` for rotated ones, and the byte offset of
+/// every event in `audit/audit_index.sqlite`.
+///
+/// Every line carries the SHA-256 of the line before it (`prev`), and the
+/// hash of the last line of each day is kept in `chain_heads`, so edits,
+/// removals and truncation are detected by [`AuditLog::verify`].
+pub struct AuditLog {
+    dir: PathBuf,
+    lock_path: PathBuf,
+    index: Connection,
+}
+
+impl AuditLog {
+    /// Opens the audit log of an initialized workspace.
+    pub fn open(workspace: &Workspace) -> Result<Self> {
+        let path = workspace.audit_index_path();
+        let index = Connection::open_with_flags(
+            &path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| {
+            format!(
+                "Failed to open audit index at {} (run `alisa init` to create it)",
+                path.display()
+            )
+        })?;
+        let dir = path
+            .parent()
+            .expect("audit index lives inside the audit directory")
+            .to_path_buf();
+        Ok(Self {
+            dir,
+            lock_path: workspace.audit_lock_path(),
+            index,
+        })
+    }
+
+    /// Appends `event` to the file of its day and indexes its byte offset.
+    ///
+    /// Days before the event's day are rotated into history files first. An
+    /// event older than the newest day of the trail (a clock running behind,
+    /// or a process that started before midnight) goes to the newest day so
+    /// the chain only ever grows at its end.
+    pub fn append(&self, event: &AuditEvent) -> Result<()> {
+        let _lock = self.lock()?;
+        let newest: Option<String> = self
+            .index
+            .query_row("SELECT MAX(day) FROM chain_heads", [], |r| r.get(0))
+            .context("Failed to read audit chain head")?;
+        let day = match newest {
+            Some(newest) if newest.as_str() > event.day() => newest,
+            _ => event.day().to_string(),
+        };
+        self.rotate_before(&day)?;
+
+        let path = self.daily_path(&day);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = lock_live(&path, true)?.expect("created files are always live");
+        let result = self.append_locked(&mut file, &day, event);
+        let _ = FileExt::unlock(&file);
+        result.with_context(|| format!("Failed to append to {}", path.display()))
+    }
+
+    fn append_locked(&self, file: &mut File, day: &str, event: &AuditEvent) -> Result<()> {
+        let prev = self
+            .index
+            .query_row(
+                "SELECT head FROM chain_heads ORDER BY day DESC LIMIT 1",
+                [],
+                |r| r.get::<_, String>(0),
+            )
+            .optional()
+            .context("Failed to read audit chain head")?;
+        let event = AuditEvent {
+            prev,
+            ..event.clone()
+        };
+        let line = serde_json::to_string(&event).context("Failed to encode audit event")?;
+        let head = hex::encode(Sha256::digest(line.as_bytes()));
+
+        // A day rotated before this event keeps its earlier lines in the
+        // history file; offsets continue after them.
+        let offset = self.history_len(day)? + file.metadata()?.len();
+        file.write_all(format!("{line}\n").as_bytes())?;
+        file.flush()?;
+
+        let tx = self.index.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO events (day, offset, ts, event, task_id, run_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                day,
+                offset as i64,
+                event.ts,
+                event.event,
+                event.task_id,
+                event.run_id
+            ],
+        )
+        .context("Failed to index audit event")?;
+        tx.execute(
+            "INSERT INTO chain_heads (day, head, events) VALUES (?1, ?2, 1)
+             ON CONFLICT(day) DO UPDATE SET head = excluded.head, events = events + 1",
+            params![day, head],
+        )
+        .context("Failed to update audit chain head")?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Walks the hash chain over every day, plain or rotated, and reports
+    /// the first broken link.
+    pub fn verify(&self) -> Result<ChainReport> {
+        let mut heads: std::collections::BTreeMap<String, (String, u64)> = self
+            .index
+            .prepare("SELECT day, head, events FROM chain_heads")?
+            .query_map([], |r| {
+                Ok((r.get(0)?, (r.get(1)?, r.get::<_, i64>(2)? as u64)))
+            })
+            .and_then(Iterator::collect)
+            .context("Failed to read audit chain heads")?;
+        let mut days = self.daily_days()?;
+        days.extend(self.history_days()?);
+        days.extend(heads.keys().cloned());
+        days.sort();
+        days.dedup();
+
+        let mut report = ChainReport {
+            days: days.len(),
+            events: 0,
+            broken: None,
+        };
+        let mut prev: Option<String> = None;
+        for day in days {
+            let broken = |offset, reason: String| ChainBreak {
+                day: day.clone(),
+                offset,
+                reason,
+            };
+            if !self.daily_path(&day).exists() && !self.history_path(&day).exists() {
+                report.broken = Some(broken(None, "audit file is missing".into()));
+                return Ok(report);
+            }
+            let mut reader = BufReader::new(self.open_day(&day)?);
+            let mut offset = 0u64;
+            let mut count = 0u64;
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                let read = reader
+                    .read_until(b'\n', &mut line)
+                    .with_context(|| format!("Failed to read audit file of {day}"))?;
+                if read == 0 {
+                    break;
+                }
+                let content = line.strip_suffix(b"\n").unwrap_or(&line);
+                let event: AuditEvent = match serde_json::from_slice(content) {
+                    Ok(event) => event,
+                    Err(err) => {
+                        report.broken =
+                            Some(broken(Some(offset), format!("malformed event: {err}")));
+                        return Ok(report);
+                    }
+                };
+                if event.prev != prev {
+                    report.broken = Some(broken(
+                        Some(offset),
+                        format!(
+                            "previous hash is {}, expected {}",
+                            event.prev.as_deref().unwrap_or("<none>"),
+                            prev.as_deref().unwrap_or("<none>")
+                        ),
+                    ));
+                    return Ok(report);
+                }
+                prev = Some(hex::encode(Sha256::digest(content)));
+                offset += read as u64;
+                count += 1;
+                report.events += 1;
+            }
+            match heads.remove(&day) {
+                None => {
+                    report.broken = Some(broken(None, "no chain head recorded".into()));
+                    return Ok(report);
+                }
+                Some((head, events)) if Some(&head) != prev.as_ref() || events != count => {
+                    report.broken = Some(broken(
+                        None,
+                        format!(
+                            "chain head {head} ({events} events) does not match the file ({count} events)"
+                        ),
+                    ));
+                    return Ok(report);
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(report)
+    }
+
+    /// Compresses every daily file older than `day` into its history file.
+    pub fn rotate_before(&self, day: &str) -> Result<Vec<String>> {
+        let mut rotated = Vec::new();
+        for old_day in self.daily_days()? {
+            if old_day.as_str() >= day {
+                continue;
+            }
+            let source = self.daily_path(&old_day);
+            let target = self.history_path(&old_day);
+            let tmp = target.with_extension("zst.tmp");
+            let Some(input) = lock_live(&source, false)? else {
+                // Another process rotated the day in the meantime.
+                continue;
+            };
+            // Zstd frames decode as one stream, so lines written after an
+            // earlier rotation of the day become a frame after the old ones.
+            let output = if target.exists() {
+                fs::copy(&target, &tmp)
+                    .with_context(|| format!("Failed to copy {}", target.display()))?;
+                OpenOptions::new().append(true).open(&tmp)
+            } else {
+                File::create(&tmp)
+            }
+            .with_context(|| format!("Failed to create {}", tmp.display()))?;
+            zstd::stream::copy_encode(&input, &output, 0)
+                .and_then(|()| output.sync_all())
+                .with_context(|| format!("Failed to compress {}", source.display()))?;
+            fs::rename(&tmp, &target)
+                .with_context(|| format!("Failed to write {}", target.display()))?;
+            fs::remove_file(&source)
+                .with_context(|| format!("Failed to remove {}", source.display()))?;
+            rotated.push(old_day);
+        }
+        Ok(rotated)
+    }
+
+    /// Rewrites every audit file so each event carries the hash of the one
+    /// before it, then rebuilds `events` and `chain_heads` from the new
+    /// offsets. Upgrades trails written before events were chained; running
+    /// it on a chained trail leaves the files unchanged.
+    pub fn rechain(&self) -> Result<usize> {
+        let _lock = self.lock()?;
+        let mut days = self.daily_days()?;
+        days.extend(self.history_days()?);
+        days.sort();
+        days.dedup();
+
+        let mut rewritten = Vec::new();
+        let mut rows = Vec::new();
+        let mut heads = Vec::new();
+        let mut prev: Option<String> = None;
+        for day in &days {
+            let mut text = String::new();
+            self.open_day(day)?
+                .read_to_string(&mut text)
+                .with_context(|| format!("Failed to read audit file of {day}"))?;
+            let mut content = String::new();
+            let mut count = 0;
+            for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                let event: AuditEvent = serde_json::from_str(line)
+                    .with_context(|| format!("Malformed audit event in {day}: {line}"))?;
+                let event = AuditEvent { prev, ..event };
+                let line = serde_json::to_string(&event)?;
+                prev = Some(hex::encode(Sha256::digest(line.as_bytes())));
+                rows.push((day.clone(), content.len() as i64, event));
+                content.push_str(&line);
+                content.push('\n');
+                count += 1;
+            }
+            if let Some(head) = &prev {
+                heads.push((day.clone(), head.clone(), count));
+            }
+            rewritten.push((day.clone(), content));
+        }
+
+        // New files are staged next to the old ones and only swapped in once
+        // the index is rebuilt.
+        let mut staged = Vec::new();
+        for (day, content) in &rewritten {
+            let history = self.history_path(day);
+            let (target, plain) = match self.daily_path(day) {
+                path if path.exists() && !history.exists() => (path, true),
+                _ => (history, false),
+            };
+            let tmp = target.with_extension("rechain.tmp");
+            let bytes = if plain {
+                content.clone().into_bytes()
+            } else {
+                zstd::encode_all(content.as_bytes(), 0)?
+            };
+            fs::write(&tmp, bytes).with_context(|| format!("Failed to write {}", tmp.display()))?;
+            staged.push((tmp, target));
+        }
+
+        let tx = self.index.unchecked_transaction()?;
+        tx.execute_batch("DELETE FROM events; DELETE FROM chain_heads;")?;
+        for (day, offset, event) in &rows {
+            tx.execute(
+                "INSERT INTO events (day, offset, ts, event, task_id, run_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    day,
+                    offset,
+                    event.ts,
+                    event.event,
+                    event.task_id,
+                    event.run_id
+                ],
+            )?;
+        }
+        for (day, head, count) in &heads {
+            tx.execute(
+                "INSERT INTO chain_heads (day, head, events) VALUES (?1, ?2, ?3)",
+                params![day, head, count],
+            )?;
+        }
+        tx.commit().context("Failed to rebuild audit index")?;
+
+        for (tmp, target) in staged {
+            fs::rename(&tmp, &target)
+                .with_context(|| format!("Failed to replace {}", target.display()))?;
+        }
+        for day in &days {
+            let daily = self.daily_path(day);
+            if daily.exists() && self.history_path(day).exists() {
+                fs::remove_file(&daily)
+                    .with_context(|| format!("Failed to remove {}", daily.display()))?;
+            }
+        }
+        Ok(rows.len())
+    }
+
+    /// Reads the event stored at `offset` in the file of `day`, whether the
+    /// day is still plain or already rotated.
+    pub fn read_at(&self, day: &str, offset: u64) -> Result<AuditEvent> {
+        let daily = self.daily_path(day);
+        let reader: Box<dyn Read> = if daily.exists() && !self.history_path(day).exists() {
+            let mut file = File::open(&daily)
+                .with_context(|| format!("Failed to read {}", daily.display()))?;
+            file.seek(SeekFrom::Start(offset))?;
+            Box::new(file)
+        } else {
+            // Compressed streams cannot seek; skip to the offset instead.
+            let mut decoder = self.open_day(day)?;
+            let skipped = io::copy(&mut (&mut decoder).take(offset), &mut io::sink())?;
+            if skipped < offset {
+                bail!("Audit file of {day} ends before offset {offset}");
+            }
+            decoder
+        };
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line)?;
+        serde_json::from_str(line.trim_end())
+            .with_context(|| format!("Malformed audit event at {day}:{offset}"))
+    }
+
+    /// Events matching `query`, oldest first.
+    ///
+    /// Rows are selected through the index and each event is read from its
+    /// stored offset; a rotated day is decompressed once for all its events.
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        let (sql, values, exact) = index_query(query)?;
+        let mut statement = self
+            .index
+            .prepare(&sql)
+            .context("Failed to query audit index")?;
+        let matching = statement
+            .query_map(rusqlite::params_from_iter(&values), |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, i64>(1)? as u64,
+                    r.get::<_, String>(2)?,
+                ))
+            })
+            .context("Failed to query audit index")?
+            .filter(|row| row.as_ref().map_or(true, |(_, _, ts)| exact.contains(ts)))
+            .map(|row| row.map(|(day, offset, _)| (day, offset)));
+        // The limit applies after the exact time check, so it is not in SQL.
+        let mut rows = matching
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to query audit index")?;
+        rows.reverse();
+
+        let mut by_day: Vec<(String, Vec<u64>)> = Vec::new();
+        for (day, offset) in &rows {
+            match by_day.last_mut() {
+                Some((last, offsets)) if last == day => offsets.push(*offset),
+                _ => by_day.push((day.clone(), vec![*offset])),
+            }
+        }
+        let mut events = Vec::with_capacity(rows.len());
+        for (day, offsets) in by_day {
+            events.extend(self.read_day_at(&day, &offsets)?);
+        }
+        Ok(events)
+    }
+
+    /// Reads the events at `offsets` of one day in the given order.
+    fn read_day_at(&self, day: &str, offsets: &[u64]) -> Result<Vec<AuditEvent>> {
+        if self.daily_path(day).exists() && !self.history_path(day).exists() {
+            return offsets
+                .iter()
+                .map(|offset| self.read_at(day, *offset))
+                .collect();
+        }
+        let mut sorted = offsets.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let mut reader = BufReader::new(self.open_day(day)?);
+        let mut position = 0;
+        let mut found = std::collections::HashMap::new();
+        for offset in sorted {
+            let skipped = io::copy(&mut (&mut reader).take(offset - position), &mut io::sink())?;
+            if position + skipped < offset {
+                bail!("Audit file of {day} ends before offset {offset}");
+            }
+            let mut line = String::new();
+            position = offset + reader.read_line(&mut line)? as u64;
+            let event: AuditEvent = serde_json::from_str(line.trim_end())
+                .with_context(|| format!("Malformed audit event at {day}:{offset}"))?;
+            found.insert(offset, event);
+        }
+        Ok(offsets.iter().map(|offset| found[offset].clone()).collect())
+    }
+
+    /// The most recent event with the given name.
+    pub fn last_event(&self, event: &str) -> Result<Option<AuditEvent>> {
+        let location: Option<(String, i64)> = self
+            .index
+            .query_row(
+                "SELECT day, offset FROM events WHERE event = ?1 ORDER BY ts DESC, offset DESC LIMIT 1",
+                [event],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()
+            .context("Failed to query audit index")?;
+        location
+            .map(|(day, offset)| self.read_at(&day, offset as u64))
+            .transpose()
+    }
+
+    /// Opens the decompressed contents of a day's audit file: the history
+    /// file, followed by the plain file when events arrived after rotation.
+    pub fn open_day(&self, day: &str) -> Result<Box<dyn Read>> {
+        let daily = self.daily_path(day);
+        let history = self.history_path(day);
+        let plain = match File::open(&daily) {
+            Ok(file) => Some(file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to read {}", daily.display()));
+            }
+        };
+        if !history.exists()
+            && let Some(file) = plain
+        {
+            return Ok(Box::new(file));
+        }
+        let file = File::open(&history)
+            .with_context(|| format!("No audit file for {day} at {}", history.display()))?;
+        let decoder = zstd::stream::read::Decoder::new(file)?;
+        match plain {
+            Some(file) => Ok(Box::new(decoder.chain(file))),
+            None => Ok(Box::new(decoder)),
+        }
+    }
+
+    /// Uncompressed size of a day's history file, 0 when it was not rotated.
+    fn history_len(&self, day: &str) -> Result<u64> {
+        let history = self.history_path(day);
+        if !history.exists() {
+            return Ok(0);
+        }
+        let file = File::open(&history)
+            .with_context(|| format!("Failed to read {}", history.display()))?;
+        io::copy(
+            &mut zstd::stream::read::Decoder::new(file)?,
+            &mut io::sink(),
+        )
+        .with_context(|| format!("Failed to decompress {}", history.display()))
+    }
+
+    /// Takes the lock shared by every writer of this workspace's trail; it
+    /// covers reading the chain head through recording the new one.
+    fn lock(&self) -> Result<File> {
+        if let Some(parent) = self.lock_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.lock_path)
+            .with_context(|| format!("Failed to open audit lock {}", self.lock_path.display()))?;
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", self.lock_path.display()))?;
+        Ok(file)
+    }
+
+    fn daily_path(&self, day: &str) -> PathBuf {
+        self.dir
+            .join("runs")
+            .join(format!("{day}.{DAILY_EXTENSION}"))
+    }
+
+    fn history_path(&self, day: &str) -> PathBuf {
+        self.dir
+            .join(format!("{HISTORY_PREFIX}{day}{HISTORY_SUFFIX}"))
+    }
+
+    /// Days that were rotated into history files, oldest first.
+    fn history_days(&self) -> Result<Vec<String>> {
+        let mut days: Vec<String> = fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to list {}", self.dir.display()))?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let day = name
+                    .strip_prefix(HISTORY_PREFIX)?
+                    .strip_suffix(HISTORY_SUFFIX)?;
+                Some(day.to_string())
+            })
+            .collect();
+        days.sort();
+        Ok(days)
+    }
+
+    /// Days that still have a plain daily file, oldest first.
+    fn daily_days(&self) -> Result<Vec<String>> {
+        let dir = self.dir.join("runs");
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to list {}", dir.display()));
+            }
+        };
+        let mut days: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let path = entry.path();
+                (path.extension()? == DAILY_EXTENSION)
+                    .then(|| path.file_stem()?.to_str().map(str::to_string))
+                    .flatten()
+            })
+            .collect();
+        days.sort();
+        Ok(days)
+    }
+}
+
+/// Instants a query bound falls between, checked against each row's `ts`.
+#[derive(Debug, Default)]
+struct ExactBounds {
+    since: Option<OffsetDateTime>,
+    until: Option<OffsetDateTime>,
+}
+
+impl ExactBounds {
+    fn contains(&self, ts: &str) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        OffsetDateTime::parse(ts, &Rfc3339).is_ok_and(|ts| {
+            self.since.is_none_or(|since| ts >= since) && self.until.is_none_or(|until| ts <= until)
+        })
+    }
+}
+
+/// SQL selecting the index rows of `query`, newest first.
+///
+/// Time bounds stay plain `ts` comparisons so `idx_events_ts` is searched.
+/// `ts` is UTC RFC 3339 text whose fraction varies in length, so a bound
+/// with a time is widened to whole seconds here and checked exactly with
+/// the returned [`ExactBounds`].
+fn index_query(query: &AuditQuery) -> Result<(String, Vec<String>, ExactBounds)> {
+    let mut sql = String::from("SELECT day, offset, ts FROM events WHERE 1 = 1");
+    let mut values: Vec<String> = Vec::new();
+    let mut filter = |clause: &str, value: String| {
+        values.push(value);
+        sql.push_str(&format!(" AND {clause} ?{}", values.len()));
+    };
+    for (clause, value) in [
+        ("event =", &query.event),
+        ("task_id =", &query.task_id),
+        ("run_id =", &query.run_id),
+    ] {
+        if let Some(value) = value {
+            filter(clause, value.clone());
+        }
+    }
+
+    let mut exact = ExactBounds::default();
+    if let Some(since) = query.since.as_deref() {
+        match parse_bound(since)? {
+            Bound::Day(day) => filter("ts >=", format_day(day)?),
+            Bound::At(at) => {
+                filter("ts >=", format_second(at)?);
+                exact.since = Some(at);
+            }
+        }
+    }
+    if let Some(until) = query.until.as_deref() {
+        match parse_bound(until)? {
+            Bound::Day(day) => {
+                let next = day
+                    .next_day()
+                    .with_context(|| format!("No day follows {until}"))?;
+                filter("ts <", format_day(next)?);
+            }
+            Bound::At(at) => {
+                filter("ts <", format_second(at + Duration::SECOND)?);
+                exact.until = Some(at);
+            }
+        }
+    }
+    sql.push_str(" ORDER BY ts DESC, day DESC, offset DESC");
+    Ok((sql, values, exact))
+}
+
+enum Bound {
+    Day(Date),
+    At(OffsetDateTime),
+}
+
+fn parse_bound(value: &str) -> Result<Bound> {
+    if let Ok(day) = Date::parse(value, format_description!("[year]-[month]-[day]")) {
+        return Ok(Bound::Day(day));
+    }
+    let at = OffsetDateTime::parse(value, &Rfc3339)
+        .with_context(|| format!("Invalid time bound '{value}'"))?;
+    Ok(Bound::At(at.to_offset(UtcOffset::UTC)))
+}
+
+fn format_day(day: Date) -> Result<String> {
+    Ok(day.format(format_description!("[year]-[month]-[day]"))?)
+}
+
+/// The UTC second of `at` as a `ts` prefix: it sorts before every
+/// timestamp within that second.
+fn format_second(at: OffsetDateTime) -> Result<String> {
+    Ok(at.to_offset(UtcOffset::UTC).format(format_description!(
+        "[year]-[month]-[day]T[hour]:[minute]:[second]"
+    ))?)
+}
+
+/// Opens and locks the daily file at `path`, retrying until the locked file
+/// is still the one at `path`: rotation may remove it while we wait for the
+/// lock. Without `create`, returns `None` once the file is gone.
+fn lock_live(path: &Path, create: bool) -> Result<Option<File>> {
+    loop {
+        let file = match OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(create)
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(err) if !create && err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to open audit file {}", path.display()));
+            }
+        };
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock audit file {}", path.display()))?;
+        if is_same_file(&file, path) {
+            return Ok(Some(file));
+        }
+        let _ = FileExt::unlock(&file);
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(opened), Ok(current)) => opened.dev() == current.dev() && opened.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> bool {
+    // Open files cannot be replaced here, only removed.
+    path.exists()
+}
+
+/// Records an event if the workspace has an audit index at the current
+/// schema version.
+///
+/// Auditing never fails the command that triggered it; problems are
+/// reported as warnings.
+pub fn emit(workspace: &Workspace, event: AuditEvent) {
+    if !workspace.audit_index_path().exists() || migrations::schema_is_outdated(workspace) {
+        return;
+    }
+    if let Err(err) = AuditLog::open(workspace).and_then(|log| log.append(&event)) {
+        eprintln!(
+            "[warn] Failed to record audit event '{}': {err:#}",
+            event.event
+        );
+    }
+}
+
+/// Records `config.changed` when the configuration file differs from the
+/// version seen by the previous such event.
+pub fn note_config(workspace: &Workspace, config_path: &Path) {
+    if !workspace.audit_index_path().exists() || migrations::schema_is_outdated(workspace) {
+        return;
+    }
+    let Ok(contents) = fs::read(config_path) else {
+        return;
+    };
+    let sha256 = hex::encode(Sha256::digest(&contents));
+    let result = AuditLog::open(workspace).and_then(|log| {
+        let previous = log
+            .last_event(EVENT_CONFIG_CHANGED)?
+            .and_then(|event| event.data["sha256"].as_str().map(str::to_string));
+        if previous.as_deref() == Some(sha256.as_str()) {
+            return Ok(());
+        }
+        log.append(&AuditEvent::new(EVENT_CONFIG_CHANGED).data(json!({
+            "path": config_path,
+            "sha256": sha256,
+            "previous": previous,
+        })))
+    });
+    if let Err(err) = result {
+        eprintln!("[warn] Failed to record audit event '{EVENT_CONFIG_CHANGED}': {err:#}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn audit_log(root: &Path) -> AuditLog {
+        let workspace = Workspace::new(root);
+        let path = workspace.audit_index_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(crate::commands::init::AUDIT_INDEX_SCHEMA_SQL)
+            .unwrap();
+        AuditLog::open(&workspace).unwrap()
+    }
+
+    fn event_at(ts: &str, event: &str) -> AuditEvent {
+        AuditEvent {
+            ts: ts.to_string(),
+            ..AuditEvent::new(event)
+        }
+    }
+
+    fn indexed(log: &AuditLog) -> Vec<(String, i64, String)> {
+        log.index
+            .prepare("SELECT day, offset, event FROM events ORDER BY ts")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn appends_ndjson_lines_and_indexes_offsets() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        let started = event_at("2026-10-01T09:00:00Z", EVENT_RUN_STARTED)
+            .task("A-1")
+            .run("run_1")
+            .data(json!({ "profile": "fast" }));
+        log.append(&started).unwrap();
+        log.append(&event_at("2026-10-01T09:05:00Z", EVENT_RUN_FINISHED).run("run_1"))
+            .unwrap();
+
+        let text = fs::read_to_string(log.daily_path("2026-10-01")).unwrap();
+        let first_len = text.lines().next().unwrap().len() as i64 + 1;
+        assert_eq!(
+            indexed(&log),
+            [
+                ("2026-10-01".into(), 0, EVENT_RUN_STARTED.into()),
+                ("2026-10-01".into(), first_len, EVENT_RUN_FINISHED.into()),
+            ]
+        );
+        assert_eq!(log.read_at("2026-10-01", 0).unwrap(), started);
+    }
+
+    #[test]
+    fn rotates_previous_days_into_compressed_history() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        log.append(&event_at("2026-10-01T09:00:00Z", EVENT_INIT))
+            .unwrap();
+        log.append(&event_at("2026-10-01T10:00:00Z", EVENT_LOCK_ACQUIRED))
+            .unwrap();
+        log.append(&event_at("2026-10-02T08:00:00Z", EVENT_LOCK_RELEASED))
+            .unwrap();
+
+        assert!(!log.daily_path("2026-10-01").exists());
+        let history = log.history_path("2026-10-01");
+        assert!(history.exists());
+        assert!(log.daily_path("2026-10-02").exists());
+
+        let (day, offset, _) = indexed(&log)[1].clone();
+        let event = log.read_at(&day, offset as u64).unwrap();
+        assert_eq!(event.event, EVENT_LOCK_ACQUIRED);
+        assert!(log.rotate_before("2026-10-02").unwrap().is_empty());
+    }
+
+    #[test]
+    fn late_events_join_the_newest_day_and_keep_the_chain() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        for (ts, event) in [
+            ("2026-10-01T09:00:00Z", EVENT_INIT),
+            ("2026-10-02T08:00:00Z", EVENT_LOCK_ACQUIRED),
+            ("2026-10-01T23:59:59Z", EVENT_LOCK_RELEASED),
+            ("2026-10-02T09:00:00Z", EVENT_INIT),
+        ] {
+            log.append(&event_at(ts, event)).unwrap();
+        }
+        assert!(!log.daily_path("2026-10-01").exists());
+        let days: Vec<String> = indexed(&log).into_iter().map(|(day, ..)| day).collect();
+        assert_eq!(
+            days,
+            ["2026-10-01", "2026-10-02", "2026-10-02", "2026-10-02"]
+        );
+        let report = log.verify().unwrap();
+        assert_eq!((report.events, report.broken), (4, None));
+    }
+
+    #[test]
+    fn rotation_appends_to_an_existing_history() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        log.append(&event_at("2026-10-01T09:00:00Z", EVENT_INIT))
+            .unwrap();
+        log.append(&event_at("2026-10-02T08:00:00Z", EVENT_INIT))
+            .unwrap();
+        let mut rotated = String::new();
+        log.open_day("2026-10-01")
+            .unwrap()
+            .read_to_string(&mut rotated)
+            .unwrap();
+        // A writer that lost the race with rotation leaves a new plain file
+        // next to the history of its day.
+        let late = event_at("2026-10-01T23:59:59Z", EVENT_LOCK_RELEASED);
+        fs::write(
+            log.daily_path("2026-10-01"),
+            format!("{}\n", serde_json::to_string(&late).unwrap()),
+        )
+        .unwrap();
+        let first_len = rotated.len() as u64;
+        assert_eq!(log.read_at("2026-10-01", first_len).unwrap(), late);
+
+        assert_eq!(log.rotate_before("2026-10-02").unwrap(), ["2026-10-01"]);
+        assert!(!log.daily_path("2026-10-01").exists());
+        let mut text = String::new();
+        log.open_day("2026-10-01")
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert_eq!(log.read_at("2026-10-01", 0).unwrap().event, EVENT_INIT);
+        assert_eq!(log.read_at("2026-10-01", first_len).unwrap(), late);
+    }
+
+    #[test]
+    fn query_reads_matching_events_from_plain_and_compressed_days() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        for (ts, event, task) in [
+            ("2026-09-30T09:00:00Z", EVENT_RUN_FINISHED, "A-1"),
+            ("2026-10-01T09:00:00Z", EVENT_RUN_STARTED, "A-1"),
+            ("2026-10-01T09:05:00Z", EVENT_RUN_FINISHED, "A-1"),
+            ("2026-10-01T10:00:00Z", EVENT_RUN_FINISHED, "B-1"),
+            ("2026-10-02T08:00:00Z", EVENT_RUN_FINISHED, "A-1"),
+        ] {
+            log.append(&event_at(ts, event).task(task)).unwrap();
+        }
+        let times = |query: AuditQuery| -> Vec<String> {
+            log.query(&query)
+                .unwrap()
+                .into_iter()
+                .map(|event| event.ts)
+                .collect()
+        };
+
+        let finished = AuditQuery {
+            event: Some(EVENT_RUN_FINISHED.into()),
+            task_id: Some("A-1".into()),
+            ..AuditQuery::default()
+        };
+        assert_eq!(
+            times(finished.clone()),
+            [
+                "2026-09-30T09:00:00Z",
+                "2026-10-01T09:05:00Z",
+                "2026-10-02T08:00:00Z"
+            ]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                since: Some("2026-10-01".into()),
+                until: Some("2026-10-01".into()),
+                ..finished.clone()
+            }),
+            ["2026-10-01T09:05:00Z"]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                since: Some("2026-10-01T04:05:00-05:00".into()),
+                until: Some("2026-10-01T09:05:00.5Z".into()),
+                ..finished.clone()
+            }),
+            ["2026-10-01T09:05:00Z"]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                limit: Some(2),
+                ..AuditQuery::default()
+            }),
+            ["2026-10-01T10:00:00Z", "2026-10-02T08:00:00Z"]
+        );
+    }
+
+    #[test]
+    fn query_compares_fractional_seconds_exactly() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        for ts in [
+            "2026-10-01T09:00:00Z",
+            "2026-10-01T09:00:00.25Z",
+            "2026-10-01T09:00:00.7Z",
+            "2026-10-01T09:00:01Z",
+        ] {
+            log.append(&event_at(ts, EVENT_RUN_FINISHED)).unwrap();
+        }
+        let times = |since: &str, until: &str| -> Vec<String> {
+            log.query(&AuditQuery {
+                since: Some(since.into()),
+                until: Some(until.into()),
+                ..AuditQuery::default()
+            })
+            .unwrap()
+            .into_iter()
+            .map(|event| event.ts)
+            .collect()
+        };
+
+        assert_eq!(
+            times("2026-10-01T09:00:00.1Z", "2026-10-01T09:00:00.5Z"),
+            ["2026-10-01T09:00:00.25Z"]
+        );
+        assert_eq!(
+            times("2026-10-01T11:00:00.25+02:00", "2026-10-01T09:00:01Z"),
+            [
+                "2026-10-01T09:00:00.25Z",
+                "2026-10-01T09:00:00.7Z",
+                "2026-10-01T09:00:01Z"
+            ]
+        );
+        assert!(
+            log.query(&AuditQuery {
+                since: Some("yesterday".into()),
+                ..AuditQuery::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn query_time_bounds_search_the_ts_index() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        for (since, until) in [
+            ("2026-10-01", "2026-10-02"),
+            ("2026-10-01T09:00:00.5+02:00", "2026-10-02T09:00:00Z"),
+        ] {
+            let (sql, values, _) = index_query(&AuditQuery {
+                since: Some(since.into()),
+                until: Some(until.into()),
+                ..AuditQuery::default()
+            })
+            .unwrap();
+            let plan: Vec<String> = log
+                .index
+                .prepare(&format!("EXPLAIN QUERY PLAN {sql}"))
+                .unwrap()
+                .query_map(rusqlite::params_from_iter(&values), |r| r.get(3))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap();
+            assert!(
+                plan.iter()
+                    .any(|step| step.contains("SEARCH") && step.contains("idx_events_ts")),
+                "{plan:?}"
+            );
+        }
+        let (_, values, _) = index_query(&AuditQuery {
+            since: Some("2026-10-01T09:00:00.5+02:00".into()),
+            until: Some("2026-10-01".into()),
+            ..AuditQuery::default()
+        })
+        .unwrap();
+        assert_eq!(values, ["2026-10-01T07:00:00", "2026-10-02"]);
+    }
+
+    #[test]
+    fn verify_walks_the_chain_across_rotated_days() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        for ts in [
+            "2026-10-01T09:00:00Z",
+            "2026-10-01T10:00:00Z",
+            "2026-10-02T08:00:00Z",
+            "2026-10-02T09:00:00Z",
+        ] {
+            log.append(&event_at(ts, EVENT_INIT)).unwrap();
+        }
+        assert!(log.history_path("2026-10-01").exists());
+        let report = log.verify().unwrap();
+        assert_eq!((report.days, report.events), (2, 4));
+        assert_eq!(report.broken, None);
+
+        let first = log.read_at("2026-10-01", 0).unwrap();
+        assert_eq!(first.prev, None);
+        let next_day = log.read_at("2026-10-02", 0).unwrap();
+        let head: String = log
+            .index
+            .query_row(
+                "SELECT head FROM chain_heads WHERE day = '2026-10-01'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(next_day.prev, Some(head));
+    }
+
+    #[test]
+    fn verify_reports_the_first_broken_link() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        for ts in [
+            "2026-10-02T08:00:00Z",
+            "2026-10-02T09:00:00Z",
+            "2026-10-02T10:00:00Z",
+        ] {
+            log.append(&event_at(ts, EVENT_INIT)).unwrap();
+        }
+        let path = log.daily_path("2026-10-02");
+        let original = fs::read_to_string(&path).unwrap();
+        let second = original.lines().next().unwrap().len() as u64 + 1;
+
+        fs::write(&path, original.replacen("init", "edit", 1)).unwrap();
+        let broken = log.verify().unwrap().broken.unwrap();
+        assert_eq!(broken.day, "2026-10-02");
+        assert_eq!(broken.offset, Some(second));
+
+        let truncated: Vec<&str> = original.lines().take(2).collect();
+        fs::write(&path, format!("{}\n", truncated.join("\n"))).unwrap();
+        let broken = log.verify().unwrap().broken.unwrap();
+        assert_eq!(broken.offset, None);
+        assert!(broken.reason.contains("does not match"), "{broken}");
+    }
+
+    #[test]
+    fn rechain_upgrades_unchained_trails() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        for ts in [
+            "2026-10-01T09:00:00Z",
+            "2026-10-02T08:00:00Z",
+            "2026-10-02T09:00:00Z",
+        ] {
+            log.append(&event_at(ts, EVENT_INIT)).unwrap();
+        }
+        // Simulate a trail written before chaining: no `prev`, no heads.
+        let daily = log.daily_path("2026-10-02");
+        let stripped: String = fs::read_to_string(&daily)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let mut event: AuditEvent = serde_json::from_str(line).unwrap();
+                event.prev = None;
+                format!("{}\n", serde_json::to_string(&event).unwrap())
+            })
+            .collect();
+        fs::write(&daily, stripped).unwrap();
+        log.index.execute("DELETE FROM chain_heads", []).unwrap();
+        assert!(log.verify().unwrap().broken.is_some());
+
+        assert_eq!(log.rechain().unwrap(), 3);
+        let report = log.verify().unwrap();
+        assert_eq!((report.events, report.broken), (3, None));
+        let (day, offset, _) = indexed(&log)[2].clone();
+        assert_eq!(
+            log.read_at(&day, offset as u64).unwrap().ts,
+            "2026-10-02T09:00:00Z"
+        );
+
+        let before = fs::read(&daily).unwrap();
+        log.rechain().unwrap();
+        assert_eq!(fs::read(&daily).unwrap(), before);
+    }
+
+    #[test]
+    fn config_changes_are_recorded_once_per_version() {
+        let temp = tempdir().unwrap();
+        let log = audit_log(temp.path());
+        let workspace = Workspace::new(temp.path());
+        let config = temp.path().join("alisa.toml");
+
+        fs::write(&config, "[roles]\n").unwrap();
+        note_config(&workspace, &config);
+        note_config(&workspace, &config);
+        fs::write(&config, "[roles]\nplan = \"x\"\n").unwrap();
+        note_config(&workspace, &config);
+
+        let events = indexed(&log);
+        assert_eq!(events.len(), 2);
+        let last = log.last_event(EVENT_CONFIG_CHANGED).unwrap().unwrap();
+        assert!(last.data["previous"].is_string());
+    }
+
+    #[test]
+    fn emit_skips_workspaces_without_audit_index() {
+        let temp = tempdir().unwrap();
+        let workspace = Workspace::new(temp.path());
+        emit(&workspace, AuditEvent::new(EVENT_INIT));
+        assert!(!temp.path().join(".alisa/audit/runs").exists());
+    }
+}
@@ -0,0 +1,144 @@
+use clap::{Args, Subcommand};
+use thiserror::Error;
+
+use super::runs::parse_date_bound;
+use crate::{
+    audit::{AuditEvent, AuditLog, AuditQuery, ChainBreak},
+    workspace::Workspace,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct AuditCliArgs {
+    #[command(subcommand)]
+    pub command: AuditCommand,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum AuditCommand {
+    /// Print recorded audit events, oldest first
+    Query(AuditQueryArgs),
+    /// Check the hash chain of the audit trail and report the first broken link
+    Verify,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct AuditQueryArgs {
+    /// Only events of this task
+    #[arg(long)]
+    pub task: Option<String>,
+
+    /// Only events with this name (e.g. `run.finished`)
+    #[arg(long)]
+    pub event: Option<String>,
+
+    /// Only events of this run
+    #[arg(long)]
+    pub run: Option<String>,
+
+    /// Only events recorded on or after this date (`YYYY-MM-DD` or RFC 3339)
+    #[arg(long, value_parser = parse_date_bound)]
+    pub since: Option<String>,
+
+    /// Only events recorded on or before this date (`YYYY-MM-DD` or RFC 3339)
+    #[arg(long, value_parser = parse_date_bound)]
+    pub until: Option<String>,
+
+    /// Keep only the most recent events
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Print the events as NDJSON, one object per line
+    #[arg(long)]
+    pub ndjson: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("workspace is not initialized at {path}; run `alisa init` first")]
+    WorkspaceMissing { path: String },
+    #[error("audit chain broken at {0}")]
+    ChainBroken(ChainBreak),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub fn run(args: &AuditCliArgs) -> Result<(), AuditError> {
+    let workspace = Workspace::detect_from_cwd()?;
+    if !workspace.audit_index_path().exists() {
+        return Err(AuditError::WorkspaceMissing {
+            path: workspace.workspace_root().display().to_string(),
+        });
+    }
+    let log = AuditLog::open(&workspace)?;
+
+    match &args.command {
+        AuditCommand::Query(query) => {
+            let events = log.query(&AuditQuery {
+                event: query.event.clone(),
+                task_id: query.task.clone(),
+                run_id: query.run.clone(),
+                since: query.since.clone(),
+                until: query.until.clone(),
+                limit: query.limit,
+            })?;
+            if query.ndjson {
+                for event in &events {
+                    println!(
+                        "{}",
+                        serde_json::to_string(event).map_err(anyhow::Error::from)?
+                    );
+                }
+            } else {
+                print_events(&events);
+            }
+            Ok(())
+        }
+        AuditCommand::Verify => {
+            let report = log.verify()?;
+            if let Some(broken) = report.broken {
+                return Err(AuditError::ChainBroken(broken));
+            }
+            println!(
+                "[ok] Audit chain intact: {} event(s) across {} day(s).",
+                report.events, report.days
+            );
+            Ok(())
+        }
+    }
+}
+
+fn print_events(events: &[AuditEvent]) {
+    if events.is_empty() {
+        println!("No matching events.");
+        return;
+    }
+    let width = |column: fn(&AuditEvent) -> &str, header: &str| {
+        events
+            .iter()
+            .map(|event| column(event).len())
+            .max()
+            .unwrap_or(0)
+            .max(header.len())
+    };
+    let event_width = width(|event| &event.event, "EVENT");
+    let task_width = width(|event| event.task_id.as_deref().unwrap_or("-"), "TASK");
+    let run_width = width(|event| event.run_id.as_deref().unwrap_or("-"), "RUN");
+    println!(
+        "{:<20}  {:<event_width$}  {:<task_width$}  {:<run_width$}  DATA",
+        "TIME", "EVENT", "TASK", "RUN"
+    );
+    for event in events {
+        let data = if event.data.is_null() {
+            String::new()
+        } else {
+            event.data.to_string()
+        };
+        println!(
+            "{:<20}  {:<event_width$}  {:<task_width$}  {:<run_width$}  {data}",
+            event.ts,
+            event.event,
+            event.task_id.as_deref().unwrap_or("-"),
+            event.run_id.as_deref().unwrap_or("-"),
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use clap::{Args, Subcommand};
+use thiserror::Error;
+
+use super::config_files;
+use crate::{
+    config::{
+        ConditionValues, Config, ConfigError, Diagnostic, Limits, PromptInput, ReviewConsensus,
+        ReviewStageKind, Roles, RoutingConditions, Severity, diagnose, env_overrides,
+    },
+    workspace::Workspace,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct ConfigCliArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective configuration after merging all layers
+    Show {
+        /// Name the file or environment variable that set each value
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Check every layer and report problems with their location
+    Validate,
+    /// Describe runners, profiles, routing rules and review pipelines
+    Explain,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigCommandError {
+    #[error("invalid configuration: {count} problem(s) found")]
+    Invalid { count: usize },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub fn run(args: &ConfigCliArgs) -> Result<(), ConfigCommandError> {
+    let workspace = Workspace::detect_from_cwd()?;
+    let files = config_files(&workspace)?;
+
+    match &args.command {
+        ConfigCommand::Show { origin } => {
+            let layered = files.load()?;
+            print!("{}", layered.render(*origin));
+            Ok(())
+        }
+        ConfigCommand::Validate => {
+            let diagnostics = diagnose(&files, env_overrides()?)?;
+            for diagnostic in &diagnostics {
+                eprint!("{}", render_diagnostic(diagnostic));
+            }
+            let errors = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .count();
+            if errors > 0 {
+                return Err(ConfigCommandError::Invalid { count: errors });
+            }
+            let names: Vec<_> = files
+                .read()?
+                .into_iter()
+                .map(|source| source.name)
+                .collect();
+            let mut summary = format!("[ok] Configuration is valid ({})", names.join(", "));
+            if !diagnostics.is_empty() {
+                summary.push_str(&format!(" with {} warning(s)", diagnostics.len()));
+            }
+            println!("{summary}");
+            Ok(())
+        }
+        ConfigCommand::Explain => {
+            print!("{}", explain(&files.load()?.config));
+            Ok(())
+        }
+    }
+}
+
+/// Formats a diagnostic the way compilers do: message, location, the
+/// offending source line with the value underlined, and the key path.
+fn render_diagnostic(diagnostic: &Diagnostic) -> String {
+    let label = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let mut out = format!("{label}: {}\n", diagnostic.message);
+    let gutter = diagnostic
+        .span
+        .as_ref()
+        .map_or(1, |span| span.line.to_string().len());
+    let pad = " ".repeat(gutter);
+    match (&diagnostic.origin, &diagnostic.span) {
+        (Some(origin), Some(span)) => {
+            out.push_str(&format!(
+                "{pad}--> {origin}:{}:{}\n",
+                span.line, span.column
+            ));
+            out.push_str(&format!("{pad} |\n"));
+            out.push_str(&format!("{} | {}\n", span.line, span.text));
+            out.push_str(&format!(
+                "{pad} | {}{}\n",
+                " ".repeat(span.column - 1),
+                "^".repeat(span.width)
+            ));
+        }
+        (Some(origin), None) => out.push_str(&format!("{pad}--> {origin}\n")),
+        (None, _) => {}
+    }
+    if let Some(key) = &diagnostic.key {
+        out.push_str(&format!("{pad} = key: {key}\n"));
+    }
+    out.push('\n');
+    out
+}
+
+/// Plain-language summary of the configuration, section by section.
+fn explain(config: &Config) -> String {
+    let mut out = String::new();
+
+    out.push_str("Runners\n");
+    let mut runners: Vec<_> = config.runners.iter().collect();
+    runners.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, runner) in runners {
+        let command = [runner.cmd.as_str()]
+            .into_iter()
+            .chain(runner.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut facts = vec![format!("runs `{command}`")];
+        if let Some(model) = &runner.model {
+            facts.push(format!("model {model}"));
+        }
+        facts.push(match runner.prompt_input {
+            PromptInput::Stdin => "prompt on stdin".to_string(),
+            PromptInput::File => "prompt in a file".to_string(),
+        });
+        if let Some(dir) = &runner.prompt_dir {
+            facts.push(format!("templates from {dir}"));
+        }
+        if let Some(timeout) = runner.timeout_ms {
+            facts.push(format!("killed after {timeout} ms"));
+        }
+        if !runner.env.is_empty() {
+            facts.push(format!("{} extra env var(s)", runner.env.len()));
+        }
+        out.push_str(&format!("  {name}: {}\n", facts.join(", ")));
+    }
+
+    out.push_str("\nRoles\n");
+    out.push_str(&format!("  {}\n", describe_roles(&config.roles)));
+    if let Some(limits) = describe_limits(&config.limits) {
+        out.push_str(&format!("  limits: {limits}\n"));
+    }
+
+    if !config.profiles.is_empty() {
+        out.push_str("\nProfiles\n");
+        for (name, profile) in &config.profiles {
+            let mut facts = vec![describe_roles(&profile.roles)];
+            if let Some(limits) = describe_limits(&profile.limits) {
+                facts.push(format!("limits: {limits}"));
+            }
+            out.push_str(&format!("  {name}: {}\n", facts.join("; ")));
+        }
+    }
+
+    if !config.routing.is_empty() {
+        out.push_str(
+            "\nRouting (the matching rule with the highest priority, then the most specific, wins)\n",
+        );
+        for (index, rule) in config.routing.iter().enumerate() {
+            let mut line = format!("  #{index}");
+            if let Some(priority) = rule.priority {
+                line.push_str(&format!(" (priority {priority})"));
+            }
+            line.push_str(&format!(
+                ": {} runs on {}",
+                rule.role.as_str(),
+                rule.use_runner
+            ));
+            match describe_conditions(&rule.when) {
+                Some(conditions) => line.push_str(&format!(" when {conditions}")),
+                None => line.push_str(" for every task"),
+            }
+            if let Some(profile) = &rule.profile {
+                line.push_str(&format!(", then switches to profile {profile}"));
+            }
+            out.push_str(&format!("{line}\n"));
+        }
+    }
+
+    if !config.review.pipelines.is_empty() {
+        out.push_str("\nReview pipelines");
+        if let Some(default) = &config.review.default_pipeline {
+            out.push_str(&format!(" (default: {default})"));
+        }
+        out.push('\n');
+        for (name, pipeline) in &config.review.pipelines {
+            let consensus = match pipeline.consensus() {
+                ReviewConsensus::Gate => "passes when every stage passes".to_string(),
+                ReviewConsensus::Majority => {
+                    "passes when more than half of the stages pass".to_string()
+                }
+                ReviewConsensus::Weighted => {
+                    let mut weights: Vec<_> = pipeline.weights.iter().collect();
+                    weights.sort_by(|a, b| a.0.cmp(b.0));
+                    let weights: Vec<_> = weights
+                        .into_iter()
+                        .map(|(stage, weight)| format!("{stage} {weight}"))
+                        .collect();
+                    format!(
+                        "passes when passing stages hold {}% of the weight ({})",
+                        pipeline.threshold.unwrap_or(0.5) * 100.0,
+                        weights.join(", ")
+                    )
+                }
+            };
+            out.push_str(&format!(
+                "  {name}: {}; {consensus}",
+                pipeline.stages.join(" → ")
+            ));
+            if !pipeline.fail_on.is_empty() {
+                out.push_str(&format!(
+                    "; fails whenever {} fails",
+                    pipeline.fail_on.join(" or ")
+                ));
+            }
+            out.push('\n');
+        }
+    }
+
+    if !config.review.stages.is_empty() {
+        out.push_str("\nReview stages\n");
+        for (name, stage) in &config.review.stages {
+            let mut line = match stage.kind {
+                ReviewStageKind::Exec => format!(
+                    "runs `{}` and passes on exit code 0",
+                    stage.cmd.as_deref().unwrap_or_default().join(" ")
+                ),
+                ReviewStageKind::Llm => format!(
+                    "asks {} for a verdict",
+                    stage.runner.as_deref().unwrap_or("?")
+                ),
+                ReviewStageKind::Arbiter => format!(
+                    "asks {} to weigh the earlier verdicts",
+                    stage.runner.as_deref().unwrap_or("?")
+                ),
+            };
+            if let Some(prompt) = &stage.prompt {
+                line.push_str(&format!(", prompt {prompt}"));
+            }
+            if let Some(schema) = &stage.schema {
+                line.push_str(&format!(", verdict checked against {schema}"));
+            }
+            if stage.strict {
+                line.push_str(", strict");
+            }
+            out.push_str(&format!("  {name}: {line}\n"));
+        }
+    }
+
+    out
+}
+
+fn describe_roles(roles: &Roles) -> String {
+    let entries: Vec<_> = roles
+        .configured_entries()
+        .map(|(role, runner)| format!("{} → {runner}", role.as_str()))
+        .collect();
+    if entries.is_empty() {
+        "no roles assigned".to_string()
+    } else {
+        entries.join(", ")
+    }
+}
+
+fn describe_limits(limits: &Limits) -> Option<String> {
+    let facts: Vec<_> = [
+        ("max_files", limits.max_files),
+        ("max_tokens", limits.max_tokens),
+        ("max_changed_lines", limits.max_changed_lines),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|value| format!("{name} {value}")))
+    .collect();
+    (!facts.is_empty()).then(|| facts.join(", "))
+}
+
+fn describe_conditions(when: &RoutingConditions) -> Option<String> {
+    let conditions = condition_phrases(when);
+    (!conditions.is_empty()).then(|| conditions.join(" and "))
+}
+
+fn condition_phrases(when: &RoutingConditions) -> Vec<String> {
+    let mut conditions = Vec::new();
+    if let Some(lang) = &when.lang {
+        conditions.push(format!("the language {}", one_of(lang, "is")));
+    }
+    if let Some(profile) = &when.profile {
+        conditions.push(format!("the profile {}", one_of(profile, "is")));
+    }
+    if let Some(task_id) = &when.task_id {
+        conditions.push(format!("the task id {}", one_of(task_id, "matches")));
+    }
+    if let Some(path) = &when.path {
+        conditions.push(format!("a task path {}", one_of(path, "matches")));
+    }
+    if let Some(tags) = &when.tags {
+        conditions.push(format!("the task is {}", one_of(tags, "tagged")));
+    }
+    if let Some(priority) = &when.priority {
+        conditions.push(format!("the priority {}", one_of(priority, "is")));
+    }
+    if let Some(status) = &when.status {
+        conditions.push(format!("the status {}", one_of(status, "is")));
+    }
+    if let Some(blocks) = &when.any {
+        let blocks: Vec<_> = blocks.iter().map(describe_block).collect();
+        conditions.push(format!("({})", blocks.join(" or ")));
+    }
+    if let Some(blocks) = &when.all {
+        let blocks: Vec<_> = blocks.iter().map(describe_block).collect();
+        conditions.push(format!("({})", blocks.join(" and ")));
+    }
+    if let Some(block) = &when.not {
+        conditions.push(format!("not {}", describe_block(block)));
+    }
+    conditions
+}
+
+/// A nested block, parenthesized when it has several conditions.
+fn describe_block(when: &RoutingConditions) -> String {
+    let conditions = condition_phrases(when);
+    match conditions.len() {
+        0 => "(always)".to_string(),
+        1 => conditions.join(""),
+        _ => format!("({})", conditions.join(" and ")),
+    }
+}
+
+fn one_of(values: &ConditionValues, verb: &str) -> String {
+    match values {
+        ConditionValues::One(value) => format!("{verb} {value}"),
+        ConditionValues::Any(values) => format!("{verb} one of {}", values.join(", ")),
+    }
+}
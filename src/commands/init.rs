@@ -8,6 +8,7 @@ use std::{
 
 use anyhow::Context;
 use clap::Args;
+use serde_json::json;
 use thiserror::Error;
 
 mod platform;
@@ -15,8 +16,13 @@ mod prompt;
 mod schema;
 mod validation;
 
+pub(crate) use prompt::ask_yes_no;
+#[cfg(test)]
+pub(crate) use schema::{AUDIT_INDEX_SCHEMA_SQL, RAG_INDEX_SCHEMA_SQL, REGISTRY_SCHEMA_SQL};
+
 use super::{LockPolicy, WorkspaceLockError, WorkspaceLockStatus, acquire_workspace_lock};
 use crate::{
+    audit::{self, AuditEvent},
     metadata::{
         self, MANIFEST_SCHEMA_VERSION, Manifest, default_project_toml, default_runtime_toml,
         default_session_state, to_pretty_json,
@@ -34,6 +40,10 @@ pub struct InitCliArgs {
     #[arg(long)]
     pub check: bool,
 
+    /// With --check, also verify the hash chain of the audit trail
+    #[arg(long, requires = "check")]
+    pub verify_audit: bool,
+
     /// Recreate auxiliary artifacts (indices, caches)
     #[arg(long)]
     pub force: bool,
@@ -97,7 +107,7 @@ pub fn run(args: &InitCliArgs) -> Result<(), InitError> {
     }
 
     let result = match mode {
-        InitMode::Check => validation::run_check(&workspace),
+        InitMode::Check => validation::run_check(&workspace, args.verify_audit),
         InitMode::Execute(opts) => execute(&workspace, opts),
     };
 
@@ -201,6 +211,16 @@ fn execute(workspace: &Workspace, opts: InitOptions) -> Result<(), InitError> {
     interruptible(|| schema::ensure_audit_index_database(workspace, &opts, &mut reporter))?;
     interruptible(|| schema::ensure_rag_index_database(workspace, &opts, &mut reporter))?;
 
+    if !opts.dry_run {
+        audit::emit(
+            workspace,
+            AuditEvent::new(audit::EVENT_INIT).data(json!({ "force": opts.force })),
+        );
+        if let Ok(config) = super::config_path(workspace) {
+            audit::note_config(workspace, &config);
+        }
+    }
+
     Ok(())
 }
 
@@ -448,7 +468,7 @@ where
 
 fn schema_mismatch_error(found: &str, expected: &str) -> InitError {
     InitError::SchemaMismatch(format!(
-        "Workspace schema version {found} is incompatible with {expected}"
+        "Workspace schema version {found} is incompatible with {expected}; run `alisa migrate` to upgrade it"
     ))
 }
 
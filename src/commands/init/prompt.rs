@@ -122,34 +122,44 @@ where
 }
 
 fn prompt_yes_no(question: &str) -> Result<bool, InitError> {
+    ask_yes_no(question, true, "Leaving artifact unchanged.")
+        .map_err(|err| InitError::Other(err.into()))
+}
+
+/// Asks a yes/no question on stdin, waiting at most `PROMPT_TIMEOUT`.
+///
+/// An empty answer picks `default`; a timeout or closed stdin answers "no" after
+/// printing `on_no_input`.
+pub(crate) fn ask_yes_no(question: &str, default: bool, on_no_input: &str) -> io::Result<bool> {
     let mut stdout = io::stdout();
 
     loop {
         print!("{question} ");
-        stdout.flush().map_err(|err| InitError::Other(err.into()))?;
+        stdout.flush()?;
 
-        if !platform::wait_for_stdin(PROMPT_TIMEOUT).map_err(|err| InitError::Other(err.into()))? {
-            eprintln!(
-                "No input received within {PROMPT_TIMEOUT_SECS} seconds. Leaving artifact unchanged."
-            );
+        if !platform::wait_for_stdin(PROMPT_TIMEOUT)? {
+            eprintln!("No input received within {PROMPT_TIMEOUT_SECS} seconds. {on_no_input}");
             return Ok(false);
         }
 
         let mut buffer = String::new();
-        let bytes = io::stdin()
-            .read_line(&mut buffer)
-            .map_err(|err| InitError::Other(err.into()))?;
+        let bytes = io::stdin().read_line(&mut buffer)?;
 
         if bytes == 0 {
-            eprintln!("No input received. Leaving artifact unchanged.");
+            eprintln!("No input received. {on_no_input}");
             return Ok(false);
         }
 
         match buffer.trim().to_lowercase().as_str() {
-            "" | "y" | "yes" => return Ok(true),
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
             "n" | "no" => return Ok(false),
             _ => {
-                eprintln!("Please answer Y or n.");
+                if default {
+                    eprintln!("Please answer Y or n.");
+                } else {
+                    eprintln!("Please answer y or N.");
+                }
             }
         }
     }
@@ -7,7 +7,7 @@ use crate::workspace::Workspace;
 
 use super::{InitError, InitOptions, InitReporter, prompt};
 
-pub(super) const REGISTRY_SCHEMA_SQL: &str = r#"
+pub(crate) const REGISTRY_SCHEMA_SQL: &str = r#"
 BEGIN;
 CREATE TABLE IF NOT EXISTS tasks (
     id TEXT PRIMARY KEY,
@@ -50,7 +50,7 @@ COMMIT;
 
 pub(super) const REGISTRY_TABLES: &[&str] = &["tasks", "runs", "artifacts"];
 
-pub(super) const AUDIT_INDEX_SCHEMA_SQL: &str = r#"
+pub(crate) const AUDIT_INDEX_SCHEMA_SQL: &str = r#"
 BEGIN;
 CREATE TABLE IF NOT EXISTS events (
     day TEXT NOT NULL,
@@ -64,12 +64,17 @@ CREATE TABLE IF NOT EXISTS events (
 CREATE INDEX IF NOT EXISTS idx_events_ts ON events(ts);
 CREATE INDEX IF NOT EXISTS idx_events_event ON events(event);
 CREATE INDEX IF NOT EXISTS idx_events_task ON events(task_id);
+CREATE TABLE IF NOT EXISTS chain_heads (
+    day TEXT PRIMARY KEY,
+    head TEXT NOT NULL,
+    events INTEGER NOT NULL
+);
 COMMIT;
 "#;
 
-pub(super) const AUDIT_TABLES: &[&str] = &["events"];
+pub(super) const AUDIT_TABLES: &[&str] = &["events", "chain_heads"];
 
-pub(super) const RAG_INDEX_SCHEMA_SQL: &str = r#"
+pub(crate) const RAG_INDEX_SCHEMA_SQL: &str = r#"
 BEGIN;
 CREATE TABLE IF NOT EXISTS docs (
     id TEXT PRIMARY KEY,
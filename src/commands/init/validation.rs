@@ -1,12 +1,12 @@
 use std::path::Path;
 
-use crate::{metadata, workspace::Workspace};
+use crate::{audit::AuditLog, metadata, workspace::Workspace};
 
 use super::{
     InitError, ensure_manifest_compatibility, ensure_schema_version_matches, interruptible, schema,
 };
 
-pub(super) fn run_check(workspace: &Workspace) -> Result<(), InitError> {
+pub(super) fn run_check(workspace: &Workspace, verify_audit: bool) -> Result<(), InitError> {
     let mut report = ValidationReport::new();
 
     interruptible(|| {
@@ -129,6 +129,15 @@ pub(super) fn run_check(workspace: &Workspace) -> Result<(), InitError> {
         Ok(())
     })?;
 
+    if verify_audit {
+        interruptible(|| {
+            if let Err(issue) = validate_audit_chain(workspace) {
+                report.push(issue);
+            }
+            Ok(())
+        })?;
+    }
+
     interruptible(|| report.finish())
 }
 
@@ -190,10 +199,10 @@ fn validate_content_if_present<F>(path: &Path, validator: F, report: &mut Valida
 where
     F: Fn(&Path) -> Result<(), String>,
 {
-    if path.exists() {
-        if let Err(issue) = validator(path) {
-            report.push(issue);
-        }
+    if path.exists()
+        && let Err(issue) = validator(path)
+    {
+        report.push(issue);
     }
 }
 
@@ -206,7 +215,7 @@ fn validate_schema_marker(workspace: &Workspace) -> Result<(), String> {
     let content = std::fs::read_to_string(&path)
         .map_err(|err| format!("Failed to read schema marker {}: {err}", path.display()))?;
     ensure_schema_version_matches(content.trim(), |found, expected| {
-        format!("Schema marker reports {found}, expected {expected}")
+        format!("Schema marker reports {found}, expected {expected}; run `alisa migrate`")
     })
 }
 
@@ -229,3 +238,16 @@ fn validate_audit_schema(workspace: &Workspace) -> Result<(), String> {
 fn validate_rag_schema(workspace: &Workspace) -> Result<(), String> {
     schema::validate_sqlite_tables(&workspace.rag_index_path(), schema::RAG_TABLES, "RAG index")
 }
+
+fn validate_audit_chain(workspace: &Workspace) -> Result<(), String> {
+    if !workspace.audit_index_path().exists() {
+        return Ok(());
+    }
+    let report = AuditLog::open(workspace)
+        .and_then(|log| log.verify())
+        .map_err(|err| format!("Failed to verify audit chain: {err:#}"))?;
+    match report.broken {
+        Some(broken) => Err(format!("Audit chain broken at {broken}")),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,101 @@
+use clap::Args;
+use serde_json::json;
+use thiserror::Error;
+
+use super::{LockPolicy, WorkspaceLockError, WorkspaceLockStatus, acquire_workspace_lock};
+use crate::{
+    audit::{self, AuditEvent},
+    metadata::MANIFEST_SCHEMA_VERSION,
+    migrations::{self, Migration},
+    workspace::Workspace,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct MigrateCliArgs {
+    /// Print the pending migrations without applying them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum MigrateError {
+    #[error("workspace is not initialized at {path}; run `alisa init` first")]
+    WorkspaceMissing { path: String },
+    #[error("workspace lock at {lock_path} is held by another process")]
+    WorkspaceLocked { lock_path: String },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub fn run(args: &MigrateCliArgs) -> Result<(), MigrateError> {
+    let workspace = Workspace::detect_from_cwd()?;
+    if !workspace.workspace_root().exists() {
+        return Err(MigrateError::WorkspaceMissing {
+            path: workspace.workspace_root().display().to_string(),
+        });
+    }
+
+    let _lock = match acquire_workspace_lock(&workspace, LockPolicy::Required) {
+        Ok(WorkspaceLockStatus::Acquired(guard)) => guard,
+        Ok(WorkspaceLockStatus::Skipped) => unreachable!("required lock policy never skips"),
+        Err(WorkspaceLockError::AlreadyLocked) => {
+            return Err(MigrateError::WorkspaceLocked {
+                lock_path: workspace.lock_path().display().to_string(),
+            });
+        }
+        Err(WorkspaceLockError::Other(err)) => return Err(MigrateError::Other(err)),
+    };
+
+    let from = migrations::current_version(&workspace)?;
+    let pending = migrations::plan(&from)?;
+    let Some(backup) = migrations::backup_dir(&workspace, &pending) else {
+        println!("[ok] Workspace schema is up to date ({MANIFEST_SCHEMA_VERSION}).");
+        return Ok(());
+    };
+
+    if args.dry_run {
+        print_plan(&workspace, &pending);
+        println!(
+            "[plan] {} migration(s) would upgrade {from} to {MANIFEST_SCHEMA_VERSION}",
+            pending.len()
+        );
+        return Ok(());
+    }
+
+    migrations::apply(&workspace, &pending, &backup)?;
+    for migration in &pending {
+        println!(
+            "[ok] Migrated {} → {}: {}",
+            migration.from, migration.to, migration.description
+        );
+    }
+    println!("[ok] Backups saved to {}", backup.display());
+    audit::emit(
+        &workspace,
+        AuditEvent::new(audit::EVENT_SCHEMA_MIGRATED).data(json!({
+            "from": from,
+            "to": MANIFEST_SCHEMA_VERSION,
+            "backup": backup,
+        })),
+    );
+    Ok(())
+}
+
+fn print_plan(workspace: &Workspace, pending: &[&Migration]) {
+    for migration in pending {
+        println!(
+            "[plan] {} → {}: {}",
+            migration.from, migration.to, migration.description
+        );
+        for database in migration.databases() {
+            println!(
+                "[plan]   Back up the {}: {}",
+                database.label(),
+                database.path(workspace).display()
+            );
+        }
+        for step in migration.steps {
+            println!("[plan]   {}", step.describe());
+        }
+    }
+}
@@ -1,10 +1,46 @@
-use std::fmt;
+use std::{fmt, path::PathBuf};
 
 use anyhow::Error;
 
-use crate::workspace::{Workspace, WorkspaceLock};
+use crate::{
+    config::ConfigFiles,
+    metadata::{self, DEFAULT_CONFIG_PATH, DEFAULT_LOCAL_OVERRIDES_PATH},
+    workspace::{Workspace, WorkspaceLock},
+};
 
+pub mod audit;
+pub mod config;
 pub mod init;
+pub mod migrate;
+pub mod rag;
+pub mod resolve;
+pub mod run;
+pub mod runs;
+pub mod task;
+
+/// Path of the project configuration as recorded in the manifest.
+pub(crate) fn config_path(workspace: &Workspace) -> Result<PathBuf, Error> {
+    let relative = metadata::read_manifest(&workspace.manifest_path())?
+        .map(|manifest| manifest.paths.config)
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_owned());
+    Ok(workspace.project_root().join(relative))
+}
+
+/// Configuration layers recorded in the manifest: the project file and the
+/// local overrides next to it.
+pub(crate) fn config_files(workspace: &Workspace) -> Result<ConfigFiles, Error> {
+    let paths = metadata::read_manifest(&workspace.manifest_path())?.map(|manifest| manifest.paths);
+    Ok(ConfigFiles {
+        root: workspace.project_root().to_path_buf(),
+        project: paths
+            .as_ref()
+            .map_or_else(|| DEFAULT_CONFIG_PATH.to_owned(), |paths| paths.config.clone()),
+        local: match paths {
+            Some(paths) => paths.local_overrides,
+            None => Some(DEFAULT_LOCAL_OVERRIDES_PATH.to_owned()),
+        },
+    })
+}
 
 /// Policy describing when workspace lock should be attempted.
 #[derive(Debug, Clone, Copy)]
@@ -0,0 +1,162 @@
+use clap::{Args, Subcommand};
+use serde_json::json;
+use thiserror::Error;
+
+use super::{
+    LockPolicy, WorkspaceLockError, WorkspaceLockStatus, acquire_workspace_lock, config_files,
+};
+use crate::{
+    config::ConfigError,
+    metadata::to_pretty_json,
+    rag::{self, DocHit, RagIndex},
+    tasks::{TaskLoadError, TaskSet},
+    workspace::Workspace,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct RagCliArgs {
+    #[command(subcommand)]
+    pub command: RagCommand,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum RagCommand {
+    /// Index the docs directory and the docs listed by tasks
+    Index {
+        /// Re-index every file, even if its content did not change
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show the chunks that retrieval returns for a query
+    Search(RagSearchArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RagSearchArgs {
+    /// Free text, matched like a task title and description
+    pub query: String,
+
+    /// Use the token limits of this profile for the budget
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Print the chunks as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum RagError {
+    #[error("workspace is not initialized at {path}; run `alisa init` first")]
+    WorkspaceMissing { path: String },
+    #[error("workspace lock at {lock_path} is held by another process")]
+    WorkspaceLocked { lock_path: String },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Tasks(#[from] TaskLoadError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub fn run(args: &RagCliArgs) -> Result<(), RagError> {
+    let workspace = Workspace::detect_from_cwd()?;
+    if !workspace.workspace_root().exists() {
+        return Err(RagError::WorkspaceMissing {
+            path: workspace.workspace_root().display().to_string(),
+        });
+    }
+
+    match &args.command {
+        RagCommand::Index { force } => {
+            let _lock = match acquire_workspace_lock(&workspace, LockPolicy::Required) {
+                Ok(WorkspaceLockStatus::Acquired(guard)) => guard,
+                Ok(WorkspaceLockStatus::Skipped) => {
+                    unreachable!("required lock policy never skips")
+                }
+                Err(WorkspaceLockError::AlreadyLocked) => {
+                    return Err(RagError::WorkspaceLocked {
+                        lock_path: workspace.lock_path().display().to_string(),
+                    });
+                }
+                Err(WorkspaceLockError::Other(err)) => return Err(RagError::Other(err)),
+            };
+
+            let root = workspace.project_root();
+            let paths = doc_paths(&workspace)?;
+            let (sources, missing) = rag::collect_sources(root, &paths);
+            for path in missing {
+                eprintln!("[warn] Docs path {path} does not exist; skipping");
+            }
+            let report =
+                RagIndex::open(&workspace.rag_index_path())?.index_files(root, &sources, *force)?;
+            println!(
+                "[ok] Indexed {} file(s) into {} chunk(s): {} unchanged, {} removed",
+                report.indexed, report.chunks, report.unchanged, report.removed
+            );
+            Ok(())
+        }
+        RagCommand::Search(search) => {
+            let config = config_files(&workspace)?.load()?.config;
+            let budget = rag::token_budget(&config.effective_limits(search.profile.as_deref()));
+            let hits =
+                RagIndex::open(&workspace.rag_index_path())?.retrieve(&search.query, budget)?;
+            if search.json {
+                println!(
+                    "{}",
+                    to_pretty_json(&json!({ "budget": budget, "hits": hits }))?
+                );
+            } else {
+                print_hits(&hits, budget);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_hits(hits: &[DocHit], budget: u64) {
+    if hits.is_empty() {
+        println!("No matching docs.");
+        return;
+    }
+    for hit in hits {
+        let mut line = format!(
+            "{:>7.2}  {}:{}-{}  ({} tokens)",
+            hit.score, hit.source, hit.start_line, hit.end_line, hit.tokens
+        );
+        if let Some(heading) = &hit.heading {
+            line.push_str(&format!("  # {heading}"));
+        }
+        println!("{line}");
+        for preview in hit
+            .content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(2)
+        {
+            println!("         {}", preview.trim());
+        }
+    }
+    println!(
+        "{} chunk(s), {} of {budget} tokens",
+        hits.len(),
+        hits.iter().map(|hit| hit.tokens).sum::<u64>()
+    );
+}
+
+/// `paths.docs_dir` followed by every `context.docs` entry of the tasks file.
+fn doc_paths(workspace: &Workspace) -> Result<Vec<String>, RagError> {
+    let config = config_files(workspace)?.load()?.config;
+    let mut paths = vec![config.paths.docs_dir.clone()];
+    let tasks_path = workspace.project_root().join(&config.paths.tasks_file);
+    if tasks_path.exists() {
+        for task in TaskSet::from_path(&tasks_path)?.tasks {
+            for doc in task.context.docs {
+                if !paths.contains(&doc) {
+                    paths.push(doc);
+                }
+            }
+        }
+    }
+    Ok(paths)
+}
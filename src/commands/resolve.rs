@@ -0,0 +1,137 @@
+use clap::Args;
+use thiserror::Error;
+
+use super::config_files;
+use crate::{
+    config::{ConfigError, RoleKind},
+    runtime::resolver::{
+        CliRoleOverrides, ConditionCheck, DecisionSource, ResolveError, RoleTrace, TaskMeta,
+        resolve_runners,
+    },
+    tasks::{TaskLoadError, TaskSet},
+    workspace::Workspace,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct ResolveCliArgs {
+    /// Identifier of the task from the tasks file
+    pub task_id: String,
+
+    /// Profile to start from instead of the configured default
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Language hint used by routing rules (overrides the task `lang`)
+    #[arg(long)]
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ResolveCmdError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Tasks(#[from] TaskLoadError),
+    #[error("task '{0}' is not defined in the tasks file")]
+    UnknownTask(String),
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub fn run(args: &ResolveCliArgs) -> Result<(), ResolveCmdError> {
+    let workspace = Workspace::detect_from_cwd()?;
+    let config = config_files(&workspace)?.load()?.config;
+    let tasks = TaskSet::from_path(&workspace.project_root().join(&config.paths.tasks_file))?;
+    let task = tasks
+        .find(&args.task_id)
+        .ok_or_else(|| ResolveCmdError::UnknownTask(args.task_id.clone()))?;
+
+    let overrides = CliRoleOverrides {
+        profile: args.profile.clone(),
+        lang: args.lang.clone(),
+        ..CliRoleOverrides::default()
+    };
+    let runners = resolve_runners(&config, &TaskMeta::from(task), &overrides)?;
+
+    let lang = args.lang.as_deref().or(task.lang.as_deref());
+    let starting_profile = runners
+        .trace
+        .first()
+        .and_then(|decision| decision.profile.as_deref());
+    println!(
+        "Task {} (lang: {}, profile: {})",
+        task.id,
+        lang.unwrap_or("<none>"),
+        starting_profile.unwrap_or("<none>")
+    );
+    for decision in &runners.trace {
+        print!("{}", render_decision(decision));
+    }
+    println!(
+        "Final profile: {}",
+        runners.profile.as_deref().unwrap_or("<none>")
+    );
+    Ok(())
+}
+
+/// One role's runner, the layer that chose it and every routing rule for
+/// the role with its rank and conditions.
+fn render_decision(decision: &RoleTrace) -> String {
+    let mut out = format!(
+        "{} → {} [{}]\n",
+        decision.role.as_str(),
+        decision.runner,
+        describe_source(decision.role, &decision.source)
+    );
+    for rule in &decision.rules {
+        let outcome = match decision.source {
+            DecisionSource::Routing { index } if index == rule.index => "selected",
+            _ if rule.matched => "matched, outranked",
+            _ => "skipped",
+        };
+        out.push_str(&format!(
+            "  rule #{}: {outcome} (priority {}, specificity {})\n",
+            rule.index, rule.priority, rule.specificity
+        ));
+        if rule.conditions.is_empty() {
+            out.push_str("    ✓ no conditions\n");
+        }
+        render_checks(&rule.conditions, 2, &mut out);
+    }
+    if let Some(profile) = &decision.profile_switch {
+        out.push_str(&format!("  switches to profile {profile}\n"));
+    }
+    out
+}
+
+/// Value checks show what the task had; `any`, `all` and `not` checks list
+/// their nested checks one level deeper.
+fn render_checks(checks: &[ConditionCheck], depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    for check in checks {
+        let mark = if check.passed { '✓' } else { '✗' };
+        if check.nested.is_empty() {
+            let actual = check.actual.as_deref().unwrap_or("<none>");
+            out.push_str(&format!(
+                "{indent}{mark} {} (task: {actual})\n",
+                check.condition
+            ));
+        } else {
+            out.push_str(&format!("{indent}{mark} {}\n", check.condition));
+            render_checks(&check.nested, depth + 1, out);
+        }
+    }
+}
+
+fn describe_source(role: RoleKind, source: &DecisionSource) -> String {
+    match source {
+        DecisionSource::CliRole => format!("--{}-llm", role.as_str()),
+        DecisionSource::CliLlm => "--llm".to_string(),
+        DecisionSource::Task => format!("task llm.{}", role.as_str()),
+        DecisionSource::Routing { index } => format!("routing rule #{index}"),
+        DecisionSource::Profile { name } => format!("profile {name}"),
+        DecisionSource::Roles => "global roles".to_string(),
+    }
+}
@@ -0,0 +1,883 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::Context;
+use clap::Args;
+use serde_json::{Value, json};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::{
+    LockPolicy, WorkspaceLockError, WorkspaceLockStatus, acquire_workspace_lock, config_files,
+    config_path, init::ask_yes_no,
+};
+use crate::{
+    audit::{self, AuditEvent},
+    config::{Config, ConfigError, ReviewStageKind, RoleKind},
+    metadata::{self, current_timestamp},
+    rag::{self, DocHit, RagIndex},
+    registry::{Registry, StageFinish, StageRecord, StageStart},
+    runtime::{
+        executor::{self, ExecError, ExecRequest, ExitState},
+        limits::{self, ChangeMeasure, LimitReport, estimate_tokens},
+        patch::{FileChange, Patch, PatchError},
+        prompt::{PromptContext, PromptError, PromptTemplate},
+        resolver::{
+            CliRoleOverrides, ResolveError, ResolvedRunners, TaskMeta, resolve_review_pipeline,
+            resolve_runners,
+        },
+        review::{self, ReviewContext, ReviewError, StageVerdict},
+    },
+    tasks::{Task, TaskLoadError, TaskSet},
+    workspace::Workspace,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct RunCliArgs {
+    /// Identifier of the task from the tasks file
+    pub task_id: String,
+
+    /// Profile to use instead of the configured default
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Runner to use for every role
+    #[arg(long)]
+    pub llm: Option<String>,
+
+    /// Runner to use for the plan stage
+    #[arg(long)]
+    pub plan_llm: Option<String>,
+
+    /// Runner to use for the code stage
+    #[arg(long)]
+    pub code_llm: Option<String>,
+
+    /// Runner to use for the review stage
+    #[arg(long)]
+    pub review_llm: Option<String>,
+
+    /// Language hint used by routing rules (overrides the task `lang`)
+    #[arg(long)]
+    pub lang: Option<String>,
+
+    /// Review pipeline to use instead of the task or configured default
+    #[arg(long)]
+    pub pipeline: Option<String>,
+
+    /// Apply the resulting patch without asking for confirmation
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+}
+
+impl RunCliArgs {
+    fn role_overrides(&self) -> CliRoleOverrides {
+        CliRoleOverrides {
+            plan_llm: self.plan_llm.clone(),
+            code_llm: self.code_llm.clone(),
+            review_llm: self.review_llm.clone(),
+            llm: self.llm.clone(),
+            profile: self.profile.clone(),
+            pipeline: self.pipeline.clone(),
+            lang: self.lang.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RunError {
+    #[error("workspace is not initialized at {path}; run `alisa init` first")]
+    WorkspaceMissing { path: String },
+    #[error("workspace lock at {lock_path} is held by another process")]
+    WorkspaceLocked { lock_path: String },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Tasks(#[from] TaskLoadError),
+    #[error("task '{0}' is not defined in the tasks file")]
+    UnknownTask(String),
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+    #[error(transparent)]
+    Exec(#[from] ExecError),
+    #[error(transparent)]
+    Prompt(#[from] PromptError),
+    #[error(transparent)]
+    Review(ReviewError),
+    #[error("stage '{stage}' failed: {reason}")]
+    StageFailed { stage: String, reason: String },
+    #[error("invalid patch in code stage output: {0}")]
+    Patch(#[from] PatchError),
+    #[error("{0}")]
+    LimitsExceeded(LimitReport),
+    #[error("patch could not be applied: {reason}; no files were changed, patch kept at {patch}")]
+    ApplyFailed { reason: String, patch: String },
+    #[error("operation interrupted")]
+    Interrupted,
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Unified diff extracted from the code stage output.
+const PATCH_FILE_NAME: &str = "patch.diff";
+
+/// Limit check report written into the code stage directory.
+const LIMITS_FILE_NAME: &str = "limits.json";
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+pub fn run(args: &RunCliArgs) -> Result<(), RunError> {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    let workspace = Workspace::detect_from_cwd()?;
+    if !workspace.workspace_root().exists() {
+        return Err(RunError::WorkspaceMissing {
+            path: workspace.workspace_root().display().to_string(),
+        });
+    }
+
+    let _lock = match acquire_workspace_lock(&workspace, LockPolicy::Required) {
+        Ok(WorkspaceLockStatus::Acquired(guard)) => guard,
+        Ok(WorkspaceLockStatus::Skipped) => unreachable!("required lock policy never skips"),
+        Err(WorkspaceLockError::AlreadyLocked) => {
+            return Err(RunError::WorkspaceLocked {
+                lock_path: workspace.lock_path().display().to_string(),
+            });
+        }
+        Err(WorkspaceLockError::Other(err)) => return Err(RunError::Other(err)),
+    };
+
+    ctrlc::set_handler(|| {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        eprintln!("\n[warn] Interrupt received. Stopping the current stage.");
+    })
+    .map_err(|err| RunError::Other(err.into()))?;
+
+    let config = load_config(&workspace)?;
+    audit::note_config(&workspace, &config_path(&workspace)?);
+    let tasks = TaskSet::from_path(&workspace.project_root().join(&config.paths.tasks_file))?;
+    let task = tasks
+        .find(&args.task_id)
+        .ok_or_else(|| RunError::UnknownTask(args.task_id.clone()))?;
+    let registry = Registry::open(&workspace.registry_path())?;
+    registry.sync_tasks(&tasks.tasks)?;
+
+    let overrides = args.role_overrides();
+    let runners = resolve_runners(&config, &TaskMeta::from(task), &overrides)?;
+    let run_id = new_run_id();
+    let started_at = current_timestamp();
+    println!(
+        "[run] {run_id}: task {} (profile: {})",
+        task.id,
+        runners.profile.as_deref().unwrap_or("<none>")
+    );
+
+    audit::emit(
+        &workspace,
+        AuditEvent::new(audit::EVENT_RUN_STARTED)
+            .task(&task.id)
+            .run(&run_id)
+            .data(json!({ "profile": runners.profile, "runners": {
+                "plan": runners.plan,
+                "code": runners.code,
+                "review": runners.review,
+            } })),
+    );
+
+    let docs = retrieve_docs(&workspace, &config, task, runners.profile.as_deref());
+    let ctx = RunContext {
+        workspace: &workspace,
+        config: &config,
+        registry: &registry,
+        run_id: &run_id,
+        task,
+        runners: &runners,
+        overrides: &overrides,
+        docs: &docs,
+        assume_yes: args.yes,
+    };
+    let mut progress = RunProgress::default();
+    let result = run_stages(&ctx, &mut progress).and_then(|()| {
+        check_for_interrupt()?;
+        apply_patch(&ctx, &mut progress)
+    });
+    let summary = write_run_summary(&ctx, &started_at, &progress, &result);
+    audit::emit(
+        &workspace,
+        AuditEvent::new(audit::EVENT_RUN_FINISHED)
+            .task(&task.id)
+            .run(&run_id)
+            .data(json!({
+                "success": result.is_ok(),
+                "error": result.as_ref().err().map(ToString::to_string),
+            })),
+    );
+
+    // The run's own failure matters more than a summary that was not written.
+    result?;
+    summary?;
+    println!(
+        "[ok] Run {run_id} finished: {}",
+        workspace.run_dir(&run_id)?.display()
+    );
+    Ok(())
+}
+
+/// Loads the effective configuration from the layers recorded in the manifest.
+fn load_config(workspace: &Workspace) -> Result<Config, RunError> {
+    Ok(config_files(workspace)?.load()?.config)
+}
+
+/// Retrieves documentation for the task from the RAG index within the token
+/// budget of the effective limits. Retrieval is best effort: a missing or
+/// unreadable index yields no documents.
+fn retrieve_docs(
+    workspace: &Workspace,
+    config: &Config,
+    task: &Task,
+    profile: Option<&str>,
+) -> Vec<DocHit> {
+    let path = workspace.rag_index_path();
+    if !path.exists() {
+        return Vec::new();
+    }
+    let budget = rag::token_budget(&config.effective_limits(profile));
+    match RagIndex::open(&path).and_then(|index| index.retrieve(&rag::task_text(task), budget)) {
+        Ok(docs) => {
+            if !docs.is_empty() {
+                println!(
+                    "[rag] {} doc chunk(s) retrieved ({} of {budget} tokens)",
+                    docs.len(),
+                    docs.iter().map(|doc| doc.tokens).sum::<u64>()
+                );
+            }
+            docs
+        }
+        Err(err) => {
+            eprintln!("[warn] Failed to retrieve docs: {err:#}");
+            Vec::new()
+        }
+    }
+}
+
+fn new_run_id() -> String {
+    format!("run_{}", Uuid::new_v4().simple())
+}
+
+/// State shared by every stage of a single run.
+struct RunContext<'a> {
+    workspace: &'a Workspace,
+    config: &'a Config,
+    registry: &'a Registry,
+    run_id: &'a str,
+    task: &'a Task,
+    runners: &'a ResolvedRunners,
+    overrides: &'a CliRoleOverrides,
+    /// Documentation retrieved for the plan and code prompts.
+    docs: &'a [DocHit],
+    /// Skip the confirmation before applying the patch (`--yes`).
+    assume_yes: bool,
+}
+
+/// Everything a run produced so far; summarized in `run.json` even when it fails.
+#[derive(Debug, Default)]
+struct RunProgress {
+    stages: Vec<StageOutcome>,
+    patch: Option<Patch>,
+    apply: Option<Value>,
+}
+
+fn run_stages(ctx: &RunContext<'_>, progress: &mut RunProgress) -> Result<(), RunError> {
+    for role in [RoleKind::Plan, RoleKind::Code, RoleKind::Review] {
+        check_for_interrupt()?;
+        let row_id = start_stage_row(ctx, role)?;
+        let outcomes = &progress.stages;
+        let result = match role {
+            RoleKind::Plan => run_runner_stage(ctx, role, &ctx.runners.plan, outcomes),
+            RoleKind::Code => run_runner_stage(ctx, role, &ctx.runners.code, outcomes),
+            RoleKind::Review => run_review_stage(ctx, outcomes),
+        };
+        let mut outcome = match result {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                finish_stage_row(
+                    ctx,
+                    &row_id,
+                    role,
+                    None,
+                    false,
+                    json!({ "error": err.to_string() }),
+                )?;
+                return Err(err);
+            }
+        };
+        let failure = outcome.failure.clone();
+        let limits = match (role, &failure) {
+            (RoleKind::Code, None) => {
+                progress.patch = capture_patch(ctx, &outcome)?;
+                Some(enforce_limits(ctx, &mut outcome, progress.patch.as_ref())?)
+            }
+            _ => None,
+        };
+        let success = failure.is_none() && limits.as_ref().is_none_or(LimitReport::passed);
+        finish_stage_row(
+            ctx,
+            &row_id,
+            role,
+            Some(&outcome),
+            success,
+            outcome.summary.clone(),
+        )?;
+        progress.stages.push(outcome);
+
+        if let Some(reason) = failure {
+            return Err(RunError::StageFailed {
+                stage: role.as_str().to_string(),
+                reason,
+            });
+        }
+        if let Some(report) = limits
+            && !report.passed()
+        {
+            return Err(RunError::LimitsExceeded(report));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct StageOutcome {
+    role: RoleKind,
+    /// Model reported by the runner; pipelines have none of their own.
+    model: Option<String>,
+    output: String,
+    failure: Option<String>,
+    /// Estimated prompt and response tokens of the stage runner.
+    tokens_in: u64,
+    tokens_out: u64,
+    summary: Value,
+}
+
+fn stage_outputs(previous: &[StageOutcome]) -> BTreeMap<String, String> {
+    previous
+        .iter()
+        .map(|outcome| (outcome.role.as_str().to_string(), outcome.output.clone()))
+        .collect()
+}
+
+/// Runs a single role through its resolved runner.
+fn run_runner_stage(
+    ctx: &RunContext<'_>,
+    role: RoleKind,
+    runner_name: &str,
+    previous: &[StageOutcome],
+) -> Result<StageOutcome, RunError> {
+    let runner = ctx
+        .config
+        .runner(runner_name)
+        .ok_or_else(|| ResolveError::UnknownRunner {
+            name: runner_name.to_string(),
+        })?;
+    let dir = ctx.workspace.run_stage_dir(ctx.run_id, role.as_str())?;
+
+    let template = PromptTemplate::for_role(ctx.workspace.project_root(), runner, role)?;
+    let prompt = template.render(&PromptContext {
+        task: ctx.task,
+        extra: &ctx.task.context.extra,
+        role: role.as_str(),
+        profile: ctx.runners.profile.as_deref(),
+        runner: runner_name,
+        model: runner.model.as_deref(),
+        stages: stage_outputs(previous),
+        verdicts: &[],
+        docs: ctx.docs,
+    })?;
+
+    let exec = executor::execute(&ExecRequest {
+        runner_name,
+        runner,
+        prompt: &prompt,
+        working_dir: ctx.workspace.project_root(),
+        output_dir: &dir,
+        cancel: Some(&INTERRUPTED),
+    })?;
+    if exec.state == ExitState::Cancelled {
+        return Err(RunError::Interrupted);
+    }
+    let output = exec.read_stdout()?;
+    let tokens_in = estimate_tokens(&prompt);
+    let tokens_out = estimate_tokens(&output);
+    println!(
+        "[{}] {} via '{}' in {} ms",
+        role.as_str(),
+        if exec.success() { "ok" } else { "failed" },
+        exec.runner,
+        exec.duration_ms
+    );
+
+    let result = json!({
+        "stage": role.as_str(),
+        "runner": exec.runner,
+        "model": exec.model,
+        "template": template.source.to_string(),
+        "docs": ctx.docs.iter().map(|doc| &doc.id).collect::<Vec<_>>(),
+        "state": exec.state,
+        "exit_code": exec.exit_code(),
+        "success": exec.success(),
+        "duration_ms": exec.duration_ms,
+        "tokens": { "prompt": tokens_in, "response": tokens_out },
+        "prompt": exec.prompt_path,
+        "stdout": exec.stdout_path,
+        "stderr": exec.stderr_path,
+    });
+    write_artifact(
+        &dir.join("result.json"),
+        metadata::to_pretty_json(&result)?.as_bytes(),
+    )?;
+
+    Ok(StageOutcome {
+        role,
+        model: exec.model.clone(),
+        output,
+        failure: (!exec.success()).then(|| exec.failure_reason(runner.timeout_ms)),
+        tokens_in,
+        tokens_out,
+        summary: json!({
+            "stage": role.as_str(),
+            "runner": exec.runner,
+            "success": exec.success(),
+            "duration_ms": exec.duration_ms,
+        }),
+    })
+}
+
+/// Runs the resolved review pipeline, or the review role runner when no pipeline is configured.
+fn run_review_stage(
+    ctx: &RunContext<'_>,
+    previous: &[StageOutcome],
+) -> Result<StageOutcome, RunError> {
+    let pipeline =
+        match resolve_review_pipeline(ctx.config, &TaskMeta::from(ctx.task), ctx.overrides) {
+            Ok(pipeline) => pipeline,
+            Err(ResolveError::PipelineNotSpecified) => {
+                return run_runner_stage(ctx, RoleKind::Review, &ctx.runners.review, previous);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+    let review_dir = ctx
+        .workspace
+        .run_stage_dir(ctx.run_id, RoleKind::Review.as_str())?;
+    let stages = stage_outputs(previous);
+    let outcome = review::run_pipeline(
+        &pipeline,
+        &ReviewContext {
+            config: ctx.config,
+            task: ctx.task,
+            profile: ctx.runners.profile.as_deref(),
+            project_root: ctx.workspace.project_root(),
+            review_dir: &review_dir,
+            stages: &stages,
+            cancel: Some(&INTERRUPTED),
+        },
+    )
+    .map_err(|err| match err {
+        ReviewError::Cancelled { .. } => RunError::Interrupted,
+        other => RunError::Review(other),
+    })?;
+
+    let mut output = String::new();
+    for verdict in &outcome.verdicts {
+        println!(
+            "[review] {} {}: {} ({} ms)",
+            verdict.stage,
+            if verdict.passed { "passed" } else { "failed" },
+            verdict.summary,
+            verdict.exec.duration_ms
+        );
+        output.push_str(&format!(
+            "{}: {} — {}\n",
+            verdict.stage,
+            if verdict.passed { "pass" } else { "fail" },
+            verdict.summary
+        ));
+        for warning in &verdict.warnings {
+            eprintln!("[warn] review stage '{}': {warning}", verdict.stage);
+        }
+        record_verdict(ctx, &outcome.pipeline, verdict)?;
+    }
+
+    let decision = &outcome.decision;
+    println!(
+        "[review] {} consensus {}: {}",
+        decision.consensus.as_str(),
+        if decision.passed { "passed" } else { "failed" },
+        decision.reason
+    );
+    let failure = (!decision.passed).then(|| {
+        format!(
+            "pipeline '{}' rejected the change ({})",
+            outcome.pipeline, decision.reason
+        )
+    });
+
+    Ok(StageOutcome {
+        role: RoleKind::Review,
+        model: None,
+        output,
+        failure,
+        tokens_in: 0,
+        tokens_out: 0,
+        summary: json!({
+            "stage": RoleKind::Review.as_str(),
+            "pipeline": outcome.pipeline,
+            "success": outcome.passed(),
+            "consensus": decision,
+            "verdicts": outcome.verdicts.iter().map(|verdict| json!({
+                "stage": verdict.stage,
+                "kind": verdict.kind,
+                "passed": verdict.passed,
+                "summary": verdict.summary,
+                "warnings": verdict.warnings,
+                "runner": verdict.runner,
+                "duration_ms": verdict.exec.duration_ms,
+            })).collect::<Vec<_>>(),
+        }),
+    })
+}
+
+/// Saves the unified diff from the code stage output as `patch.diff` and parses it.
+fn capture_patch(ctx: &RunContext<'_>, outcome: &StageOutcome) -> Result<Option<Patch>, RunError> {
+    let Some(diff) = Patch::extract(&outcome.output) else {
+        return Ok(None);
+    };
+    let dir = ctx
+        .workspace
+        .run_stage_dir(ctx.run_id, RoleKind::Code.as_str())?;
+    write_artifact(&dir.join(PATCH_FILE_NAME), diff.as_bytes())?;
+    Ok(Some(Patch::parse(&diff)?))
+}
+
+/// Measures the change produced by the code stage and checks it against the
+/// limits of the effective profile; the report is saved as `limits.json`.
+fn enforce_limits(
+    ctx: &RunContext<'_>,
+    outcome: &mut StageOutcome,
+    patch: Option<&Patch>,
+) -> Result<LimitReport, RunError> {
+    let stats = patch.map(Patch::stats).unwrap_or_default();
+    let measured = ChangeMeasure::new(stats, outcome.tokens_in, outcome.tokens_out);
+    let profile = ctx.runners.profile.as_deref();
+    let report = limits::check(&ctx.config.effective_limits(profile), profile, measured);
+
+    println!(
+        "[code] change: {} file(s), {} changed line(s), ~{} token(s)",
+        measured.files,
+        measured.changed_lines,
+        measured.total_tokens()
+    );
+    let dir = ctx
+        .workspace
+        .run_stage_dir(ctx.run_id, RoleKind::Code.as_str())?;
+    let report_json = json!(report);
+    write_artifact(
+        &dir.join(LIMITS_FILE_NAME),
+        metadata::to_pretty_json(&report_json)?.as_bytes(),
+    )?;
+    outcome.summary["limits"] = report_json;
+    Ok(report)
+}
+
+/// Applies the reviewed patch to the project tree, asking first unless
+/// `apply.confirm = false` or `--yes` was given.
+fn apply_patch(ctx: &RunContext<'_>, progress: &mut RunProgress) -> Result<(), RunError> {
+    let Some(patch) = progress
+        .patch
+        .as_ref()
+        .filter(|patch| !patch.files.is_empty())
+    else {
+        println!("[apply] code stage produced no patch; nothing to apply");
+        progress.apply = Some(json!({ "status": "none" }));
+        return Ok(());
+    };
+    let patch_path = ctx
+        .workspace
+        .run_stage_dir(ctx.run_id, RoleKind::Code.as_str())?
+        .join(PATCH_FILE_NAME);
+    let confirm = ctx.config.apply.confirm && !ctx.assume_yes;
+
+    if confirm {
+        let diff = fs::read_to_string(&patch_path)
+            .with_context(|| format!("Failed to read {}", patch_path.display()))?;
+        print!("{diff}");
+    }
+    let stats = patch.stats();
+    println!(
+        "[apply] {}: {} file(s), +{} -{}",
+        patch_path.display(),
+        stats.files,
+        stats.added,
+        stats.removed
+    );
+    for file in &patch.files {
+        let file_stats = file.stats();
+        let path = match (file.change(), &file.old_path) {
+            (FileChange::Renamed, Some(old)) => format!("{old} -> {}", file.path()),
+            _ => file.path().to_string(),
+        };
+        println!(
+            "  {} {path} (+{} -{})",
+            file.change().marker(),
+            file_stats.added,
+            file_stats.removed
+        );
+    }
+
+    if confirm {
+        let approved = ask_yes_no(
+            "Apply this patch to the project tree? [y/N]",
+            false,
+            "Leaving the project tree unchanged.",
+        )
+        .map_err(|err| RunError::Other(err.into()))?;
+        check_for_interrupt()?;
+        if !approved {
+            println!("[apply] skipped; patch kept at {}", patch_path.display());
+            progress.apply = Some(json!({ "status": "skipped", "patch": patch_path }));
+            return Ok(());
+        }
+    }
+
+    match patch.apply(ctx.workspace.project_root()) {
+        Ok(()) => {
+            println!("[apply] applied to {} file(s)", stats.files);
+            audit::emit(
+                ctx.workspace,
+                AuditEvent::new(audit::EVENT_PATCH_APPLIED)
+                    .task(&ctx.task.id)
+                    .run(ctx.run_id)
+                    .data(json!({
+                        "patch": patch_path,
+                        "files": patch.files.iter().map(|file| file.path()).collect::<Vec<_>>(),
+                        "added": stats.added,
+                        "removed": stats.removed,
+                    })),
+            );
+            progress.apply = Some(json!({
+                "status": "applied",
+                "patch": patch_path,
+                "files": patch.files.iter().map(|file| file.path()).collect::<Vec<_>>(),
+            }));
+            Ok(())
+        }
+        Err(err) => {
+            progress.apply = Some(json!({
+                "status": "failed",
+                "patch": patch_path,
+                "error": err.to_string(),
+            }));
+            Err(RunError::ApplyFailed {
+                reason: err.to_string(),
+                patch: patch_path.display().to_string(),
+            })
+        }
+    }
+}
+
+fn record_verdict(
+    ctx: &RunContext<'_>,
+    pipeline: &str,
+    verdict: &StageVerdict,
+) -> Result<(), RunError> {
+    let stage = format!("{}:{}", RoleKind::Review.as_str(), verdict.stage);
+    let id = Registry::stage_row_id(ctx.run_id, &stage);
+    // Exec stages run commands, not models, so they use no tokens.
+    let (tokens_in, tokens_out) = match verdict.kind {
+        ReviewStageKind::Exec => (0, 0),
+        ReviewStageKind::Llm | ReviewStageKind::Arbiter => (
+            estimate_file_tokens(&verdict.exec.prompt_path),
+            estimate_file_tokens(&verdict.exec.stdout_path),
+        ),
+    };
+    ctx.registry.record_stage(&StageRecord {
+        id: id.clone(),
+        task_id: &ctx.task.id,
+        stage: &stage,
+        started_at: &verdict.started_at,
+        finished_at: &verdict.finished_at,
+        model: verdict.exec.model.as_deref(),
+        profile: ctx.runners.profile.as_deref(),
+        tokens_in,
+        tokens_out,
+        success: verdict.passed,
+        meta: json!({
+            "run_id": ctx.run_id,
+            "pipeline": pipeline,
+            "kind": verdict.kind,
+            "runner": verdict.runner,
+            "summary": verdict.summary,
+            "warnings": verdict.warnings,
+            "duration_ms": verdict.exec.duration_ms,
+        }),
+    })?;
+    if let Some(dir) = verdict.exec.stdout_path.parent() {
+        record_artifacts(ctx, &id, dir)?;
+    }
+    Ok(())
+}
+
+/// Opens the registry row of a stage before it runs.
+fn start_stage_row(ctx: &RunContext<'_>, role: RoleKind) -> Result<String, RunError> {
+    let runner = match role {
+        RoleKind::Plan => &ctx.runners.plan,
+        RoleKind::Code => &ctx.runners.code,
+        RoleKind::Review => &ctx.runners.review,
+    };
+    let id = Registry::stage_row_id(ctx.run_id, role.as_str());
+    ctx.registry.start_stage(&StageStart {
+        id: id.clone(),
+        task_id: &ctx.task.id,
+        stage: role.as_str(),
+        started_at: &current_timestamp(),
+        model: ctx
+            .config
+            .runner(runner)
+            .and_then(|runner| runner.model.as_deref()),
+        profile: ctx.runners.profile.as_deref(),
+        meta: json!({ "run_id": ctx.run_id, "runner": runner }),
+    })?;
+    Ok(id)
+}
+
+/// Closes the registry row of a stage and registers the files it wrote.
+fn finish_stage_row(
+    ctx: &RunContext<'_>,
+    id: &str,
+    role: RoleKind,
+    outcome: Option<&StageOutcome>,
+    success: bool,
+    meta: Value,
+) -> Result<(), RunError> {
+    ctx.registry.finish_stage(&StageFinish {
+        id,
+        finished_at: &current_timestamp(),
+        model: outcome.and_then(|outcome| outcome.model.as_deref()),
+        tokens_in: outcome.map_or(0, |outcome| outcome.tokens_in),
+        tokens_out: outcome.map_or(0, |outcome| outcome.tokens_out),
+        success,
+        meta,
+    })?;
+    let dir = ctx.workspace.run_stage_dir(ctx.run_id, role.as_str())?;
+    record_artifacts(ctx, id, &dir)
+}
+
+/// Registers every file directly inside `dir` as an artifact of a stage row.
+fn record_artifacts(ctx: &RunContext<'_>, stage_id: &str, dir: &Path) -> Result<(), RunError> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    for file in files {
+        let name = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let path = file
+            .strip_prefix(ctx.workspace.project_root())
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        ctx.registry
+            .record_artifact(stage_id, artifact_kind(name), &path, &file)?;
+    }
+    Ok(())
+}
+
+/// Classifies a stage output file by its well-known name.
+fn artifact_kind(file_name: &str) -> &'static str {
+    match file_name {
+        "prompt.md" => "prompt",
+        "stdout.log" => "stdout",
+        "stderr.log" => "stderr",
+        "result.json" => "result",
+        PATCH_FILE_NAME => "patch",
+        LIMITS_FILE_NAME => "limits",
+        review::VERDICT_FILE_NAME => "verdict",
+        review::VERDICTS_FILE_NAME => "verdicts",
+        _ => "file",
+    }
+}
+
+fn estimate_file_tokens(path: &Path) -> u64 {
+    fs::read_to_string(path)
+        .map(|text| estimate_tokens(&text))
+        .unwrap_or(0)
+}
+
+fn write_run_summary(
+    ctx: &RunContext<'_>,
+    started_at: &str,
+    progress: &RunProgress,
+    result: &Result<(), RunError>,
+) -> Result<(), RunError> {
+    let dir = ctx.workspace.run_dir(ctx.run_id)?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create run directory {}", dir.display()))?;
+
+    let summary = json!({
+        "run_id": ctx.run_id,
+        "task_id": ctx.task.id,
+        "profile": ctx.runners.profile,
+        "runners": {
+            "plan": ctx.runners.plan,
+            "code": ctx.runners.code,
+            "review": ctx.runners.review,
+        },
+        "started_at": started_at,
+        "finished_at": current_timestamp(),
+        "success": result.is_ok(),
+        "error": result.as_ref().err().map(ToString::to_string),
+        "stages": progress.stages.iter().map(|outcome| &outcome.summary).collect::<Vec<_>>(),
+        "apply": progress.apply,
+    });
+    write_artifact(
+        &dir.join("run.json"),
+        metadata::to_pretty_json(&summary)?.as_bytes(),
+    )
+}
+
+fn write_artifact(path: &Path, contents: &[u8]) -> Result<(), RunError> {
+    fs::write(path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))
+        .map_err(RunError::Other)
+}
+
+fn check_for_interrupt() -> Result<(), RunError> {
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        Err(RunError::Interrupted)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_ids_are_unique_components() {
+        let first = new_run_id();
+        let second = new_run_id();
+        assert_ne!(first, second);
+        assert!(first.starts_with("run_"));
+        assert!(!first.contains('/'));
+    }
+}
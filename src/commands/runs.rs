@@ -0,0 +1,422 @@
+use std::{collections::BTreeMap, fs};
+
+use anyhow::Context;
+use clap::{Args, Subcommand};
+use serde::Serialize;
+use serde_json::{Value, json};
+use thiserror::Error;
+use time::{
+    Date, OffsetDateTime, UtcOffset, format_description::well_known::Rfc3339,
+    macros::format_description,
+};
+
+use crate::{
+    metadata::to_pretty_json,
+    registry::{ArtifactRow, Registry, StageRow},
+    workspace::Workspace,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct RunsCliArgs {
+    #[command(subcommand)]
+    pub command: RunsCommand,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum RunsCommand {
+    /// List recorded runs, newest first
+    List(RunsListArgs),
+    /// Show the stages, verdicts and artifacts of a run
+    Show {
+        /// Identifier of the run (`run_…`)
+        run_id: String,
+
+        /// Print the run as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RunsListArgs {
+    /// Only runs of this task
+    #[arg(long)]
+    pub task: Option<String>,
+
+    /// Only runs that executed this stage (e.g. `code` or `review:lint`)
+    #[arg(long)]
+    pub stage: Option<String>,
+
+    /// Only runs where a stage used this model
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// Only successful (`true`) or failed (`false`) runs; with `--stage`, the
+    /// outcome of that stage
+    #[arg(long)]
+    pub success: Option<bool>,
+
+    /// Only runs started on or after this date (`YYYY-MM-DD` or RFC 3339)
+    #[arg(long, value_parser = parse_date_bound)]
+    pub since: Option<String>,
+
+    /// Only runs started on or before this date (`YYYY-MM-DD` or RFC 3339)
+    #[arg(long, value_parser = parse_date_bound)]
+    pub until: Option<String>,
+
+    /// Maximum number of runs to list
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Print runs as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum RunsError {
+    #[error("workspace is not initialized at {path}; run `alisa init` first")]
+    WorkspaceMissing { path: String },
+    #[error("run '{0}' not found")]
+    UnknownRun(String),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Stage rows of one run grouped from the registry.
+#[derive(Debug, Serialize)]
+struct RunEntry {
+    run_id: String,
+    task_id: String,
+    started_at: String,
+    finished_at: Option<String>,
+    /// Outcome from `run.json`, else every top-level stage (plan, code,
+    /// review) finished and succeeded.
+    success: bool,
+    tokens_in: u64,
+    tokens_out: u64,
+    stages: Vec<StageRow>,
+}
+
+impl RunEntry {
+    fn from_rows(run_id: String, stages: Vec<StageRow>) -> Self {
+        let finished = stages.iter().all(|stage| stage.finished_at.is_some());
+        Self {
+            task_id: stages[0].task_id.clone(),
+            started_at: stages[0].started_at.clone(),
+            finished_at: stages
+                .iter()
+                .filter_map(|stage| stage.finished_at.clone())
+                .max()
+                .filter(|_| finished),
+            // `review:<stage>` rows are single verdicts; the `review` row
+            // carries the consensus.
+            success: finished
+                && stages
+                    .iter()
+                    .filter(|stage| !stage.stage.contains(':'))
+                    .all(|stage| stage.success),
+            tokens_in: stages.iter().map(|stage| stage.tokens_in).sum(),
+            tokens_out: stages.iter().map(|stage| stage.tokens_out).sum(),
+            run_id,
+            stages,
+        }
+    }
+
+    /// Takes the outcome recorded in `run.json`, which also covers apply.
+    fn settle(&mut self, summary: &Value) {
+        if let Some(success) = summary["success"].as_bool() {
+            self.success = success;
+        }
+        if self.finished_at.is_none() {
+            self.finished_at = summary["finished_at"].as_str().map(str::to_string);
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        match (&self.finished_at, self.success) {
+            (None, _) => "running",
+            (Some(_), true) => "ok",
+            (Some(_), false) => "failed",
+        }
+    }
+}
+
+impl RunsListArgs {
+    fn matches(&self, run: &RunEntry) -> bool {
+        let stage_matches = |stage: &StageRow| {
+            self.stage.as_deref().is_none_or(|name| stage.stage == name)
+                && self
+                    .model
+                    .as_deref()
+                    .is_none_or(|model| stage.model.as_deref() == Some(model))
+        };
+        let success_matches = |stage: &StageRow| {
+            self.stage.is_none() || self.success.is_none_or(|success| stage.success == success)
+        };
+        run.stages
+            .iter()
+            .any(|stage| stage_matches(stage) && success_matches(stage))
+            && (self.stage.is_some() || self.success.is_none_or(|success| run.success == success))
+            && self.started_within_bounds(&run.started_at)
+    }
+
+    fn started_within_bounds(&self, started_at: &str) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        let Ok(started) = OffsetDateTime::parse(started_at, &Rfc3339) else {
+            return false;
+        };
+        let started = started.to_offset(UtcOffset::UTC);
+        self.since
+            .as_deref()
+            .is_none_or(|since| match parse_day(since) {
+                Some(day) => started.date() >= day,
+                None => OffsetDateTime::parse(since, &Rfc3339).is_ok_and(|since| started >= since),
+            })
+            && self
+                .until
+                .as_deref()
+                .is_none_or(|until| match parse_day(until) {
+                    Some(day) => started.date() <= day,
+                    None => {
+                        OffsetDateTime::parse(until, &Rfc3339).is_ok_and(|until| started <= until)
+                    }
+                })
+    }
+}
+
+pub fn run(args: &RunsCliArgs) -> Result<(), RunsError> {
+    let workspace = Workspace::detect_from_cwd()?;
+    if !workspace.workspace_root().exists() {
+        return Err(RunsError::WorkspaceMissing {
+            path: workspace.workspace_root().display().to_string(),
+        });
+    }
+    let registry = Registry::open(&workspace.registry_path())?;
+
+    match &args.command {
+        RunsCommand::List(list) => {
+            let mut runs = group_runs(registry.stage_rows(list.task.as_deref())?);
+            for run in &mut runs {
+                run.settle(&read_run_summary(&workspace, &run.run_id)?);
+            }
+            runs.retain(|run| list.matches(run));
+            runs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+            runs.truncate(list.limit);
+            if list.json {
+                println!("{}", to_pretty_json(&json!(runs))?);
+            } else {
+                print_run_list(&runs);
+            }
+            Ok(())
+        }
+        RunsCommand::Show { run_id, json } => {
+            let rows = registry.run_stage_rows(run_id)?;
+            if rows.is_empty() {
+                return Err(RunsError::UnknownRun(run_id.clone()));
+            }
+            let mut run = RunEntry::from_rows(run_id.clone(), rows);
+            let artifacts = registry.run_artifacts(run_id)?;
+            let summary = read_run_summary(&workspace, run_id)?;
+            run.settle(&summary);
+            if *json {
+                let value = json!({
+                    "run_id": run.run_id,
+                    "task_id": run.task_id,
+                    "profile": summary["profile"],
+                    "runners": summary["runners"],
+                    "started_at": run.started_at,
+                    "finished_at": run.finished_at,
+                    "success": run.success,
+                    "error": summary["error"],
+                    "tokens": { "in": run.tokens_in, "out": run.tokens_out },
+                    "stages": run.stages.iter().map(|stage| {
+                        let mut value = json!(stage);
+                        value["duration_ms"] = json!(duration_ms(stage));
+                        value
+                    }).collect::<Vec<_>>(),
+                    "consensus": review_consensus(&summary),
+                    "apply": summary["apply"],
+                    "artifacts": artifacts,
+                });
+                println!("{}", to_pretty_json(&value)?);
+            } else {
+                print_run(&run, &summary, &artifacts);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn group_runs(rows: Vec<StageRow>) -> Vec<RunEntry> {
+    let mut grouped: BTreeMap<String, Vec<StageRow>> = BTreeMap::new();
+    for row in rows {
+        grouped.entry(row.run_id.clone()).or_default().push(row);
+    }
+    grouped
+        .into_iter()
+        .map(|(run_id, stages)| RunEntry::from_rows(run_id, stages))
+        .collect()
+}
+
+/// Reads `run.json`; runs interrupted before writing it yield `null`.
+fn read_run_summary(workspace: &Workspace, run_id: &str) -> Result<Value, RunsError> {
+    let path = workspace.run_dir(run_id)?.join("run.json");
+    match fs::read(&path) {
+        Ok(data) => Ok(serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse {}", path.display()))?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Value::Null),
+        Err(err) => Err(anyhow::Error::new(err)
+            .context(format!("Failed to read {}", path.display()))
+            .into()),
+    }
+}
+
+fn review_consensus(summary: &Value) -> Value {
+    summary["stages"]
+        .as_array()
+        .and_then(|stages| stages.iter().find_map(|stage| stage.get("consensus")))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn duration_ms(stage: &StageRow) -> Option<i128> {
+    let parse = |value: &str| OffsetDateTime::parse(value, &Rfc3339).ok();
+    let started = parse(&stage.started_at)?;
+    let finished = parse(stage.finished_at.as_deref()?)?;
+    Some((finished - started).whole_milliseconds())
+}
+
+/// Accepts `YYYY-MM-DD` or an RFC 3339 timestamp for `--since`/`--until`.
+///
+/// Timestamps are converted to UTC, the zone every recorded time uses; a
+/// bare date names a UTC day and is kept as is.
+pub(super) fn parse_date_bound(value: &str) -> Result<String, String> {
+    if parse_day(value).is_some() {
+        return Ok(value.to_string());
+    }
+    OffsetDateTime::parse(value, &Rfc3339)
+        .ok()
+        .and_then(|at| at.to_offset(UtcOffset::UTC).format(&Rfc3339).ok())
+        .ok_or_else(|| {
+            format!("invalid date '{value}' (expected YYYY-MM-DD or an RFC 3339 timestamp)")
+        })
+}
+
+fn parse_day(value: &str) -> Option<Date> {
+    Date::parse(value, format_description!("[year]-[month]-[day]")).ok()
+}
+
+fn print_run_list(runs: &[RunEntry]) {
+    if runs.is_empty() {
+        println!("No runs found.");
+        return;
+    }
+    let task_width = runs.iter().map(|run| run.task_id.len()).max().unwrap_or(0);
+    for run in runs {
+        let stages: Vec<String> = run
+            .stages
+            .iter()
+            .filter(|stage| !stage.stage.contains(':'))
+            .map(|stage| format!("{}:{}", stage.stage, stage_status(stage)))
+            .collect();
+        println!(
+            "{}  {:<task_width$}  {}  {:<7}  {}  tokens {}/{}",
+            run.run_id,
+            run.task_id,
+            run.started_at,
+            run.status(),
+            stages.join(" "),
+            run.tokens_in,
+            run.tokens_out
+        );
+    }
+}
+
+fn print_run(run: &RunEntry, summary: &Value, artifacts: &[ArtifactRow]) {
+    println!("run:       {}", run.run_id);
+    println!("task:      {}", run.task_id);
+    println!(
+        "profile:   {}",
+        summary["profile"].as_str().unwrap_or("<none>")
+    );
+    if let Some(runners) = summary["runners"].as_object() {
+        let runners: Vec<String> = runners
+            .iter()
+            .map(|(role, runner)| format!("{role}={}", runner.as_str().unwrap_or("?")))
+            .collect();
+        println!("runners:   {}", runners.join(", "));
+    }
+    println!("started:   {}", run.started_at);
+    println!(
+        "finished:  {}",
+        run.finished_at.as_deref().unwrap_or("<not finished>")
+    );
+    match summary["error"].as_str() {
+        Some(error) => println!("status:    failed: {error}"),
+        None => println!("status:    {}", run.status()),
+    }
+    println!("tokens:    {} in / {} out", run.tokens_in, run.tokens_out);
+    if let Some(apply) = summary["apply"]["status"].as_str() {
+        println!("apply:     {apply}");
+    }
+
+    println!("\nStages:");
+    let width = run
+        .stages
+        .iter()
+        .map(|stage| stage.stage.len())
+        .max()
+        .unwrap_or(0);
+    for stage in &run.stages {
+        let mut line = format!(
+            "  {:<width$}  {:<7}  {:>8}  tokens {}/{}",
+            stage.stage,
+            stage_status(stage),
+            duration_ms(stage).map_or("-".to_string(), |ms| format!("{ms} ms")),
+            stage.tokens_in,
+            stage.tokens_out
+        );
+        if let Some(model) = &stage.model {
+            line.push_str(&format!("  model {model}"));
+        }
+        if let Some(verdict) = stage.meta["summary"].as_str() {
+            line.push_str(&format!("  — {verdict}"));
+        }
+        println!("{line}");
+    }
+    let consensus = review_consensus(summary);
+    if let Some(reason) = consensus["reason"].as_str() {
+        println!(
+            "  consensus {}: {reason}",
+            if consensus["passed"].as_bool() == Some(true) {
+                "passed"
+            } else {
+                "failed"
+            }
+        );
+    }
+
+    if !artifacts.is_empty() {
+        println!("\nArtifacts:");
+        for artifact in artifacts {
+            let sha = artifact.sha256.as_deref().unwrap_or("-");
+            println!(
+                "  {:<8}  {}  {}",
+                artifact.kind,
+                &sha[..sha.len().min(12)],
+                artifact.path
+            );
+        }
+    }
+}
+
+fn stage_status(stage: &StageRow) -> &'static str {
+    match (&stage.finished_at, stage.success) {
+        (None, _) => "running",
+        (Some(_), true) => "ok",
+        (Some(_), false) => "failed",
+    }
+}
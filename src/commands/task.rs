@@ -0,0 +1,390 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use serde_json::json;
+use thiserror::Error;
+
+use super::{
+    LockPolicy, WorkspaceLockError, WorkspaceLockStatus, acquire_workspace_lock, config_files,
+};
+use crate::{
+    config::ConfigError,
+    metadata::to_pretty_json,
+    registry::{Registry, TaskSearchFilter, TaskSearchHit},
+    tasks::{Task, TaskContext, TaskDocument, TaskEditError, TaskLoadError, TaskSet, TaskStatus},
+    workspace::{Workspace, WorkspaceLock},
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct TaskCliArgs {
+    #[command(subcommand)]
+    pub command: TaskCommand,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum TaskCommand {
+    /// List tasks from the tasks file
+    List(TaskListArgs),
+    /// Show every field of a task
+    Show {
+        /// Identifier of the task
+        id: String,
+    },
+    /// Append a new task to the tasks file
+    Add(TaskAddArgs),
+    /// Change the status of a task
+    SetStatus {
+        /// Identifier of the task
+        id: String,
+        /// New status: todo, doing, done or blocked
+        status: TaskStatus,
+    },
+    /// Print the most urgent task that is ready to start
+    Next,
+    /// Copy the tasks file into the workspace registry
+    Sync,
+    /// Full-text search over task titles and descriptions
+    Search(TaskSearchArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TaskListArgs {
+    /// Only list tasks with this status
+    #[arg(long)]
+    pub status: Option<TaskStatus>,
+
+    /// Only list tasks carrying this tag (repeat to require several)
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// Only list tasks for this language
+    #[arg(long)]
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TaskSearchArgs {
+    /// FTS5 query, e.g. `login AND (token OR session)` or `"exact phrase"`
+    pub query: String,
+
+    /// Only return tasks with this status
+    #[arg(long)]
+    pub status: Option<TaskStatus>,
+
+    /// Only return tasks carrying this tag (repeat to require several)
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// Also search tasks that were removed from the tasks file
+    #[arg(long)]
+    pub archived: bool,
+
+    /// Maximum number of results
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Print results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TaskAddArgs {
+    /// Identifier of the new task
+    pub id: String,
+
+    /// Short title of the new task
+    pub title: String,
+
+    /// Longer description
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Initial status
+    #[arg(long, default_value = "todo")]
+    pub status: TaskStatus,
+
+    /// Priority label
+    #[arg(long)]
+    pub priority: Option<String>,
+
+    /// Language hint used by routing rules
+    #[arg(long)]
+    pub lang: Option<String>,
+
+    /// Tag to attach (repeatable)
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// Task that must be done first (repeatable)
+    #[arg(long = "depends-on")]
+    pub depends_on: Vec<String>,
+
+    /// Acceptance criterion (repeatable)
+    #[arg(long = "acceptance")]
+    pub acceptance: Vec<String>,
+}
+
+impl TaskAddArgs {
+    fn to_task(&self) -> Task {
+        Task {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            status: self.status,
+            priority: self.priority.clone(),
+            lang: self.lang.clone(),
+            depends_on: self.depends_on.clone(),
+            tags: self.tags.clone(),
+            description: self.description.clone(),
+            acceptance: self.acceptance.clone(),
+            context: TaskContext::default(),
+            llm: None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TaskError {
+    #[error("workspace is not initialized at {path}; run `alisa init` first")]
+    WorkspaceMissing { path: String },
+    #[error("workspace lock at {lock_path} is held by another process")]
+    WorkspaceLocked { lock_path: String },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Tasks(#[from] TaskLoadError),
+    #[error(transparent)]
+    Edit(#[from] TaskEditError),
+    #[error("task '{0}' not found")]
+    UnknownTask(String),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub fn run(args: &TaskCliArgs) -> Result<(), TaskError> {
+    let workspace = Workspace::detect_from_cwd()?;
+    let tasks_path = tasks_path(&workspace)?;
+
+    match &args.command {
+        TaskCommand::List(list) => {
+            let tasks = TaskSet::from_path(&tasks_path)?;
+            print_task_list(tasks.tasks.iter().filter(|task| list.matches(task)));
+            Ok(())
+        }
+        TaskCommand::Show { id } => {
+            let tasks = TaskSet::from_path(&tasks_path)?;
+            let task = tasks
+                .find(id)
+                .ok_or_else(|| TaskError::UnknownTask(id.clone()))?;
+            print_task(task);
+            Ok(())
+        }
+        TaskCommand::Next => {
+            let tasks = TaskSet::from_path(&tasks_path)?;
+            match tasks.next_ready() {
+                Some(task) => println!("{}", task_line(task, task.id.len())),
+                None => println!("No ready tasks."),
+            }
+            Ok(())
+        }
+        TaskCommand::Sync => {
+            let _lock = lock_workspace(&workspace)?;
+            let tasks = TaskSet::from_path(&tasks_path)?;
+            let report = Registry::open(&workspace.registry_path())?.sync_tasks(&tasks.tasks)?;
+            println!(
+                "[ok] Synced {} task(s) into the registry: {} added, {} updated, {} archived",
+                tasks.tasks.len(),
+                report.added,
+                report.updated,
+                report.archived
+            );
+            Ok(())
+        }
+        TaskCommand::Search(search) => {
+            let registry = open_registry(&workspace)?;
+            refresh_registry(&workspace, &registry, &tasks_path)?;
+            let filter = TaskSearchFilter {
+                status: search.status.map(|status| status.as_str()),
+                tags: &search.tags,
+                include_archived: search.archived,
+                limit: search.limit,
+            };
+            let hits = registry.search_tasks(&search.query, &filter)?;
+            if search.json {
+                println!("{}", to_pretty_json(&json!(hits))?);
+            } else {
+                print_search_hits(&hits);
+            }
+            Ok(())
+        }
+        TaskCommand::Add(add) => {
+            let _lock = lock_workspace(&workspace)?;
+            let mut doc = TaskDocument::open(&tasks_path)?;
+            doc.append(&add.to_task())?;
+            let tasks = doc.save()?;
+            sync_registry(&workspace, &tasks)?;
+            println!("[ok] Added task {} to {}", add.id, tasks_path.display());
+            Ok(())
+        }
+        TaskCommand::SetStatus { id, status } => {
+            let _lock = lock_workspace(&workspace)?;
+            let mut doc = TaskDocument::open(&tasks_path)?;
+            doc.set_status(id, *status)?;
+            let tasks = doc.save()?;
+            sync_registry(&workspace, &tasks)?;
+            println!("[ok] Task {id} is now {status}");
+            Ok(())
+        }
+    }
+}
+
+/// Resolves the tasks file from the project configuration.
+fn tasks_path(workspace: &Workspace) -> Result<PathBuf, TaskError> {
+    let config = config_files(workspace)?.load()?.config;
+    Ok(workspace.project_root().join(&config.paths.tasks_file))
+}
+
+/// Keeps the registry in step after an edit; skipped before `alisa init`.
+fn sync_registry(workspace: &Workspace, tasks: &TaskSet) -> Result<(), TaskError> {
+    let path = workspace.registry_path();
+    if path.exists() {
+        Registry::open(&path)?.sync_tasks(&tasks.tasks)?;
+    }
+    Ok(())
+}
+
+fn open_registry(workspace: &Workspace) -> Result<Registry, TaskError> {
+    if !workspace.workspace_root().exists() {
+        return Err(TaskError::WorkspaceMissing {
+            path: workspace.workspace_root().display().to_string(),
+        });
+    }
+    Ok(Registry::open(&workspace.registry_path())?)
+}
+
+/// Syncs the tasks file before a query unless another process holds the lock;
+/// in that case the registry is used as last synced.
+fn refresh_registry(
+    workspace: &Workspace,
+    registry: &Registry,
+    tasks_path: &Path,
+) -> Result<(), TaskError> {
+    match acquire_workspace_lock(workspace, LockPolicy::Optional) {
+        Ok(WorkspaceLockStatus::Acquired(_guard)) => {
+            registry.sync_tasks(&TaskSet::from_path(tasks_path)?.tasks)?;
+            Ok(())
+        }
+        Ok(WorkspaceLockStatus::Skipped) | Err(WorkspaceLockError::AlreadyLocked) => Ok(()),
+        Err(WorkspaceLockError::Other(err)) => Err(TaskError::Other(err)),
+    }
+}
+
+fn lock_workspace(workspace: &Workspace) -> Result<WorkspaceLock, TaskError> {
+    if !workspace.workspace_root().exists() {
+        return Err(TaskError::WorkspaceMissing {
+            path: workspace.workspace_root().display().to_string(),
+        });
+    }
+    match acquire_workspace_lock(workspace, LockPolicy::Required) {
+        Ok(WorkspaceLockStatus::Acquired(guard)) => Ok(guard),
+        Ok(WorkspaceLockStatus::Skipped) => unreachable!("required lock policy never skips"),
+        Err(WorkspaceLockError::AlreadyLocked) => Err(TaskError::WorkspaceLocked {
+            lock_path: workspace.lock_path().display().to_string(),
+        }),
+        Err(WorkspaceLockError::Other(err)) => Err(TaskError::Other(err)),
+    }
+}
+
+impl TaskListArgs {
+    fn matches(&self, task: &Task) -> bool {
+        self.status.is_none_or(|status| task.status == status)
+            && self.tags.iter().all(|tag| task.tags.contains(tag))
+            && self
+                .lang
+                .as_deref()
+                .is_none_or(|lang| task.lang.as_deref() == Some(lang))
+    }
+}
+
+fn print_task_list<'a>(tasks: impl Iterator<Item = &'a Task>) {
+    let tasks: Vec<&Task> = tasks.collect();
+    if tasks.is_empty() {
+        println!("No tasks found.");
+        return;
+    }
+    let id_width = tasks.iter().map(|task| task.id.len()).max().unwrap_or(0);
+    for task in tasks {
+        println!("{}", task_line(task, id_width));
+    }
+}
+
+fn task_line(task: &Task, id_width: usize) -> String {
+    let mut line = format!(
+        "{:<id_width$}  {:<7}  {}",
+        task.id,
+        task.status.as_str(),
+        task.title
+    );
+    if let Some(priority) = &task.priority {
+        line.push_str(&format!("  (priority: {priority})"));
+    }
+    if !task.tags.is_empty() {
+        line.push_str(&format!("  [{}]", task.tags.join(", ")));
+    }
+    line
+}
+
+fn print_search_hits(hits: &[TaskSearchHit]) {
+    if hits.is_empty() {
+        println!("No matching tasks.");
+        return;
+    }
+    let id_width = hits.iter().map(|hit| hit.id.len()).max().unwrap_or(0);
+    for hit in hits {
+        println!(
+            "{:<id_width$}  {:<8}  {}  (score {:.2})",
+            hit.id, hit.status, hit.title, hit.score
+        );
+        if !hit.snippet.is_empty() {
+            println!("{:<id_width$}  {}", "", hit.snippet);
+        }
+    }
+}
+
+fn print_task(task: &Task) {
+    println!("{}: {}", task.id, task.title);
+    println!("status:      {}", task.status);
+    let optional = [
+        ("priority:", task.priority.as_deref()),
+        ("lang:", task.lang.as_deref()),
+    ];
+    for (label, value) in optional {
+        if let Some(value) = value {
+            println!("{label:<12} {value}");
+        }
+    }
+    let lists = [
+        ("tags:", &task.tags),
+        ("depends on:", &task.depends_on),
+        ("code:", &task.context.code),
+        ("docs:", &task.context.docs),
+        ("scope:", &task.context.scope),
+    ];
+    for (label, values) in lists {
+        if !values.is_empty() {
+            println!("{label:<12} {}", values.join(", "));
+        }
+    }
+    for (key, value) in &task.context.extra {
+        println!("{:<12} {value}", format!("{key}:"));
+    }
+    if let Some(description) = &task.description {
+        println!("\n{}", description.trim_end());
+    }
+    if !task.acceptance.is_empty() {
+        println!("\nAcceptance criteria:");
+        for item in &task.acceptance {
+            println!("  - {item}");
+        }
+    }
+}
@@ -1,8 +1,9 @@
 #![allow(dead_code)]
 
 use std::{
+    cmp::Reverse,
     collections::{BTreeMap, HashMap},
-    fs,
+    fmt, fs,
     path::{Path, PathBuf},
 };
 
@@ -10,6 +11,14 @@ use globset::Glob;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::tasks::{TaskStatus, priority_rank};
+
+mod diagnostics;
+mod layers;
+
+pub use diagnostics::{Diagnostic, Severity, diagnose};
+pub use layers::{ConfigFiles, ConfigOrigin, env_overrides};
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("failed to read config at {path}: {source}")]
@@ -18,6 +27,30 @@ pub enum ConfigError {
     ParseFailed { context: String, source: toml::de::Error },
     #[error("invalid config: {0}")]
     Invalid(String),
+    #[error("environment variable {0} is not valid UTF-8")]
+    EnvNotUnicode(String),
+}
+
+/// A validation problem and the key path it concerns, e.g.
+/// `["roles", "code"]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub path: Vec<String>,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn new<S: AsRef<str>>(path: &[S], message: String) -> Self {
+        Self {
+            path: path.iter().map(|key| key.as_ref().to_string()).collect(),
+            message,
+        }
+    }
+
+    /// The key path in TOML notation, e.g. `review.stages."llm.criteria"`.
+    pub fn key(&self) -> String {
+        layers::key_path(&self.path)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -65,24 +98,52 @@ impl Config {
     }
 
     pub fn validate(&self) -> Result<(), ConfigError> {
+        let issues = self.issues();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            let messages: Vec<_> = issues.into_iter().map(|issue| issue.message).collect();
+            Err(ConfigError::Invalid(messages.join("; ")))
+        }
+    }
+
+    /// Every problem `validate` would report, with the key each one is about.
+    pub fn issues(&self) -> Vec<ConfigIssue> {
         let mut issues = Vec::new();
 
         if self.runners.is_empty() {
-            issues.push("at least one runner must be defined".to_string());
+            issues.push(ConfigIssue::new(
+                &["runners"],
+                "at least one runner must be defined".to_string(),
+            ));
         }
 
-        for (name, runner) in &self.runners {
+        let mut runners: Vec<_> = self.runners.iter().collect();
+        runners.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, runner) in runners {
             if runner.cmd.trim().is_empty() {
-                issues.push(format!("runner '{}' must define non-empty cmd", name));
+                issues.push(ConfigIssue::new(
+                    &["runners", name, "cmd"],
+                    format!("runner '{}' must define non-empty cmd", name),
+                ));
+            }
+            if runner.timeout_ms == Some(0) {
+                issues.push(ConfigIssue::new(
+                    &["runners", name, "timeout_ms"],
+                    format!("runner '{}' must define a positive timeout_ms", name),
+                ));
             }
         }
 
         for (role, runner) in self.roles.configured_entries() {
             if !self.runners.contains_key(runner) {
-                issues.push(format!(
-                    "role '{}' references unknown runner '{}'",
-                    role.as_str(),
-                    runner
+                issues.push(ConfigIssue::new(
+                    &["roles", role.as_str()],
+                    format!(
+                        "role '{}' references unknown runner '{}'",
+                        role.as_str(),
+                        runner
+                    ),
                 ));
             }
         }
@@ -90,109 +151,226 @@ impl Config {
         for (profile_name, profile) in &self.profiles {
             for (role, runner) in profile.roles.configured_entries() {
                 if !self.runners.contains_key(runner) {
-                    issues.push(format!(
-                        "profile '{}' role '{}' references unknown runner '{}'",
-                        profile_name,
-                        role.as_str(),
-                        runner
+                    issues.push(ConfigIssue::new(
+                        &["profiles", profile_name, "roles", role.as_str()],
+                        format!(
+                            "profile '{}' role '{}' references unknown runner '{}'",
+                            profile_name,
+                            role.as_str(),
+                            runner
+                        ),
                     ));
                 }
             }
         }
 
         for (idx, rule) in self.routing.iter().enumerate() {
+            let index = idx.to_string();
             if !self.runners.contains_key(&rule.use_runner) {
-                issues.push(format!(
-                    "routing rule #{} for role '{}' references unknown runner '{}'",
-                    idx,
-                    rule.role.as_str(),
-                    rule.use_runner
+                issues.push(ConfigIssue::new(
+                    &["routing", &index, "use"],
+                    format!(
+                        "routing rule #{} for role '{}' references unknown runner '{}'",
+                        idx,
+                        rule.role.as_str(),
+                        rule.use_runner
+                    ),
                 ))
             }
-            if let Some(profile) = &rule.profile {
-                if !self.profiles.contains_key(profile) {
-                    issues.push(format!(
+            if let Some(profile) = &rule.profile
+                && !self.profiles.contains_key(profile)
+            {
+                issues.push(ConfigIssue::new(
+                    &["routing", &index, "profile"],
+                    format!(
                         "routing rule #{} references unknown profile '{}'",
                         idx, profile
-                    ));
-                }
-            }
-            if let Some(pattern) = &rule.when.path {
-                if let Err(err) = Glob::new(pattern) {
-                    issues.push(format!(
-                        "routing rule #{} has invalid path glob '{}': {}",
-                        idx, pattern, err
-                    ));
-                }
-            }
-            if let Some(pattern) = &rule.when.task_id {
-                if let Err(err) = Glob::new(pattern) {
-                    issues.push(format!(
-                        "routing rule #{} has invalid task_id glob '{}': {}",
-                        idx, pattern, err
-                    ));
-                }
+                    ),
+                ));
             }
+            condition_issues(
+                idx,
+                &rule.when,
+                &["routing".to_string(), index, "when".to_string()],
+                &mut issues,
+                &mut Vec::new(),
+            );
         }
 
-        if let Some(default_pipeline) = &self.review.default_pipeline {
-            if !self.review.pipelines.contains_key(default_pipeline) {
-                issues.push(format!(
+        if let Some(default_pipeline) = &self.review.default_pipeline
+            && !self.review.pipelines.contains_key(default_pipeline)
+        {
+            issues.push(ConfigIssue::new(
+                &["review", "default_pipeline"],
+                format!(
                     "review.default_pipeline '{}' is not defined",
                     default_pipeline
-                ));
-            }
+                ),
+            ));
         }
 
         for (name, pipeline) in &self.review.pipelines {
+            let key = |field: &str| ["review", "pipelines", name.as_str(), field].map(String::from);
             if pipeline.stages.is_empty() {
-                issues.push(format!(
-                    "review pipeline '{}' must list at least one stage",
-                    name
+                issues.push(ConfigIssue::new(
+                    &key("stages"),
+                    format!("review pipeline '{}' must list at least one stage", name),
                 ));
             }
             for stage_name in &pipeline.stages {
                 if !self.review.stages.contains_key(stage_name) {
-                    issues.push(format!(
-                        "review pipeline '{}' references undefined stage '{}'",
-                        name, stage_name
+                    issues.push(ConfigIssue::new(
+                        &key("stages"),
+                        format!(
+                            "review pipeline '{}' references undefined stage '{}'",
+                            name, stage_name
+                        ),
                     ));
                 }
             }
+            for stage_name in &pipeline.fail_on {
+                if !pipeline.stages.contains(stage_name) {
+                    issues.push(ConfigIssue::new(
+                        &key("fail_on"),
+                        format!(
+                            "review pipeline '{}' lists fail_on stage '{}' that is not part of the pipeline",
+                            name, stage_name
+                        ),
+                    ));
+                }
+            }
+            let mut weighted: Vec<_> = pipeline.weights.iter().collect();
+            weighted.sort_by(|a, b| a.0.cmp(b.0));
+            for (stage_name, weight) in weighted {
+                let weight_key = ["review", "pipelines", name, "weights", stage_name];
+                if !pipeline.stages.contains(stage_name) {
+                    issues.push(ConfigIssue::new(
+                        &weight_key,
+                        format!(
+                            "review pipeline '{}' has a weight for unknown stage '{}'",
+                            name, stage_name
+                        ),
+                    ));
+                }
+                if !weight.is_finite() || *weight < 0.0 {
+                    issues.push(ConfigIssue::new(
+                        &weight_key,
+                        format!(
+                            "review pipeline '{}' has invalid weight {} for stage '{}'",
+                            name, weight, stage_name
+                        ),
+                    ));
+                }
+            }
+            if pipeline.consensus() == ReviewConsensus::Weighted && pipeline.weights.is_empty() {
+                issues.push(ConfigIssue::new(
+                    &key("consensus"),
+                    format!(
+                        "review pipeline '{}' uses weighted consensus but defines no weights",
+                        name
+                    ),
+                ));
+            }
+            if let Some(threshold) = pipeline.threshold
+                && !(threshold > 0.0 && threshold <= 1.0)
+            {
+                issues.push(ConfigIssue::new(
+                    &key("threshold"),
+                    format!(
+                        "review pipeline '{}' threshold must be in (0, 1], got {}",
+                        name, threshold
+                    ),
+                ));
+            }
         }
 
         for (stage_name, stage) in &self.review.stages {
             match stage.kind {
                 ReviewStageKind::Exec => {
                     if stage.cmd.as_ref().map(|cmd| cmd.is_empty()).unwrap_or(true) {
-                        issues.push(format!(
-                            "review stage '{}' of type exec must define a non-empty cmd",
-                            stage_name
+                        issues.push(ConfigIssue::new(
+                            &["review", "stages", stage_name, "cmd"],
+                            format!(
+                                "review stage '{}' of type exec must define a non-empty cmd",
+                                stage_name
+                            ),
                         ));
                     }
                 }
                 ReviewStageKind::Llm | ReviewStageKind::Arbiter => {
                     match stage.runner.as_deref() {
                         Some(runner) if self.runners.contains_key(runner) => {}
-                        Some(runner) => issues.push(format!(
-                            "review stage '{}' references unknown runner '{}'",
-                            stage_name, runner
+                        Some(runner) => issues.push(ConfigIssue::new(
+                            &["review", "stages", stage_name, "runner"],
+                            format!(
+                                "review stage '{}' references unknown runner '{}'",
+                                stage_name, runner
+                            ),
                         )),
-                        None => issues.push(format!(
-                            "review stage '{}' of type '{}' must specify runner",
-                            stage_name,
-                            stage.kind.as_str()
+                        None => issues.push(ConfigIssue::new(
+                            &["review", "stages", stage_name, "type"],
+                            format!(
+                                "review stage '{}' of type '{}' must specify runner",
+                                stage_name,
+                                stage.kind.as_str()
+                            ),
                         )),
                     }
                 }
             }
         }
 
-        if issues.is_empty() {
-            Ok(())
-        } else {
-            Err(ConfigError::Invalid(issues.join("; ")))
+        let mut stage_dirs: BTreeMap<String, &String> = BTreeMap::new();
+        for stage_name in self.review.stages.keys() {
+            let dir = review_stage_dir_name(stage_name);
+            if let Some(other) = stage_dirs.get(&dir) {
+                issues.push(ConfigIssue::new(
+                    &["review", "stages", stage_name],
+                    format!(
+                        "review stages '{}' and '{}' would share the output directory '{}'",
+                        other, stage_name, dir
+                    ),
+                ));
+            } else {
+                stage_dirs.insert(dir, stage_name);
+            }
         }
+
+        issues
+    }
+
+    /// Problems worth reporting that do not stop the configuration from
+    /// loading: routing rules that can never be selected and conditions on
+    /// unrecognized priorities.
+    pub fn warnings(&self) -> Vec<ConfigIssue> {
+        let mut warnings = Vec::new();
+        for (index, rule) in self.routing.iter().enumerate() {
+            condition_issues(
+                index,
+                &rule.when,
+                &["routing".to_string(), index.to_string(), "when".to_string()],
+                &mut Vec::new(),
+                &mut warnings,
+            );
+            let shadowed_by = self.routing.iter().enumerate().find(|(other_index, other)| {
+                *other_index != index
+                    && other.role == rule.role
+                    && other.when.covers(&rule.when)
+                    && (other.rank(), Reverse(*other_index)) > (rule.rank(), Reverse(index))
+            });
+            if let Some((other_index, _)) = shadowed_by {
+                warnings.push(ConfigIssue::new(
+                    &["routing", &index.to_string()],
+                    format!(
+                        "routing rule #{} for role '{}' is shadowed by rule #{} and never applies",
+                        index,
+                        rule.role.as_str(),
+                        other_index
+                    ),
+                ));
+            }
+        }
+        warnings
     }
 
     pub fn profile(&self, name: &str) -> Option<&Profile> {
@@ -206,6 +384,14 @@ impl Config {
     pub fn review_stage(&self, name: &str) -> Option<&ReviewStage> {
         self.review.stages.get(name)
     }
+
+    /// Top-level limits with the profile's limits applied field by field.
+    pub fn effective_limits(&self, profile: Option<&str>) -> Limits {
+        match profile.and_then(|name| self.profile(name)) {
+            Some(profile) => self.limits.overridden_by(&profile.limits),
+            None => self.limits,
+        }
+    }
 }
 
 fn default_config_version() -> u32 {
@@ -230,7 +416,7 @@ impl Default for Config {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 pub struct RunnerDef {
     pub cmd: String,
@@ -241,19 +427,19 @@ pub struct RunnerDef {
     pub timeout_ms: Option<u64>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub prompt_input: PromptInput,
 }
 
-impl Default for RunnerDef {
-    fn default() -> Self {
-        Self {
-            cmd: String::new(),
-            args: Vec::new(),
-            model: None,
-            prompt_dir: None,
-            timeout_ms: None,
-            env: HashMap::new(),
-        }
-    }
+/// How a runner receives its rendered prompt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptInput {
+    /// Prompt is written to the runner's stdin.
+    #[default]
+    Stdin,
+    /// Prompt is stored in a file whose path is passed via `{prompt_file}` or appended to args.
+    File,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -299,6 +485,8 @@ pub struct RoutingRule {
     #[serde(rename = "use")]
     pub use_runner: String,
     pub profile: Option<String>,
+    /// Rules with a higher priority win over more specific ones; unset is 0.
+    pub priority: Option<i32>,
 }
 
 impl Default for RoutingRule {
@@ -308,16 +496,284 @@ impl Default for RoutingRule {
             role: RoleKind::Plan,
             use_runner: String::new(),
             profile: None,
+            priority: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+impl RoutingRule {
+    /// Among matching rules the highest rank wins; equal ranks go to the
+    /// rule that comes first in the file.
+    pub fn rank(&self) -> (i32, Specificity) {
+        (self.priority.unwrap_or(0), self.when.specificity())
+    }
+}
+
+/// How narrowly a rule targets tasks. Compared field by field: a `task_id`
+/// glob beats a `path` glob, which beats `lang`, then `profile`, then the
+/// number of other top-level conditions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Specificity {
+    pub task_id: bool,
+    pub path: bool,
+    pub lang: bool,
+    pub profile: bool,
+    pub other: usize,
+}
+
+impl fmt::Display for Specificity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = [
+            ("task_id", self.task_id),
+            ("path", self.path),
+            ("lang", self.lang),
+            ("profile", self.profile),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| name.to_string())
+        .collect();
+        if self.other > 0 {
+            parts.push(format!("{} other", self.other));
+        }
+        if parts.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&parts.join(" + "))
+        }
+    }
+}
+
+/// Conditions of a routing rule; every condition set must hold.
+#[derive(Debug, Clone, Deserialize, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct RoutingConditions {
-    pub lang: Option<String>,
-    pub path: Option<String>,
-    pub task_id: Option<String>,
-    pub profile: Option<String>,
+    pub lang: Option<ConditionValues>,
+    pub path: Option<ConditionValues>,
+    pub task_id: Option<ConditionValues>,
+    pub profile: Option<ConditionValues>,
+    /// Holds when the task has one of the tags.
+    pub tags: Option<ConditionValues>,
+    pub priority: Option<ConditionValues>,
+    pub status: Option<ConditionValues>,
+    /// Holds when at least one of the blocks holds.
+    pub any: Option<Vec<RoutingConditions>>,
+    /// Holds when every block holds.
+    pub all: Option<Vec<RoutingConditions>>,
+    /// Holds when the block does not.
+    pub not: Option<Box<RoutingConditions>>,
+}
+
+impl RoutingConditions {
+    /// The value conditions that are set, by key.
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, &ConditionValues)> {
+        [
+            ("lang", &self.lang),
+            ("path", &self.path),
+            ("task_id", &self.task_id),
+            ("profile", &self.profile),
+            ("tags", &self.tags),
+            ("priority", &self.priority),
+            ("status", &self.status),
+        ]
+        .into_iter()
+        .filter_map(|(key, values)| values.as_ref().map(|values| (key, values)))
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        Specificity {
+            task_id: self.task_id.is_some(),
+            path: self.path.is_some(),
+            lang: self.lang.is_some(),
+            profile: self.profile.is_some(),
+            other: [
+                self.tags.is_some(),
+                self.priority.is_some(),
+                self.status.is_some(),
+                self.any.is_some(),
+                self.all.is_some(),
+                self.not.is_some(),
+            ]
+            .into_iter()
+            .filter(|set| *set)
+            .count(),
+        }
+    }
+
+    /// Whether every task matching `other` also matches these conditions.
+    /// Conservative: globs only cover the same pattern and nested blocks
+    /// only cover identical blocks.
+    pub fn covers(&self, other: &RoutingConditions) -> bool {
+        let values = |mine: &Option<ConditionValues>, theirs: &Option<ConditionValues>| {
+            match (mine, theirs) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(mine), Some(theirs)) => theirs.values().iter().all(|value| {
+                    mine.values()
+                        .iter()
+                        .any(|candidate| candidate.eq_ignore_ascii_case(value))
+                }),
+            }
+        };
+        let exact = |mine: &Option<ConditionValues>, theirs: &Option<ConditionValues>| {
+            match (mine, theirs) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(mine), Some(theirs)) => theirs
+                    .values()
+                    .iter()
+                    .all(|value| mine.values().contains(value)),
+            }
+        };
+        values(&self.lang, &other.lang)
+            && exact(&self.path, &other.path)
+            && exact(&self.task_id, &other.task_id)
+            && exact(&self.profile, &other.profile)
+            && values(&self.tags, &other.tags)
+            && values(&self.priority, &other.priority)
+            && values(&self.status, &other.status)
+            && (self.any.is_none() || self.any == other.any)
+            && (self.all.is_none() || self.all == other.all)
+            && (self.not.is_none() || self.not == other.not)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields().next().is_none()
+            && self.any.is_none()
+            && self.all.is_none()
+            && self.not.is_none()
+    }
+}
+
+/// A condition value: one string, or a list any of which satisfies it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged, expecting = "a string or a list of strings")]
+pub enum ConditionValues {
+    One(String),
+    Any(Vec<String>),
+}
+
+impl ConditionValues {
+    pub fn values(&self) -> &[String] {
+        match self {
+            Self::One(value) => std::slice::from_ref(value),
+            Self::Any(values) => values,
+        }
+    }
+}
+
+/// Validates a condition block and the blocks nested in it; `key` is the
+/// path of the block itself.
+fn condition_issues(
+    rule: usize,
+    when: &RoutingConditions,
+    key: &[String],
+    issues: &mut Vec<ConfigIssue>,
+    warnings: &mut Vec<ConfigIssue>,
+) {
+    let child = |name: &str| {
+        let mut path = key.to_vec();
+        path.push(name.to_string());
+        path
+    };
+    let label = |path: &[String]| layers::key_path(&path[2..]);
+
+    for (name, values) in when.fields() {
+        let field_key = child(name);
+        if values.values().is_empty() {
+            issues.push(ConfigIssue::new(
+                &field_key,
+                format!(
+                    "routing rule #{} has an empty list in {}",
+                    rule,
+                    label(&field_key)
+                ),
+            ));
+        }
+        for (position, value) in values.values().iter().enumerate() {
+            let value_key = match values {
+                ConditionValues::One(_) => field_key.clone(),
+                ConditionValues::Any(_) => {
+                    let mut path = field_key.clone();
+                    path.push(position.to_string());
+                    path
+                }
+            };
+            let problem = match name {
+                "path" | "task_id" => Glob::new(value)
+                    .err()
+                    .map(|err| format!("invalid {name} glob '{value}': {err}")),
+                "status" => value.to_ascii_lowercase().parse::<TaskStatus>().err(),
+                _ => None,
+            };
+            if let Some(problem) = problem {
+                issues.push(ConfigIssue::new(
+                    &value_key,
+                    format!("routing rule #{} has {}", rule, problem),
+                ));
+            }
+            // Tasks may use any priority, but one without a rank is likely a
+            // typo for a known level.
+            if name == "priority" && priority_rank(value).is_none() {
+                warnings.push(ConfigIssue::new(
+                    &value_key,
+                    format!(
+                        "routing rule #{} has unrecognized priority '{}' (expected critical, \
+                         high, medium, low or p<N>)",
+                        rule, value
+                    ),
+                ));
+            }
+        }
+    }
+
+    for (name, blocks) in [("any", &when.any), ("all", &when.all)] {
+        let Some(blocks) = blocks else {
+            continue;
+        };
+        let list_key = child(name);
+        if blocks.is_empty() {
+            issues.push(ConfigIssue::new(
+                &list_key,
+                format!(
+                    "routing rule #{} has an empty list in {}",
+                    rule,
+                    label(&list_key)
+                ),
+            ));
+        }
+        for (position, block) in blocks.iter().enumerate() {
+            let mut block_key = list_key.clone();
+            block_key.push(position.to_string());
+            nested_block_issues(rule, block, &block_key, issues, warnings);
+        }
+    }
+
+    if let Some(block) = &when.not {
+        nested_block_issues(rule, block, &child("not"), issues, warnings);
+    }
+}
+
+/// A nested block without conditions would make `any` always hold and
+/// `not` never hold, so it is rejected.
+fn nested_block_issues(
+    rule: usize,
+    block: &RoutingConditions,
+    key: &[String],
+    issues: &mut Vec<ConfigIssue>,
+    warnings: &mut Vec<ConfigIssue>,
+) {
+    if block.is_empty() {
+        issues.push(ConfigIssue::new(
+            key,
+            format!(
+                "routing rule #{} has an empty condition block at {}",
+                rule,
+                layers::key_path(&key[2..])
+            ),
+        ));
+    }
+    condition_issues(rule, block, key, issues, warnings);
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -338,13 +794,24 @@ impl RoleKind {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq, Eq)]
 pub struct Limits {
     pub max_files: Option<u32>,
     pub max_tokens: Option<u32>,
     pub max_changed_lines: Option<u32>,
 }
 
+impl Limits {
+    /// Returns these limits with every field set in `overrides` replaced.
+    pub fn overridden_by(&self, overrides: &Limits) -> Limits {
+        Limits {
+            max_files: overrides.max_files.or(self.max_files),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            max_changed_lines: overrides.max_changed_lines.or(self.max_changed_lines),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct Apply {
@@ -402,7 +869,7 @@ fn default_docs_dir() -> String {
     "docs".into()
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 pub struct ReviewConfig {
     pub default_pipeline: Option<String>,
@@ -412,17 +879,7 @@ pub struct ReviewConfig {
     pub stages: BTreeMap<String, ReviewStage>,
 }
 
-impl Default for ReviewConfig {
-    fn default() -> Self {
-        Self {
-            default_pipeline: None,
-            pipelines: BTreeMap::new(),
-            stages: BTreeMap::new(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 pub struct ReviewPipeline {
     pub stages: Vec<String>,
@@ -431,16 +888,14 @@ pub struct ReviewPipeline {
     pub fail_on: Vec<String>,
     #[serde(default)]
     pub weights: HashMap<String, f32>,
+    /// Share of the total weight that must pass for `weighted` consensus.
+    pub threshold: Option<f32>,
 }
 
-impl Default for ReviewPipeline {
-    fn default() -> Self {
-        Self {
-            stages: Vec::new(),
-            consensus: None,
-            fail_on: Vec::new(),
-            weights: HashMap::new(),
-        }
+impl ReviewPipeline {
+    /// Consensus used when the pipeline does not specify one.
+    pub fn consensus(&self) -> ReviewConsensus {
+        self.consensus.unwrap_or(ReviewConsensus::Gate)
     }
 }
 
@@ -452,6 +907,16 @@ pub enum ReviewConsensus {
     Weighted,
 }
 
+impl ReviewConsensus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewConsensus::Gate => "gate",
+            ReviewConsensus::Majority => "majority",
+            ReviewConsensus::Weighted => "weighted",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct ReviewStage {
@@ -478,6 +943,25 @@ impl Default for ReviewStage {
     }
 }
 
+/// Directory that holds a review stage's outputs inside a run; characters
+/// unsafe in a path component become `_`.
+pub fn review_stage_dir_name(stage: &str) -> String {
+    let component: String = stage
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '-' | '_') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match component.trim_matches('.') {
+        "" => "_".to_string(),
+        _ => component,
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum ReviewStageKind {
@@ -487,7 +971,7 @@ pub enum ReviewStageKind {
 }
 
 impl ReviewStageKind {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             ReviewStageKind::Exec => "exec",
             ReviewStageKind::Llm => "llm",
@@ -594,4 +1078,244 @@ plan = "claude"
             other => panic!("unexpected error: {other:?}"),
         }
     }
+
+    #[test]
+    fn profile_limits_override_top_level_fields() {
+        let toml = r#"
+[runners.claude]
+cmd = "claude"
+
+[limits]
+max_files = 10
+max_tokens = 50000
+
+[profiles.fast.limits]
+max_files = 2
+max_changed_lines = 100
+"#;
+
+        let config = Config::from_str(toml).expect("config parses");
+        assert_eq!(
+            config.effective_limits(Some("fast")),
+            Limits {
+                max_files: Some(2),
+                max_tokens: Some(50000),
+                max_changed_lines: Some(100),
+            }
+        );
+        assert_eq!(config.effective_limits(None), config.limits);
+    }
+
+    #[test]
+    fn validation_checks_pipeline_weights() {
+        let toml = r#"
+[review.pipelines.weighted]
+stages = ["build"]
+consensus = "weighted"
+
+[review.pipelines.typo]
+stages = ["build"]
+weights = { buld = 1.0 }
+fail_on = ["lint"]
+
+[review.stages.build]
+type = "exec"
+cmd = ["cargo", "check"]
+"#;
+
+        let err = Config::from_str(toml).expect_err("validation should fail");
+        let msg = err.to_string();
+        assert!(msg.contains("'weighted' uses weighted consensus but defines no weights"));
+        assert!(msg.contains("'typo' has a weight for unknown stage 'buld'"));
+        assert!(msg.contains("fail_on stage 'lint'"));
+    }
+
+    #[test]
+    fn validation_rejects_stages_sharing_an_output_directory() {
+        let toml = r#"
+[review.stages."lint/x"]
+type = "exec"
+cmd = ["cargo", "clippy"]
+
+[review.stages.lint_x]
+type = "exec"
+cmd = ["cargo", "fmt"]
+"#;
+
+        let err = Config::from_str(toml).expect_err("validation should fail");
+        assert!(
+            err.to_string()
+                .contains("review stages 'lint/x' and 'lint_x' would share the output directory 'lint_x'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn validation_checks_routing_conditions() {
+        let toml = r#"
+[runners.claude]
+cmd = "claude"
+
+[[routing]]
+role = "code"
+use = "claude"
+when.tags = []
+when.status = ["todo", "wip"]
+when.any = [{ path = ["src/**", "src/["] }, {}]
+when.not = { all = [] }
+"#;
+
+        let config: Config = toml::from_str(toml).expect("config parses");
+        let issues: Vec<_> = config
+            .issues()
+            .into_iter()
+            .map(|issue| (issue.key(), issue.message))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (
+                    "routing.0.when.tags".to_string(),
+                    "routing rule #0 has an empty list in when.tags".to_string()
+                ),
+                (
+                    "routing.0.when.status.1".to_string(),
+                    "routing rule #0 has unknown task status 'wip' (expected todo, doing, done or blocked)"
+                        .to_string()
+                ),
+                (
+                    "routing.0.when.any.0.path.1".to_string(),
+                    "routing rule #0 has invalid path glob 'src/[': error parsing glob 'src/[': unclosed character class; missing ']'"
+                        .to_string()
+                ),
+                (
+                    "routing.0.when.any.1".to_string(),
+                    "routing rule #0 has an empty condition block at when.any.1".to_string()
+                ),
+                (
+                    "routing.0.when.not.all".to_string(),
+                    "routing rule #0 has an empty list in when.not.all".to_string()
+                ),
+            ]
+        );
+
+        let err = Config::from_str(
+            "[runners.claude]\ncmd = \"claude\"\n\n[[routing]]\nrole = \"code\"\nuse = \"claude\"\nwhen.tag = \"x\"\n",
+        )
+        .expect_err("unknown condition");
+        assert!(err.to_string().contains("unknown field `tag`"));
+        let err = Config::from_str(
+            "[runners.claude]\ncmd = \"claude\"\n\n[[routing]]\nrole = \"code\"\nuse = \"claude\"\nwhen.lang = 3\n",
+        )
+        .expect_err("invalid value");
+        assert!(err.to_string().contains("a string or a list of strings"));
+    }
+
+    #[test]
+    fn warnings_report_shadowed_routing_rules() {
+        let toml = r#"
+[runners.claude]
+cmd = "claude"
+
+[[routing]]
+role = "code"
+use = "claude"
+when.lang = ["rust", "go"]
+
+[[routing]]
+role = "code"
+use = "claude"
+when.lang = "Rust"
+
+[[routing]]
+role = "code"
+use = "claude"
+when.lang = "rust"
+when.task_id = "A-*"
+
+[[routing]]
+role = "code"
+use = "claude"
+priority = -1
+when.lang = "go"
+when.path = "src/**"
+
+[[routing]]
+role = "review"
+use = "claude"
+when.lang = "rust"
+"#;
+
+        let config = Config::from_str(toml).expect("warnings do not fail validation");
+        let warnings: Vec<_> = config
+            .warnings()
+            .into_iter()
+            .map(|issue| (issue.key(), issue.message))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (
+                    "routing.1".to_string(),
+                    "routing rule #1 for role 'code' is shadowed by rule #0 and never applies"
+                        .to_string()
+                ),
+                (
+                    "routing.3".to_string(),
+                    "routing rule #3 for role 'code' is shadowed by rule #0 and never applies"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn warnings_report_unrecognized_routing_priorities() {
+        let toml = r#"
+[runners.claude]
+cmd = "claude"
+
+[[routing]]
+role = "code"
+use = "claude"
+when.priority = ["High", "p2", "hihg"]
+
+[[routing]]
+role = "plan"
+use = "claude"
+when.not = { priority = "someday" }
+"#;
+
+        let config = Config::from_str(toml).expect("warnings do not fail validation");
+        let warnings: Vec<_> = config
+            .warnings()
+            .into_iter()
+            .map(|issue| issue.key())
+            .collect();
+        assert_eq!(warnings, ["routing.0.when.priority.2", "routing.1.when.not.priority"]);
+        assert!(config.warnings()[0]
+            .message
+            .contains("unrecognized priority 'hihg'"));
+    }
+
+    #[test]
+    fn specificity_orders_task_id_over_path_over_lang_over_profile() {
+        let specificity = |toml: &str| {
+            toml::from_str::<RoutingConditions>(toml)
+                .expect("conditions parse")
+                .specificity()
+        };
+        let task_id = specificity("task_id = \"A-*\"");
+        let path = specificity("path = \"src/**\"\nlang = \"rust\"\nprofile = \"fast\"");
+        let lang = specificity("lang = \"rust\"\nprofile = \"fast\"\ntags = \"x\"");
+        let profile = specificity("profile = \"fast\"\ntags = \"x\"\nstatus = \"todo\"");
+        let tags = specificity("tags = \"x\"");
+
+        assert!(task_id > path);
+        assert!(path > lang);
+        assert!(lang > profile);
+        assert!(profile > tags);
+        assert!(tags > Specificity::default());
+        assert_eq!(path.to_string(), "path + lang + profile");
+    }
 }
@@ -0,0 +1,305 @@
+use std::ops::Range;
+
+use toml_edit::{ImDocument, Item};
+
+use super::{
+    Config, ConfigError, ConfigFiles, ConfigOrigin,
+    layers::{ConfigSource, LayeredConfig, env_path, key_path},
+};
+
+/// A configuration problem located in the layer that caused it.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Key path in TOML notation; unset for syntax errors.
+    pub key: Option<String>,
+    /// File or environment variable the problem comes from.
+    pub origin: Option<ConfigOrigin>,
+    pub span: Option<SourceSpan>,
+}
+
+/// Errors make the configuration unusable; warnings do not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Position of a problem inside a configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// 1-based.
+    pub line: usize,
+    /// 1-based, in characters.
+    pub column: usize,
+    /// Characters to underline, at least one.
+    pub width: usize,
+    /// The whole source line.
+    pub text: String,
+}
+
+/// Checks every layer and the merged result. Syntax and type errors of a
+/// file are reported first, since nothing can be merged past them; then
+/// every validation issue and warning is traced back to the layer that set
+/// its key.
+/// Only failures to read a file are returned as errors.
+pub fn diagnose(
+    files: &ConfigFiles,
+    env: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<Diagnostic>, ConfigError> {
+    let sources = files.read()?;
+
+    let parse_errors: Vec<Diagnostic> = sources
+        .iter()
+        .filter_map(|source| {
+            let err = toml::from_str::<Config>(&source.text).err()?;
+            Some(Diagnostic {
+                severity: Severity::Error,
+                message: err.message().to_string(),
+                key: None,
+                origin: Some(ConfigOrigin::File(source.name.clone())),
+                span: err.span().map(|span| source_span(&source.text, span)),
+            })
+        })
+        .collect();
+    if !parse_errors.is_empty() {
+        return Ok(parse_errors);
+    }
+
+    let mut env: Vec<(String, String)> = env.into_iter().collect();
+    env.sort();
+    let layered = match LayeredConfig::merge(&sources, env.clone()) {
+        Ok(layered) => layered,
+        Err(ConfigError::ParseFailed { source, .. }) => {
+            // The files are fine on their own, so an override has the wrong
+            // type; try them one at a time to name the culprits.
+            let base = LayeredConfig::merge(&sources, Vec::new())
+                .map(|layered| layered.values)
+                .unwrap_or_default();
+            let mut diagnostics: Vec<Diagnostic> = env
+                .iter()
+                .filter_map(|(name, raw)| {
+                    let path = env_path(&base, name)?;
+                    let single = [(name.clone(), raw.clone())];
+                    let Err(ConfigError::ParseFailed { source, .. }) =
+                        LayeredConfig::merge(&sources, single)
+                    else {
+                        return None;
+                    };
+                    Some(Diagnostic {
+                        severity: Severity::Error,
+                        message: source.message().to_string(),
+                        key: Some(key_path(&path)),
+                        origin: Some(ConfigOrigin::Env(name.clone())),
+                        span: None,
+                    })
+                })
+                .collect();
+            if diagnostics.is_empty() {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    message: format!(
+                        "{} (after applying environment overrides)",
+                        source.message()
+                    ),
+                    key: None,
+                    origin: None,
+                    span: None,
+                });
+            }
+            return Ok(diagnostics);
+        }
+        Err(err) => return Err(err),
+    };
+
+    let config = &layered.config;
+    let issues = config
+        .issues()
+        .into_iter()
+        .map(|issue| (Severity::Error, issue));
+    let warnings = config
+        .warnings()
+        .into_iter()
+        .map(|issue| (Severity::Warning, issue));
+    let mut diagnostics: Vec<Diagnostic> = issues
+        .chain(warnings)
+        .map(|(severity, issue)| {
+            let origin = layered.origin_of(&issue.path).cloned();
+            let span = match &origin {
+                Some(ConfigOrigin::File(name)) => sources
+                    .iter()
+                    .find(|source| &source.name == name)
+                    .and_then(|source| locate(source, &issue.path)),
+                _ => None,
+            };
+            Diagnostic {
+                severity,
+                key: Some(issue.key()),
+                message: issue.message,
+                origin,
+                span,
+            }
+        })
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| {
+        let layer = match &diagnostic.origin {
+            Some(ConfigOrigin::File(name)) => sources
+                .iter()
+                .position(|source| &source.name == name)
+                .unwrap_or(0),
+            Some(ConfigOrigin::Env(_)) => sources.len(),
+            None => sources.len() + 1,
+        };
+        let line = diagnostic.span.as_ref().map_or(0, |span| span.line);
+        (layer, line)
+    });
+    Ok(diagnostics)
+}
+
+/// Span of the deepest item along `path` that the parser recorded.
+fn locate(source: &ConfigSource, path: &[String]) -> Option<SourceSpan> {
+    let document = ImDocument::parse(source.text.as_str()).ok()?;
+    let mut item: &Item = document.as_item();
+    let mut span = None;
+    for key in path {
+        let next = match key.parse::<usize>() {
+            Ok(index) if item.is_array_of_tables() || item.is_array() => item.get(index),
+            _ => item.get(key.as_str()),
+        };
+        let Some(next) = next else {
+            break;
+        };
+        item = next;
+        span = item.span().or(span);
+    }
+    span.map(|span| source_span(&source.text, span))
+}
+
+fn source_span(text: &str, span: Range<usize>) -> SourceSpan {
+    let start = span.start.min(text.len());
+    let line_start = text[..start].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = text[start..]
+        .find('\n')
+        .map_or(text.len(), |index| start + index);
+    let line = text[line_start..line_end].trim_end_matches('\r');
+    let column = text[line_start..start].chars().count() + 1;
+    let end = span.end.clamp(start, line_start + line.len());
+    SourceSpan {
+        line: text[..start].matches('\n').count() + 1,
+        column,
+        width: text[start..end].chars().count().max(1),
+        text: line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::Path};
+    use tempfile::tempdir;
+
+    fn files(root: &Path, project: &str, local: Option<&str>) -> ConfigFiles {
+        fs::write(root.join("alisa.toml"), project).unwrap();
+        if let Some(local) = local {
+            fs::write(root.join("alisa.local.toml"), local).unwrap();
+        }
+        ConfigFiles {
+            root: root.to_path_buf(),
+            project: "alisa.toml".into(),
+            local: Some("alisa.local.toml".into()),
+        }
+    }
+
+    fn summary(diagnostic: &Diagnostic) -> (String, Option<String>, Option<(usize, usize, usize)>) {
+        (
+            diagnostic
+                .origin
+                .as_ref()
+                .map_or_else(String::new, ToString::to_string),
+            diagnostic.key.clone(),
+            diagnostic
+                .span
+                .as_ref()
+                .map(|span| (span.line, span.column, span.width)),
+        )
+    }
+
+    #[test]
+    fn issues_point_at_the_layer_and_value_that_caused_them() {
+        let temp = tempdir().unwrap();
+        let project = "[runners.claude]\ncmd = \"claude\"\n\n[roles]\nplan = \"claude\"\ncode = \"codex\"\n\n[[routing]]\nrole = \"code\"\nuse = \"claude\"\nwhen.path = \"src/[\"\n";
+        let local = "[review.stages.\"llm.check\"]\ntype = \"llm\"\nrunner = \"gpt\"\n";
+        let diagnostics = diagnose(
+            &files(temp.path(), project, Some(local)),
+            [("ALISA_ROLES__REVIEW".to_string(), "nobody".to_string())],
+        )
+        .unwrap();
+
+        let summaries: Vec<_> = diagnostics.iter().map(summary).collect();
+        assert_eq!(
+            summaries,
+            [
+                (
+                    "alisa.toml".to_string(),
+                    Some("roles.code".to_string()),
+                    Some((6, 8, 7))
+                ),
+                (
+                    "alisa.toml".to_string(),
+                    Some("routing.0.when.path".to_string()),
+                    Some((11, 13, 7))
+                ),
+                (
+                    "alisa.local.toml".to_string(),
+                    Some("review.stages.\"llm.check\".runner".to_string()),
+                    Some((3, 10, 5))
+                ),
+                (
+                    "env ALISA_ROLES__REVIEW".to_string(),
+                    Some("roles.review".to_string()),
+                    None
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].span.as_ref().unwrap().text,
+            "code = \"codex\""
+        );
+        assert!(diagnostics[1].message.contains("invalid path glob 'src/['"));
+    }
+
+    #[test]
+    fn syntax_and_type_errors_are_reported_per_file() {
+        let temp = tempdir().unwrap();
+        let diagnostics = diagnose(
+            &files(
+                temp.path(),
+                "[runners.claude]\ncmd = \"claude\"\n\n[limits]\nmax_files = \"ten\"\n",
+                Some("[roles\n"),
+            ),
+            Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            summary(&diagnostics[0]),
+            ("alisa.toml".to_string(), None, Some((5, 13, 5)))
+        );
+        assert!(diagnostics[0].message.contains("invalid type"));
+        assert_eq!(summary(&diagnostics[1]).0, "alisa.local.toml");
+        assert_eq!(diagnostics[1].span.as_ref().unwrap().line, 1);
+    }
+
+    #[test]
+    fn valid_layers_produce_no_diagnostics() {
+        let temp = tempdir().unwrap();
+        let diagnostics = diagnose(
+            &files(temp.path(), "[runners.claude]\ncmd = \"claude\"\n", None),
+            Vec::new(),
+        )
+        .unwrap();
+        assert!(diagnostics.is_empty());
+    }
+}
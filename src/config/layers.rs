@@ -0,0 +1,564 @@
+use std::{collections::BTreeMap, ffi::OsString, fmt, fs, path::PathBuf};
+
+use toml::{Table, Value};
+
+use super::{Config, ConfigError};
+
+/// Prefix of environment variables that override configuration values.
+pub const ENV_PREFIX: &str = "ALISA_";
+/// Separates nested keys in an override variable name.
+pub const ENV_SEPARATOR: &str = "__";
+
+/// Top-level keys that environment variables may override. Other `ALISA_*`
+/// variables (such as `ALISA_MODEL`, set for runners) are ignored.
+const ENV_KEYS: &[&str] = &[
+    "version",
+    "project",
+    "runners",
+    "roles",
+    "profiles",
+    "routing",
+    "limits",
+    "apply",
+    "paths",
+    "review",
+    "summaries",
+];
+
+/// The files that make up a project's configuration, lowest precedence
+/// first. Paths are relative to `root`, as recorded in the manifest.
+#[derive(Debug, Clone)]
+pub struct ConfigFiles {
+    pub root: PathBuf,
+    pub project: String,
+    pub local: Option<String>,
+}
+
+/// Where an effective configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    File(String),
+    Env(String),
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => f.write_str(path),
+            Self::Env(name) => write!(f, "env {name}"),
+        }
+    }
+}
+
+/// A validated configuration together with the merged TOML it was built
+/// from and the origin of every value.
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: Config,
+    pub values: Table,
+    origins: BTreeMap<Vec<String>, ConfigOrigin>,
+}
+
+/// A configuration file as read from disk.
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    /// Path relative to the project root, as shown to users.
+    pub name: String,
+    pub path: PathBuf,
+    pub text: String,
+}
+
+impl ConfigFiles {
+    /// Reads the project file and, when it exists, the local overrides.
+    pub fn read(&self) -> Result<Vec<ConfigSource>, ConfigError> {
+        let mut files = vec![(&self.project, true)];
+        if let Some(local) = &self.local {
+            files.push((local, false));
+        }
+        let mut sources = Vec::new();
+        for (name, required) in files {
+            let path = self.root.join(name);
+            if !required && !path.exists() {
+                continue;
+            }
+            let text = fs::read_to_string(&path).map_err(|source| ConfigError::ReadFailed {
+                path: path.clone(),
+                source,
+            })?;
+            sources.push(ConfigSource {
+                name: name.clone(),
+                path,
+                text,
+            });
+        }
+        Ok(sources)
+    }
+
+    /// Loads the layers with overrides from the process environment.
+    pub fn load(&self) -> Result<LayeredConfig, ConfigError> {
+        self.load_with_env(env_overrides()?)
+    }
+
+    /// Deep-merges the project file, the local overrides (when the file
+    /// exists) and `ALISA_*` variables from `env`, then validates the result.
+    pub fn load_with_env(
+        &self,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<LayeredConfig, ConfigError> {
+        let layered = LayeredConfig::merge(&self.read()?, env)?;
+        layered.config.validate()?;
+        Ok(layered)
+    }
+}
+
+impl LayeredConfig {
+    /// Merges `sources` in order, then the `ALISA_*` variables from `env`.
+    /// The result is not validated.
+    pub fn merge(
+        sources: &[ConfigSource],
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ConfigError> {
+        let mut values = Table::new();
+        let mut origins = BTreeMap::new();
+
+        for source in sources {
+            let table: Table =
+                toml::from_str(&source.text).map_err(|err| ConfigError::ParseFailed {
+                    context: format!(" at {}", source.path.display()),
+                    source: err,
+                })?;
+            merge(
+                &mut values,
+                table,
+                &ConfigOrigin::File(source.name.clone()),
+                &mut Vec::new(),
+                &mut origins,
+            );
+        }
+
+        let mut overrides: Vec<_> = env
+            .into_iter()
+            .filter(|(name, _)| env_key(name).is_some())
+            .collect();
+        overrides.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, raw) in overrides {
+            let path = env_path(&values, &name).expect("overrides have valid names");
+            let (leaf, parents) = path.split_last().expect("env keys are never empty");
+            let mut table = Table::new();
+            table.insert(leaf.clone(), env_value(&raw));
+            for parent in parents.iter().rev() {
+                let mut outer = Table::new();
+                outer.insert(parent.clone(), Value::Table(table));
+                table = outer;
+            }
+            merge(
+                &mut values,
+                table,
+                &ConfigOrigin::Env(name),
+                &mut Vec::new(),
+                &mut origins,
+            );
+        }
+
+        let config: Config =
+            Value::Table(values.clone())
+                .try_into()
+                .map_err(|source| ConfigError::ParseFailed {
+                    context: String::from(" after merging overrides"),
+                    source,
+                })?;
+        Ok(Self {
+            config,
+            values,
+            origins,
+        })
+    }
+
+    /// Origin of the value at `path`, e.g. `["limits", "max_tokens"]`.
+    pub fn origin(&self, path: &[&str]) -> Option<&ConfigOrigin> {
+        let path: Vec<String> = path.iter().map(|key| key.to_string()).collect();
+        self.origins.get(&path)
+    }
+
+    /// The layer responsible for `path`: the one that set the value, the
+    /// array or value that contains it, or else the first value below it.
+    pub fn origin_of(&self, path: &[String]) -> Option<&ConfigOrigin> {
+        (1..=path.len())
+            .rev()
+            .find_map(|len| self.origins.get(&path[..len]))
+            .or_else(|| {
+                self.origins
+                    .iter()
+                    .find(|(key, _)| key.starts_with(path))
+                    .map(|(_, origin)| origin)
+            })
+    }
+
+    /// The merged configuration as TOML; with `origins`, each value is
+    /// followed by a comment naming the layer that set it.
+    pub fn render(&self, origins: bool) -> String {
+        let mut out = String::new();
+        self.render_table(&mut out, &mut Vec::new(), &self.values, origins);
+        out
+    }
+
+    fn render_table(&self, out: &mut String, path: &mut Vec<String>, table: &Table, origins: bool) {
+        let (tables, leaves): (Vec<_>, Vec<_>) = table
+            .iter()
+            .partition(|(_, value)| matches!(value, Value::Table(_)));
+        if !path.is_empty() && (!leaves.is_empty() || tables.is_empty()) {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[{}]\n", key_path(path)));
+        }
+        for (key, value) in leaves {
+            out.push_str(&format!(
+                "{} = {value}",
+                key_path(std::slice::from_ref(key))
+            ));
+            path.push(key.clone());
+            if origins && let Some(origin) = self.origins.get(path) {
+                out.push_str(&format!("  # {origin}"));
+            }
+            path.pop();
+            out.push('\n');
+        }
+        for (key, value) in tables {
+            if let Value::Table(table) = value {
+                path.push(key.clone());
+                self.render_table(out, path, table, origins);
+                path.pop();
+            }
+        }
+    }
+}
+
+/// Merges `overlay` into `base`: tables merge key by key, anything else
+/// (including arrays) replaces the previous value.
+fn merge(
+    base: &mut Table,
+    overlay: Table,
+    origin: &ConfigOrigin,
+    path: &mut Vec<String>,
+    origins: &mut BTreeMap<Vec<String>, ConfigOrigin>,
+) {
+    for (key, value) in overlay {
+        path.push(key.clone());
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(table)) => {
+                merge(existing, table, origin, path, origins);
+            }
+            (_, value) => {
+                origins.retain(|key, _| !key.starts_with(path));
+                record_leaves(&value, origin, path, origins);
+                base.insert(key, value);
+            }
+        }
+        path.pop();
+    }
+}
+
+fn record_leaves(
+    value: &Value,
+    origin: &ConfigOrigin,
+    path: &mut Vec<String>,
+    origins: &mut BTreeMap<Vec<String>, ConfigOrigin>,
+) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                path.push(key.clone());
+                record_leaves(value, origin, path, origins);
+                path.pop();
+            }
+        }
+        _ => {
+            origins.insert(path.clone(), origin.clone());
+        }
+    }
+}
+
+/// `ALISA_*` variables of the process environment.
+pub fn env_overrides() -> Result<Vec<(String, String)>, ConfigError> {
+    env_overrides_from(std::env::vars_os())
+}
+
+/// Keeps the `ALISA_*` entries of `vars`; other names, Unicode or not, are
+/// none of our business, but an override whose value is not valid UTF-8 is
+/// an error rather than silently dropped.
+fn env_overrides_from(
+    vars: impl IntoIterator<Item = (OsString, OsString)>,
+) -> Result<Vec<(String, String)>, ConfigError> {
+    let mut overrides = Vec::new();
+    for (name, value) in vars {
+        let Some(name) = name.to_str().filter(|name| name.starts_with(ENV_PREFIX)) else {
+            continue;
+        };
+        match value.into_string() {
+            Ok(value) => overrides.push((name.to_string(), value)),
+            Err(_) if env_key(name).is_some() => {
+                return Err(ConfigError::EnvNotUnicode(name.to_string()));
+            }
+            Err(_) => {}
+        }
+    }
+    Ok(overrides)
+}
+
+/// `ALISA_LIMITS__MAX_TOKENS` → `["limits", "MAX_TOKENS"]`: only the
+/// top-level key is lowercased, [`env_path`] resolves the nested ones.
+fn env_key(name: &str) -> Option<Vec<String>> {
+    let rest = name.strip_prefix(ENV_PREFIX)?;
+    let mut path: Vec<String> = rest.split(ENV_SEPARATOR).map(str::to_string).collect();
+    path[0].make_ascii_lowercase();
+    if !ENV_KEYS.contains(&path[0].as_str()) || path.iter().any(String::is_empty) {
+        return None;
+    }
+    Some(path)
+}
+
+/// Key path set by the variable `name` on top of `values`. Nested keys match
+/// existing keys regardless of case, so `ALISA_RUNNERS__MYRUNNER__MODEL`
+/// reaches `runners.MyRunner.model`; keys matching nothing are lowercased.
+pub(super) fn env_path(values: &Table, name: &str) -> Option<Vec<String>> {
+    let mut path = env_key(name)?;
+    let mut table = values.get(&path[0]).and_then(Value::as_table);
+    for key in &mut path[1..] {
+        let existing = table.and_then(|table| {
+            if table.contains_key(key.as_str()) {
+                return Some(key.clone());
+            }
+            let mut matches = table
+                .keys()
+                .filter(|existing| existing.eq_ignore_ascii_case(key));
+            match (matches.next(), matches.next()) {
+                (Some(existing), None) => Some(existing.clone()),
+                _ => None,
+            }
+        });
+        *key = existing.unwrap_or_else(|| key.to_ascii_lowercase());
+        table = table
+            .and_then(|table| table.get(key.as_str()))
+            .and_then(Value::as_table);
+    }
+    Some(path)
+}
+
+/// Values are read as TOML (`5`, `true`, `["a", "b"]`); anything else is
+/// taken as a plain string.
+fn env_value(raw: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .filter(|table| table.len() == 1)
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Renders a key path, quoting keys that are not bare TOML keys.
+pub(super) fn key_path(path: &[String]) -> String {
+    path.iter()
+        .map(|key| {
+            let bare = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if bare {
+                key.clone()
+            } else {
+                Value::String(key.clone()).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const PROJECT: &str = r#"
+[runners.claude]
+cmd = "claude"
+args = ["-p"]
+
+[roles]
+plan = "claude"
+
+[limits]
+max_files = 10
+max_tokens = 50000
+
+[review.stages."llm.criteria"]
+type = "llm"
+runner = "claude"
+"#;
+
+    const LOCAL: &str = r#"
+[runners.claude]
+args = ["--fast"]
+timeout_ms = 1000
+
+[limits]
+max_tokens = 8000
+"#;
+
+    fn files(root: &std::path::Path, local: Option<&str>) -> ConfigFiles {
+        fs::write(root.join("alisa.toml"), PROJECT).unwrap();
+        if let Some(local) = local {
+            fs::write(root.join("alisa.local.toml"), local).unwrap();
+        }
+        ConfigFiles {
+            root: root.to_path_buf(),
+            project: "alisa.toml".into(),
+            local: Some("alisa.local.toml".into()),
+        }
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn layers_deep_merge_with_later_layers_winning() {
+        let temp = tempdir().unwrap();
+        let layered = files(temp.path(), Some(LOCAL))
+            .load_with_env(env(&[
+                ("ALISA_LIMITS__MAX_TOKENS", "2000"),
+                ("ALISA_RUNNERS__CLAUDE__MODEL", "opus"),
+                ("ALISA_MODEL", "ignored"),
+                ("HOME", "/root"),
+            ]))
+            .unwrap();
+        let config = &layered.config;
+
+        let claude = config.runner("claude").unwrap();
+        assert_eq!(claude.cmd, "claude");
+        assert_eq!(claude.args, ["--fast"]);
+        assert_eq!(claude.timeout_ms, Some(1000));
+        assert_eq!(claude.model.as_deref(), Some("opus"));
+        assert_eq!(config.limits.max_files, Some(10));
+        assert_eq!(config.limits.max_tokens, Some(2000));
+        assert!(!layered.values.contains_key("model"));
+
+        let origin = |path: &[&str]| layered.origin(path).map(ToString::to_string);
+        assert_eq!(
+            origin(&["runners", "claude", "cmd"]).as_deref(),
+            Some("alisa.toml")
+        );
+        assert_eq!(
+            origin(&["runners", "claude", "args"]).as_deref(),
+            Some("alisa.local.toml")
+        );
+        assert_eq!(
+            origin(&["limits", "max_tokens"]).as_deref(),
+            Some("env ALISA_LIMITS__MAX_TOKENS")
+        );
+    }
+
+    #[test]
+    fn missing_local_file_is_skipped_and_result_is_validated() {
+        let temp = tempdir().unwrap();
+        let files = files(temp.path(), None);
+        assert!(files.load_with_env(Vec::new()).is_ok());
+
+        let err = files
+            .load_with_env(env(&[("ALISA_ROLES__CODE", "codex")]))
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("role 'code' references unknown runner 'codex'")
+        );
+    }
+
+    #[test]
+    fn env_values_are_parsed_as_toml_or_kept_as_strings() {
+        assert_eq!(env_value("42"), Value::Integer(42));
+        assert_eq!(env_value("false"), Value::Boolean(false));
+        assert_eq!(
+            env_value(r#"["a", "b"]"#),
+            Value::Array(vec!["a".into(), "b".into()])
+        );
+        assert_eq!(env_value("claude"), Value::String("claude".into()));
+        assert_eq!(
+            env_value("1\nother = 2"),
+            Value::String("1\nother = 2".into())
+        );
+        assert_eq!(env_key("ALISA_LIMITS__"), None);
+    }
+
+    #[test]
+    fn env_keys_below_the_top_level_match_existing_keys_ignoring_case() {
+        let temp = tempdir().unwrap();
+        let project = r#"
+[runners.MyRunner]
+cmd = "claude"
+
+[roles]
+plan = "MyRunner"
+"#;
+        fs::write(temp.path().join("alisa.toml"), project).unwrap();
+        let files = ConfigFiles {
+            root: temp.path().to_path_buf(),
+            project: "alisa.toml".into(),
+            local: None,
+        };
+        let layered = files
+            .load_with_env(env(&[
+                ("ALISA_RUNNERS__MYRUNNER__MODEL", "opus"),
+                ("ALISA_RUNNERS__MYRUNNER__TIMEOUT_MS", "500"),
+            ]))
+            .unwrap();
+
+        let runner = layered.config.runner("MyRunner").unwrap();
+        assert_eq!(runner.model.as_deref(), Some("opus"));
+        assert_eq!(runner.timeout_ms, Some(500));
+        assert!(layered.config.runner("myrunner").is_none());
+        assert_eq!(
+            env_path(&layered.values, "ALISA_RUNNERS__NEW__CMD").unwrap(),
+            ["runners", "new", "cmd"]
+        );
+    }
+
+    #[test]
+    fn render_annotates_values_with_their_origin() {
+        let temp = tempdir().unwrap();
+        let layered = files(temp.path(), Some(LOCAL))
+            .load_with_env(env(&[("ALISA_APPLY__CONFIRM", "false")]))
+            .unwrap();
+        let rendered = layered.render(true);
+
+        assert!(rendered.contains("[apply]\nconfirm = false  # env ALISA_APPLY__CONFIRM\n"));
+        assert!(rendered.contains("max_files = 10  # alisa.toml\n"));
+        assert!(rendered.contains("[review.stages.\"llm.criteria\"]\n"));
+        assert!(!layered.render(false).contains('#'));
+        let reparsed: Table = toml::from_str(&rendered).unwrap();
+        assert_eq!(reparsed, layered.values);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn env_overrides_skip_foreign_names_and_reject_non_unicode_values() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let bytes = |value: &[u8]| OsString::from_vec(value.to_vec());
+        let overrides = env_overrides_from([
+            (bytes(b"ALISA_LIMITS__MAX_TOKENS"), bytes(b"2000")),
+            (bytes(b"HOME\xff"), bytes(b"/root")),
+            (bytes(b"PATH"), bytes(b"/bin\xff")),
+            (bytes(b"ALISA_MODEL"), bytes(b"opus\xff")),
+        ])
+        .unwrap();
+        assert_eq!(
+            overrides,
+            [("ALISA_LIMITS__MAX_TOKENS".to_string(), "2000".to_string())]
+        );
+
+        let err =
+            env_overrides_from([(bytes(b"ALISA_ROLES__CODE"), bytes(b"codex\xff"))]).unwrap_err();
+        assert!(matches!(err, ConfigError::EnvNotUnicode(name) if name == "ALISA_ROLES__CODE"));
+    }
+}
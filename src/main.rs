@@ -1,13 +1,28 @@
+mod audit;
 mod commands;
 mod config;
 mod metadata;
+mod migrations;
+mod rag;
+mod registry;
 mod runtime;
 mod tasks;
 mod workspace;
 
 use clap::{Parser, Subcommand};
 
-use commands::init::{self, InitCliArgs, InitError};
+use commands::{
+    audit::{self as audit_cmd, AuditCliArgs, AuditError},
+    config::{self as config_cmd, ConfigCliArgs, ConfigCommandError},
+    init::{self, InitCliArgs, InitError},
+    migrate::{self as migrate_cmd, MigrateCliArgs, MigrateError},
+    rag::{self as rag_cmd, RagCliArgs, RagError},
+    resolve::{self as resolve_cmd, ResolveCliArgs, ResolveCmdError},
+    run::{self as run_cmd, RunCliArgs, RunError},
+    runs::{self as runs_cmd, RunsCliArgs, RunsError},
+    task::{self as task_cmd, TaskCliArgs, TaskError},
+};
+use config::ConfigError;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -25,6 +40,22 @@ struct Cli {
 enum Commands {
     /// Initialize the workspace (.alisa)
     Init(InitCliArgs),
+    /// Run the plan → code → review loop for a task
+    Run(RunCliArgs),
+    /// Explain which runner each role gets for a task
+    Resolve(ResolveCliArgs),
+    /// List, inspect and edit tasks in the tasks file
+    Task(TaskCliArgs),
+    /// Browse the history of recorded runs
+    Runs(RunsCliArgs),
+    /// Query the audit log
+    Audit(AuditCliArgs),
+    /// Index project documentation for retrieval
+    Rag(RagCliArgs),
+    /// Upgrade the workspace to the schema of this binary
+    Migrate(MigrateCliArgs),
+    /// Inspect the layered project configuration
+    Config(ConfigCliArgs),
 }
 
 fn main() {
@@ -44,6 +75,38 @@ fn run() -> Result<(), (i32, Option<String>)> {
             let (code, message) = map_init_error(&err);
             (code, Some(message))
         }),
+        Commands::Run(args) => run_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_run_error(&err);
+            (code, Some(message))
+        }),
+        Commands::Resolve(args) => resolve_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_resolve_error(&err);
+            (code, Some(message))
+        }),
+        Commands::Task(args) => task_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_task_error(&err);
+            (code, Some(message))
+        }),
+        Commands::Runs(args) => runs_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_runs_error(&err);
+            (code, Some(message))
+        }),
+        Commands::Audit(args) => audit_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_audit_error(&err);
+            (code, Some(message))
+        }),
+        Commands::Rag(args) => rag_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_rag_error(&err);
+            (code, Some(message))
+        }),
+        Commands::Migrate(args) => migrate_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_migrate_error(&err);
+            (code, Some(message))
+        }),
+        Commands::Config(args) => config_cmd::run(&args).map_err(|err| {
+            let (code, message) = map_config_error(&err);
+            (code, Some(message))
+        }),
     }
 }
 
@@ -55,3 +118,56 @@ fn map_init_error(err: &InitError) -> (i32, String) {
         InitError::ValidationFailed(_) | InitError::Other(_) => (1, err.to_string()),
     }
 }
+
+fn map_run_error(err: &RunError) -> (i32, String) {
+    match err {
+        RunError::WorkspaceLocked { .. } => (3, err.to_string()),
+        RunError::Interrupted => (130, err.to_string()),
+        _ => (1, err.to_string()),
+    }
+}
+
+fn map_resolve_error(err: &ResolveCmdError) -> (i32, String) {
+    (1, err.to_string())
+}
+
+fn map_task_error(err: &TaskError) -> (i32, String) {
+    match err {
+        TaskError::WorkspaceLocked { .. } => (3, err.to_string()),
+        _ => (1, err.to_string()),
+    }
+}
+
+fn map_runs_error(err: &RunsError) -> (i32, String) {
+    (1, err.to_string())
+}
+
+fn map_audit_error(err: &AuditError) -> (i32, String) {
+    (1, err.to_string())
+}
+
+fn map_rag_error(err: &RagError) -> (i32, String) {
+    match err {
+        RagError::WorkspaceLocked { .. } => (3, err.to_string()),
+        _ => (1, err.to_string()),
+    }
+}
+
+fn map_migrate_error(err: &MigrateError) -> (i32, String) {
+    match err {
+        MigrateError::WorkspaceLocked { .. } => (3, err.to_string()),
+        _ => (1, err.to_string()),
+    }
+}
+
+fn map_config_error(err: &ConfigCommandError) -> (i32, String) {
+    match err {
+        ConfigCommandError::Invalid { .. }
+        | ConfigCommandError::Config(
+            ConfigError::Invalid(_)
+            | ConfigError::ParseFailed { .. }
+            | ConfigError::EnvNotUnicode(_),
+        ) => (4, err.to_string()),
+        _ => (1, err.to_string()),
+    }
+}
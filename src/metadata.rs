@@ -16,7 +16,7 @@ use uuid::Uuid;
 pub const WORKSPACE_ID_PATTERN: &str = r"^ws_[0-9a-f]{32}$";
 
 /// Schema version expected by the current binary.
-pub const MANIFEST_SCHEMA_VERSION: &str = "1.0";
+pub const MANIFEST_SCHEMA_VERSION: &str = "1.1";
 
 /// Default relative path for the main CLI configuration file.
 pub const DEFAULT_CONFIG_PATH: &str = "alisa.toml";
@@ -180,6 +180,7 @@ fn lock_workspace_id_registry(path: &Path) -> Result<File> {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(false)
         .open(path)
         .with_context(|| format!("Failed to open workspace id registry at {}", path.display()))?;
 
@@ -268,7 +269,8 @@ fn is_valid_workspace_id(id: &str) -> bool {
     suffix.chars().all(|ch| matches!(ch, '0'..='9' | 'a'..='f'))
 }
 
-fn current_timestamp() -> String {
+/// Returns the current UTC time formatted as RFC3339.
+pub fn current_timestamp() -> String {
     let now = OffsetDateTime::now_utc();
     match now.format(&Rfc3339) {
         Ok(timestamp) => timestamp,
@@ -0,0 +1,358 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use rusqlite::Connection;
+
+use crate::{
+    audit::AuditLog,
+    metadata::{MANIFEST_SCHEMA_VERSION, current_timestamp, read_manifest, write_manifest},
+    workspace::Workspace,
+};
+
+/// Every known upgrade, oldest first. Each entry moves a workspace from one
+/// schema version to the next; [`plan`] chains them.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: "1.0",
+    to: "1.1",
+    description: "Hash-chain the audit trail",
+    steps: &[
+        Step::Sql {
+            database: Database::AuditIndex,
+            sql: "CREATE TABLE IF NOT EXISTS chain_heads (
+    day TEXT PRIMARY KEY,
+    head TEXT NOT NULL,
+    events INTEGER NOT NULL
+);",
+        },
+        Step::Layout {
+            description: "Chain audit events written before 1.1",
+            apply: rechain_audit,
+        },
+    ],
+}];
+
+/// A SQLite database that migrations may alter.
+// Not every database has needed a migration yet.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Database {
+    Registry,
+    AuditIndex,
+    RagIndex,
+}
+
+impl Database {
+    pub fn path(self, workspace: &Workspace) -> PathBuf {
+        match self {
+            Self::Registry => workspace.registry_path(),
+            Self::AuditIndex => workspace.audit_index_path(),
+            Self::RagIndex => workspace.rag_index_path(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Registry => "registry database",
+            Self::AuditIndex => "audit index",
+            Self::RagIndex => "RAG index",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Step {
+    /// Statements run inside one transaction; they must not contain their
+    /// own `BEGIN`/`COMMIT`.
+    Sql {
+        database: Database,
+        sql: &'static str,
+    },
+    /// A change to files inside `.alisa`, run after the SQL steps of the
+    /// same migration.
+    Layout {
+        description: &'static str,
+        apply: fn(&Workspace) -> Result<()>,
+    },
+}
+
+impl Step {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Sql { database, .. } => format!("Update the {} schema", database.label()),
+            Self::Layout { description, .. } => description.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Migration {
+    pub from: &'static str,
+    pub to: &'static str,
+    pub description: &'static str,
+    pub steps: &'static [Step],
+}
+
+impl Migration {
+    /// Databases touched by the SQL steps, in first-use order.
+    pub fn databases(&self) -> Vec<Database> {
+        let mut databases = Vec::new();
+        for step in self.steps {
+            if let Step::Sql { database, .. } = step
+                && !databases.contains(database)
+            {
+                databases.push(*database);
+            }
+        }
+        databases
+    }
+}
+
+/// Migrations that bring a workspace at `from` to the version of this binary.
+pub fn plan(from: &str) -> Result<Vec<&'static Migration>> {
+    plan_between(MIGRATIONS, from, MANIFEST_SCHEMA_VERSION)
+}
+
+fn plan_between<'a>(
+    migrations: &'a [Migration],
+    from: &str,
+    to: &str,
+) -> Result<Vec<&'a Migration>> {
+    let mut steps = Vec::new();
+    let mut version = from;
+    while version != to {
+        let Some(next) = migrations
+            .iter()
+            .find(|migration| migration.from == version)
+        else {
+            bail!("no migration path from schema version {from} to {to}");
+        };
+        steps.push(next);
+        version = next.to;
+    }
+    Ok(steps)
+}
+
+/// Schema version recorded by `migrations/version.txt`, falling back to the
+/// manifest when the marker is missing.
+pub fn current_version(workspace: &Workspace) -> Result<String> {
+    let path = workspace.schema_version_path();
+    if path.exists() {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read schema version at {}", path.display()))?;
+        return Ok(content.trim().to_string());
+    }
+    match read_manifest(&workspace.manifest_path())? {
+        Some(manifest) => Ok(manifest.schema_version),
+        None => bail!(
+            "workspace at {} has neither a schema marker nor a manifest",
+            workspace.workspace_root().display()
+        ),
+    }
+}
+
+/// True when the schema marker names an older or unknown version. Commands
+/// use it to leave old workspaces alone until `alisa migrate` has run.
+pub fn schema_is_outdated(workspace: &Workspace) -> bool {
+    fs::read_to_string(workspace.schema_version_path())
+        .is_ok_and(|content| content.trim() != MANIFEST_SCHEMA_VERSION)
+}
+
+/// Directory that receives the database copies taken before `migrations`
+/// run.
+pub fn backup_dir(workspace: &Workspace, migrations: &[&Migration]) -> Option<PathBuf> {
+    let (first, last) = (migrations.first()?, migrations.last()?);
+    let stamp = current_timestamp().replace(':', "-");
+    Some(
+        workspace
+            .schema_version_path()
+            .with_file_name("backups")
+            .join(format!("{}-{}-{stamp}", first.from, last.to)),
+    )
+}
+
+/// Backs up the affected databases into `backup`, then applies every
+/// migration in order. When a step fails the backups are restored and the
+/// recorded schema version is left unchanged.
+pub fn apply(workspace: &Workspace, migrations: &[&Migration], backup: &Path) -> Result<()> {
+    let mut databases = Vec::new();
+    for database in migrations
+        .iter()
+        .flat_map(|migration| migration.databases())
+    {
+        if !databases.contains(&database) && database.path(workspace).exists() {
+            databases.push(database);
+        }
+    }
+
+    fs::create_dir_all(backup)
+        .with_context(|| format!("Failed to create backup directory {}", backup.display()))?;
+    let mut copies = Vec::new();
+    for database in databases {
+        let source = database.path(workspace);
+        let copy = backup.join(source.file_name().expect("database paths name a file"));
+        fs::copy(&source, &copy).with_context(|| {
+            format!(
+                "Failed to back up the {} to {}",
+                database.label(),
+                copy.display()
+            )
+        })?;
+        copies.push((copy, source));
+    }
+
+    let result = migrations
+        .iter()
+        .try_for_each(|migration| apply_migration(workspace, migration));
+    if let Err(err) = result {
+        for (copy, source) in &copies {
+            if let Err(restore) = fs::copy(copy, source) {
+                eprintln!(
+                    "[warn] Failed to restore {} from {}: {restore}",
+                    source.display(),
+                    copy.display()
+                );
+            }
+        }
+        return Err(err);
+    }
+
+    let version = migrations
+        .last()
+        .map_or(MANIFEST_SCHEMA_VERSION, |migration| migration.to);
+    record_version(workspace, version)
+}
+
+fn apply_migration(workspace: &Workspace, migration: &Migration) -> Result<()> {
+    for database in migration.databases() {
+        let path = database.path(workspace);
+        if !path.exists() {
+            // `alisa init` recreates missing databases with the current schema.
+            continue;
+        }
+        let mut conn = Connection::open(&path).with_context(|| {
+            format!(
+                "Failed to open the {} at {}",
+                database.label(),
+                path.display()
+            )
+        })?;
+        let tx = conn.transaction()?;
+        for step in migration.steps {
+            if let Step::Sql {
+                database: target,
+                sql,
+            } = step
+                && *target == database
+            {
+                tx.execute_batch(sql).with_context(|| {
+                    format!(
+                        "Migration {} → {} failed on the {}",
+                        migration.from,
+                        migration.to,
+                        database.label()
+                    )
+                })?;
+            }
+        }
+        tx.commit()?;
+    }
+
+    for step in migration.steps {
+        if let Step::Layout { description, apply } = step {
+            apply(workspace).with_context(|| {
+                format!(
+                    "Migration {} → {} failed: {}",
+                    migration.from, migration.to, description
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+fn record_version(workspace: &Workspace, version: &str) -> Result<()> {
+    let marker = workspace.schema_version_path();
+    fs::write(&marker, format!("{version}\n"))
+        .with_context(|| format!("Failed to write schema version at {}", marker.display()))?;
+
+    let manifest_path = workspace.manifest_path();
+    if let Some(mut manifest) = read_manifest(&manifest_path)? {
+        manifest.schema_version = version.to_string();
+        manifest.alisa_version = env!("CARGO_PKG_VERSION").to_string();
+        write_manifest(&manifest_path, &manifest)?;
+    }
+    Ok(())
+}
+
+fn rechain_audit(workspace: &Workspace) -> Result<()> {
+    if workspace.audit_index_path().exists() {
+        AuditLog::open(workspace)?.rechain()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN: &[Migration] = &[
+        Migration {
+            from: "1.0",
+            to: "1.1",
+            description: "first",
+            steps: &[],
+        },
+        Migration {
+            from: "1.1",
+            to: "1.2",
+            description: "second",
+            steps: &[],
+        },
+    ];
+
+    fn descriptions(migrations: &[&Migration]) -> Vec<&'static str> {
+        migrations
+            .iter()
+            .map(|migration| migration.description)
+            .collect()
+    }
+
+    #[test]
+    fn plans_chain_from_the_recorded_version() {
+        assert_eq!(
+            descriptions(&plan_between(CHAIN, "1.0", "1.2").unwrap()),
+            ["first", "second"]
+        );
+        assert_eq!(
+            descriptions(&plan_between(CHAIN, "1.1", "1.2").unwrap()),
+            ["second"]
+        );
+        assert!(plan_between(CHAIN, "1.2", "1.2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_or_newer_versions_have_no_path() {
+        let err = plan_between(CHAIN, "0.9", "1.2").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no migration path from schema version 0.9 to 1.2"
+        );
+        assert!(plan_between(CHAIN, "2.0", "1.2").is_err());
+    }
+
+    #[test]
+    fn registered_migrations_reach_the_current_version() {
+        let chain = plan(MIGRATIONS[0].from).unwrap();
+        assert_eq!(chain.last().unwrap().to, MANIFEST_SCHEMA_VERSION);
+        for migration in MIGRATIONS {
+            for step in migration.steps {
+                if let Step::Sql { sql, .. } = step {
+                    assert!(!sql.contains("BEGIN") && !sql.contains("COMMIT"));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags, params};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::{config::Limits, runtime::limits::estimate_tokens, tasks::Task};
+
+mod chunk;
+
+pub use chunk::DocKind;
+
+/// Files larger than this are not indexed.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Most chunks retrieved for a prompt.
+pub const TOP_K: usize = 5;
+/// Retrieved chunks may use this fraction (1/n) of `limits.max_tokens`.
+const BUDGET_DIVISOR: u64 = 4;
+/// Token budget for retrieved chunks when no `max_tokens` limit is set.
+const DEFAULT_TOKEN_BUDGET: u64 = 2_000;
+/// Terms taken from the query text at most.
+const MAX_QUERY_TERMS: usize = 64;
+
+/// Handle to `cache/rag/index.sqlite` created by `alisa init`.
+///
+/// Each `docs` row is one chunk with id `<source>:<start>-<end>`; `meta`
+/// holds the line range, heading, kind and the SHA-256 of the whole file,
+/// which decides whether the file needs re-indexing.
+pub struct RagIndex {
+    conn: Connection,
+}
+
+/// Counts of files touched by [`RagIndex::index_files`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct IndexReport {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub chunks: usize,
+}
+
+/// A chunk returned by [`RagIndex::search`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocHit {
+    pub id: String,
+    pub source: String,
+    pub start_line: u64,
+    pub end_line: u64,
+    pub heading: Option<String>,
+    /// Negated bm25 rank; higher is better.
+    pub score: f64,
+    /// Estimated tokens of `content`.
+    pub tokens: u64,
+    pub content: String,
+}
+
+impl RagIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| {
+            format!(
+                "Failed to open RAG index at {} (run `alisa init` to create it)",
+                path.display()
+            )
+        })?;
+        Ok(Self { conn })
+    }
+
+    /// Brings the index in line with `sources` (paths relative to `root`):
+    /// changed files are re-chunked, unchanged ones are kept and sources no
+    /// longer listed are dropped. `force` re-indexes every file.
+    pub fn index_files(
+        &mut self,
+        root: &Path,
+        sources: &BTreeSet<String>,
+        force: bool,
+    ) -> Result<IndexReport> {
+        let known = self.file_hashes()?;
+        let mut report = IndexReport::default();
+        let tx = self.conn.transaction()?;
+
+        for source in known.keys().filter(|source| !sources.contains(*source)) {
+            delete_source(&tx, source)?;
+            report.removed += 1;
+        }
+
+        for source in sources {
+            let Some(kind) = DocKind::from_path(source) else {
+                continue;
+            };
+            let path = root.join(source);
+            let Ok(text) = fs::read_to_string(&path) else {
+                // Binary or unreadable files are not searchable.
+                continue;
+            };
+            let sha256 = hex::encode(Sha256::digest(text.as_bytes()));
+            if !force && known.get(source) == Some(&sha256) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            delete_source(&tx, source)?;
+            for chunk in chunk::chunk(kind, &text) {
+                let id = format!("{source}:{}-{}", chunk.start_line, chunk.end_line);
+                let meta = json!({
+                    "kind": kind,
+                    "start_line": chunk.start_line,
+                    "end_line": chunk.end_line,
+                    "heading": chunk.heading,
+                    "sha256": sha256,
+                });
+                tx.execute(
+                    "INSERT INTO docs (id, source, meta) VALUES (?1, ?2, ?3)",
+                    params![id, source, meta.to_string()],
+                )
+                .with_context(|| format!("Failed to index {source}"))?;
+                let content = match &chunk.heading {
+                    Some(heading) if !chunk.content.starts_with('#') => {
+                        format!("{heading}\n{}", chunk.content)
+                    }
+                    _ => chunk.content,
+                };
+                tx.execute(
+                    "INSERT INTO docs_fts (doc_id, content) VALUES (?1, ?2)",
+                    params![id, content],
+                )
+                .with_context(|| format!("Failed to index {source}"))?;
+                report.chunks += 1;
+            }
+            report.indexed += 1;
+        }
+
+        tx.commit().context("Failed to commit RAG index")?;
+        Ok(report)
+    }
+
+    /// Chunks matching an FTS5 `query`, best bm25 score first.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<DocHit>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT docs.id, docs.source, docs.meta, docs_fts.content, bm25(docs_fts) AS rank
+                 FROM docs_fts JOIN docs ON docs.id = docs_fts.doc_id
+                 WHERE docs_fts MATCH ?1
+                 ORDER BY rank LIMIT ?2",
+            )
+            .context("Failed to query RAG index")?;
+        let rows = stmt
+            .query_map(params![query, limit as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, f64>(4)?,
+                ))
+            })
+            .and_then(Iterator::collect::<rusqlite::Result<Vec<_>>>)
+            .with_context(|| format!("Invalid RAG query '{query}'"))?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, source, meta, content, rank)| {
+                let meta: Value = meta
+                    .and_then(|meta| serde_json::from_str(&meta).ok())
+                    .unwrap_or_default();
+                DocHit {
+                    id,
+                    source,
+                    start_line: meta["start_line"].as_u64().unwrap_or(0),
+                    end_line: meta["end_line"].as_u64().unwrap_or(0),
+                    heading: meta["heading"].as_str().map(str::to_string),
+                    score: -rank,
+                    tokens: estimate_tokens(&content),
+                    content,
+                }
+            })
+            .collect())
+    }
+
+    /// Best chunks for free `text`: at most [`TOP_K`] whose estimated tokens
+    /// fit in `budget` together.
+    pub fn retrieve(&self, text: &str, budget: u64) -> Result<Vec<DocHit>> {
+        let Some(query) = match_query(text) else {
+            return Ok(Vec::new());
+        };
+        let mut selected = Vec::new();
+        let mut used = 0;
+        for hit in self.search(&query, TOP_K * 4)? {
+            if selected.len() == TOP_K {
+                break;
+            }
+            if used + hit.tokens <= budget {
+                used += hit.tokens;
+                selected.push(hit);
+            }
+        }
+        Ok(selected)
+    }
+
+    /// File hash recorded for every indexed source.
+    fn file_hashes(&self) -> Result<BTreeMap<String, String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT source, meta FROM docs")
+            .context("Failed to read RAG index")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+        let mut hashes = BTreeMap::new();
+        for row in rows {
+            let (source, meta) = row?;
+            let sha256 = meta
+                .and_then(|meta| serde_json::from_str::<Value>(&meta).ok())
+                .and_then(|meta| meta["sha256"].as_str().map(str::to_string))
+                .unwrap_or_default();
+            hashes.insert(source, sha256);
+        }
+        Ok(hashes)
+    }
+}
+
+/// Tokens retrieved chunks may add to a prompt under `limits`.
+pub fn token_budget(limits: &Limits) -> u64 {
+    limits
+        .max_tokens
+        .map_or(DEFAULT_TOKEN_BUDGET, |max| u64::from(max) / BUDGET_DIVISOR)
+}
+
+/// Text a task is matched on: title, description and acceptance criteria.
+pub fn task_text(task: &Task) -> String {
+    let mut text = task.title.clone();
+    for part in task.description.iter().chain(&task.acceptance) {
+        text.push('\n');
+        text.push_str(part);
+    }
+    text
+}
+
+/// Turns free text into an FTS5 query matching any of its words, so
+/// punctuation and FTS operators in the text cannot break the query.
+pub fn match_query(text: &str) -> Option<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.chars().count() >= 3 && !terms.contains(&word) {
+            terms.push(word);
+        }
+        if terms.len() == MAX_QUERY_TERMS {
+            break;
+        }
+    }
+    (!terms.is_empty()).then(|| {
+        terms
+            .iter()
+            .map(|term| format!("\"{term}\""))
+            .collect::<Vec<_>>()
+            .join(" OR ")
+    })
+}
+
+fn delete_source(conn: &Connection, source: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM docs_fts WHERE doc_id IN (SELECT id FROM docs WHERE source = ?1)",
+        [source],
+    )
+    .and_then(|_| conn.execute("DELETE FROM docs WHERE source = ?1", [source]))
+    .with_context(|| format!("Failed to remove {source} from the RAG index"))?;
+    Ok(())
+}
+
+/// Indexable files under `paths` (files or directories relative to `root`),
+/// as `/`-separated paths relative to `root`. Hidden entries are skipped and
+/// paths that do not exist are returned separately.
+pub fn collect_sources(root: &Path, paths: &[String]) -> (BTreeSet<String>, Vec<String>) {
+    let mut sources = BTreeSet::new();
+    let mut missing = Vec::new();
+    for path in paths {
+        let full = root.join(path);
+        if full.is_dir() {
+            walk(root, &full, &mut sources);
+        } else if full.is_file() {
+            add_source(root, &full, &mut sources);
+        } else {
+            missing.push(path.clone());
+        }
+    }
+    (sources, missing)
+}
+
+fn walk(root: &Path, dir: &Path, sources: &mut BTreeSet<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            walk(root, &path, sources);
+        } else {
+            add_source(root, &path, sources);
+        }
+    }
+}
+
+fn add_source(root: &Path, path: &Path, sources: &mut BTreeSet<String>) {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return;
+    };
+    let relative = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let small_enough = fs::metadata(path).is_ok_and(|meta| meta.len() <= MAX_FILE_BYTES);
+    if small_enough && DocKind::from_path(&relative).is_some() {
+        sources.insert(relative);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rag_index(root: &Path) -> RagIndex {
+        let path = root.join("index.sqlite");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(crate::commands::init::RAG_INDEX_SCHEMA_SQL)
+            .unwrap();
+        RagIndex::open(&path).unwrap()
+    }
+
+    fn ids(index: &RagIndex) -> Vec<String> {
+        index
+            .conn
+            .prepare("SELECT id FROM docs ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn retrieval_ranks_chunks_within_the_token_budget() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        let mut index = rag_index(root);
+        fs::write(
+            root.join("auth.md"),
+            "# Login\nLogin uses email and password.\n\n# Tokens\nLogin tokens expire.\n\n# Billing\nInvoices.\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("big.md"),
+            format!("# Login\n{}\n", "login ".repeat(400)),
+        )
+        .unwrap();
+        index
+            .index_files(root, &["auth.md".into(), "big.md".into()].into(), false)
+            .unwrap();
+
+        let hits = index.retrieve("Add login form (email)!", 1_000).unwrap();
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!(ids[0], "auth.md:1-2");
+        assert!(!ids.contains(&"auth.md:7-8"));
+        assert!(ids.contains(&"big.md:1-2"));
+        assert_eq!(hits[0].heading.as_deref(), Some("Login"));
+        assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        let small = index.retrieve("Add login form (email)!", 50).unwrap();
+        assert!(small.iter().all(|hit| hit.source == "auth.md"));
+        assert!(small.iter().map(|hit| hit.tokens).sum::<u64>() <= 50);
+        assert!(index.retrieve("a b", 1_000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn query_and_budget_helpers() {
+        assert_eq!(
+            match_query("Fix \"login\" AND login-form; OR x").as_deref(),
+            Some("\"fix\" OR \"login\" OR \"and\" OR \"form\"")
+        );
+        assert_eq!(match_query("a, b"), None);
+        let limits = Limits {
+            max_tokens: Some(8_000),
+            ..Limits::default()
+        };
+        assert_eq!(token_budget(&limits), 2_000);
+        assert_eq!(token_budget(&Limits::default()), DEFAULT_TOKEN_BUDGET);
+    }
+
+    #[test]
+    fn collects_indexable_files_from_dirs_and_paths() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::create_dir_all(root.join("docs/.hidden")).unwrap();
+        fs::write(root.join("docs/index.md"), "# Home\n").unwrap();
+        fs::write(root.join("docs/guide/setup.md"), "# Setup\n").unwrap();
+        fs::write(root.join("docs/.hidden/skip.md"), "# Skip\n").unwrap();
+        fs::write(root.join("docs/logo.png"), [0u8, 1, 2]).unwrap();
+        fs::write(root.join("notes.txt"), "notes\n").unwrap();
+
+        let (sources, missing) =
+            collect_sources(root, &["docs".into(), "notes.txt".into(), "gone.md".into()]);
+        assert_eq!(
+            sources.into_iter().collect::<Vec<_>>(),
+            ["docs/guide/setup.md", "docs/index.md", "notes.txt"]
+        );
+        assert_eq!(missing, ["gone.md"]);
+    }
+
+    #[test]
+    fn reindexes_only_changed_files() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        let mut index = rag_index(root);
+        fs::write(root.join("a.md"), "# A\nfirst\n\n# B\nsecond\n").unwrap();
+        fs::write(root.join("b.rs"), "fn main() {}\n").unwrap();
+        let sources: BTreeSet<String> = ["a.md".into(), "b.rs".into()].into();
+
+        let report = index.index_files(root, &sources, false).unwrap();
+        assert_eq!((report.indexed, report.chunks), (2, 3));
+        assert_eq!(ids(&index), ["a.md:1-2", "a.md:4-5", "b.rs:1-1"]);
+
+        fs::write(root.join("a.md"), "# A\nfirst, edited\n").unwrap();
+        let report = index.index_files(root, &sources, false).unwrap();
+        assert_eq!((report.indexed, report.unchanged), (1, 1));
+        assert_eq!(ids(&index), ["a.md:1-2", "b.rs:1-1"]);
+
+        let report = index
+            .index_files(root, &["b.rs".into()].into(), false)
+            .unwrap();
+        assert_eq!((report.removed, report.unchanged), (1, 1));
+        assert_eq!(ids(&index), ["b.rs:1-1"]);
+        let fts_rows: i64 = index
+            .conn
+            .query_row("SELECT COUNT(*) FROM docs_fts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(fts_rows, 1);
+
+        let meta: String = index
+            .conn
+            .query_row("SELECT meta FROM docs", [], |row| row.get(0))
+            .unwrap();
+        let meta: Value = serde_json::from_str(&meta).unwrap();
+        assert_eq!(meta["kind"], "code");
+        assert_eq!(
+            (meta["start_line"].as_u64(), meta["end_line"].as_u64()),
+            (Some(1), Some(1))
+        );
+    }
+}
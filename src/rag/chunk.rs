@@ -0,0 +1,234 @@
+use serde::Serialize;
+
+/// Longest chunk, in lines; longer sections are split into windows.
+const MAX_LINES: usize = 80;
+/// Adjacent code blocks are merged until a chunk reaches this many lines.
+const MIN_CODE_LINES: usize = 20;
+
+/// How a file is split into chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocKind {
+    /// Split by heading.
+    Markdown,
+    /// Split into function-sized windows.
+    Code,
+}
+
+impl DocKind {
+    /// Kind of an indexable file, judged by its extension.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "rs" | "py" | "js" | "jsx" | "ts" | "tsx" | "go" | "java" | "kt" | "c" | "h" | "cc"
+            | "cpp" | "hpp" | "cs" | "rb" | "php" | "swift" | "scala" | "sh" | "sql" | "txt"
+            | "rst" | "toml" | "yaml" | "yml" | "json" => Some(Self::Code),
+            _ => None,
+        }
+    }
+}
+
+/// A contiguous range of lines of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// 1-based, inclusive.
+    pub start_line: usize,
+    /// 1-based, inclusive.
+    pub end_line: usize,
+    /// Closest Markdown heading above the chunk.
+    pub heading: Option<String>,
+    pub content: String,
+}
+
+pub fn chunk(kind: DocKind, text: &str) -> Vec<Chunk> {
+    match kind {
+        DocKind::Markdown => chunk_markdown(text),
+        DocKind::Code => chunk_code(text),
+    }
+}
+
+/// One chunk per section; a heading starts a new section unless it sits
+/// inside a fenced code block.
+fn chunk_markdown(text: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut sections: Vec<(usize, Option<String>)> = vec![(0, None)];
+    let mut in_fence = false;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence && let Some(heading) = heading_text(line) {
+            sections.push((index, Some(heading)));
+        }
+    }
+
+    let mut chunks = Vec::new();
+    for (position, (start, heading)) in sections.iter().enumerate() {
+        let end = sections
+            .get(position + 1)
+            .map_or(lines.len(), |(next, _)| *next);
+        for (window_start, window_end) in windows(*start, end) {
+            push_chunk(
+                &mut chunks,
+                &lines,
+                window_start,
+                window_end,
+                heading.clone(),
+            );
+        }
+    }
+    chunks
+}
+
+/// `# Title` → `Title`, for ATX headings of level 1 to 6.
+fn heading_text(line: &str) -> Option<String> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    let rest = &line[hashes..];
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some(rest.trim().trim_end_matches('#').trim_end().to_string())
+}
+
+/// Top-level items (a non-indented line after a blank one) are the units;
+/// small ones are merged and long ones split so chunks stay function-sized.
+fn chunk_code(text: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut boundaries = vec![0];
+    for index in 1..lines.len() {
+        let line = lines[index];
+        let starts_item = !line.trim().is_empty()
+            && !line.starts_with(char::is_whitespace)
+            && !line.starts_with(['}', ')', ']'])
+            && lines[index - 1].trim().is_empty();
+        if starts_item {
+            boundaries.push(index);
+        }
+    }
+    boundaries.push(lines.len());
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    for pair in boundaries.windows(2) {
+        let (block_start, block_end) = (pair[0], pair[1]);
+        if block_end - start > MAX_LINES && block_start > start {
+            push_windows(&mut chunks, &lines, start, block_start);
+            start = block_start;
+        }
+        if block_end - start >= MIN_CODE_LINES {
+            push_windows(&mut chunks, &lines, start, block_end);
+            start = block_end;
+        }
+    }
+    push_windows(&mut chunks, &lines, start, lines.len());
+    chunks
+}
+
+fn push_windows(chunks: &mut Vec<Chunk>, lines: &[&str], start: usize, end: usize) {
+    for (window_start, window_end) in windows(start, end) {
+        push_chunk(chunks, lines, window_start, window_end, None);
+    }
+}
+
+/// Splits `start..end` into ranges of at most [`MAX_LINES`].
+fn windows(start: usize, end: usize) -> impl Iterator<Item = (usize, usize)> {
+    (start..end)
+        .step_by(MAX_LINES)
+        .map(move |window| (window, (window + MAX_LINES).min(end)))
+}
+
+/// Adds `lines[start..end]` unless it is blank; surrounding blank lines are
+/// left out of the recorded range.
+fn push_chunk(
+    chunks: &mut Vec<Chunk>,
+    lines: &[&str],
+    start: usize,
+    end: usize,
+    heading: Option<String>,
+) {
+    let slice = &lines[start..end];
+    let Some(first) = slice.iter().position(|line| !line.trim().is_empty()) else {
+        return;
+    };
+    let last = slice
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .unwrap_or(first);
+    chunks.push(Chunk {
+        start_line: start + first + 1,
+        end_line: start + last + 1,
+        heading,
+        content: slice[first..=last].join("\n"),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(chunks: &[Chunk]) -> Vec<(usize, usize)> {
+        chunks
+            .iter()
+            .map(|chunk| (chunk.start_line, chunk.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn markdown_is_split_by_heading_outside_code_fences() {
+        let text = "Intro line\n\n# Setup\nInstall it.\n\n```sh\n# not a heading\n```\n## Usage ##\nRun it.\n";
+        let chunks = chunk(DocKind::Markdown, text);
+
+        assert_eq!(ranges(&chunks), [(1, 1), (3, 8), (9, 10)]);
+        let headings: Vec<Option<&str>> = chunks
+            .iter()
+            .map(|chunk| chunk.heading.as_deref())
+            .collect();
+        assert_eq!(headings, [None, Some("Setup"), Some("Usage")]);
+        assert!(chunks[1].content.contains("# not a heading"));
+        assert_eq!(heading_text("#hashtag"), None);
+    }
+
+    #[test]
+    fn long_sections_are_split_into_windows() {
+        let text = format!("# Big\n{}", "line\n".repeat(MAX_LINES + 10));
+        let chunks = chunk(DocKind::Markdown, &text);
+        assert_eq!(
+            ranges(&chunks),
+            [(1, MAX_LINES), (MAX_LINES + 1, MAX_LINES + 11)]
+        );
+        assert_eq!(chunks[1].heading.as_deref(), Some("Big"));
+    }
+
+    #[test]
+    fn code_is_split_at_top_level_items() {
+        let function = |name: &str, body: usize| {
+            format!("fn {name}() {{\n{}}}\n\n", "    step();\n".repeat(body))
+        };
+        let text = [
+            function("a", 4),
+            function("b", 4),
+            function("c", 20),
+            function("d", 120),
+        ]
+        .concat();
+        let chunks = chunk(DocKind::Code, &text);
+
+        // a and b are merged up to the minimum size together with c; d is
+        // split into windows.
+        assert_eq!(ranges(&chunks), [(1, 36), (38, 117), (118, 159)]);
+        assert!(chunks[1].content.starts_with("fn d()"));
+        assert!(chunks.iter().all(|chunk| chunk.heading.is_none()));
+    }
+
+    #[test]
+    fn kind_follows_extension() {
+        assert_eq!(DocKind::from_path("docs/guide.MD"), Some(DocKind::Markdown));
+        assert_eq!(DocKind::from_path("src/lib.rs"), Some(DocKind::Code));
+        assert_eq!(DocKind::from_path("logo.png"), None);
+        assert_eq!(DocKind::from_path("Makefile"), None);
+    }
+}
@@ -0,0 +1,754 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{
+    Connection, OpenFlags, OptionalExtension, params, params_from_iter, types::Value as SqlValue,
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::{metadata::current_timestamp, tasks::Task};
+
+/// Status given to registry tasks that no longer appear in the tasks file.
+pub const ARCHIVED_STATUS: &str = "archived";
+
+/// Handle to `state/registry.sqlite` created by `alisa init`.
+pub struct Registry {
+    conn: Connection,
+}
+
+/// A finished stage execution stored as a row of the `runs` table.
+#[derive(Debug, Clone)]
+pub struct StageRecord<'a> {
+    /// Unique row id, `<run_id>:<stage>`.
+    pub id: String,
+    pub task_id: &'a str,
+    pub stage: &'a str,
+    pub started_at: &'a str,
+    pub finished_at: &'a str,
+    pub model: Option<&'a str>,
+    pub profile: Option<&'a str>,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub success: bool,
+    pub meta: Value,
+}
+
+/// A stage about to execute; opens its row of the `runs` table.
+#[derive(Debug, Clone)]
+pub struct StageStart<'a> {
+    /// Unique row id, `<run_id>:<stage>`.
+    pub id: String,
+    pub task_id: &'a str,
+    pub stage: &'a str,
+    pub started_at: &'a str,
+    pub model: Option<&'a str>,
+    pub profile: Option<&'a str>,
+    pub meta: Value,
+}
+
+/// Outcome written to a `runs` row opened by [`Registry::start_stage`].
+#[derive(Debug, Clone)]
+pub struct StageFinish<'a> {
+    pub id: &'a str,
+    pub finished_at: &'a str,
+    /// Model that actually served the stage, replacing the configured one.
+    pub model: Option<&'a str>,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub success: bool,
+    pub meta: Value,
+}
+
+/// Counts of task rows touched by [`Registry::sync_tasks`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TaskSyncReport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub archived: usize,
+}
+
+/// Narrows a [`Registry::search_tasks`] query.
+#[derive(Debug, Clone, Default)]
+pub struct TaskSearchFilter<'a> {
+    pub status: Option<&'a str>,
+    /// Every tag must be present on the task.
+    pub tags: &'a [String],
+    pub include_archived: bool,
+    pub limit: usize,
+}
+
+/// A task matched by full-text search, best matches first.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskSearchHit {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    pub tags: Vec<String>,
+    /// Relevance derived from bm25; higher is better.
+    pub score: f64,
+    /// Matching excerpt with hits wrapped in `**`.
+    pub snippet: String,
+}
+
+/// A row of the `runs` table.
+#[derive(Debug, Clone, Serialize)]
+pub struct StageRow {
+    pub id: String,
+    pub run_id: String,
+    pub task_id: String,
+    pub stage: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub model: Option<String>,
+    pub profile: Option<String>,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub success: bool,
+    pub meta: Value,
+}
+
+/// A row of the `artifacts` table.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactRow {
+    /// `runs.id` of the stage that produced the file.
+    pub stage_id: String,
+    pub kind: String,
+    pub path: String,
+    pub sha256: Option<String>,
+}
+
+const STAGE_ROW_COLUMNS: &str = "id, task_id, stage, started_at, finished_at, model, profile, \
+                                 tokens_in, tokens_out, success, meta";
+
+impl Registry {
+    /// Opens an existing registry database. The schema is owned by `alisa init`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| {
+            format!(
+                "Failed to open registry database at {} (run `alisa init` to create it)",
+                path.display()
+            )
+        })?;
+        Ok(Self { conn })
+    }
+
+    /// Builds the `runs.id` value for a stage of a run.
+    pub fn stage_row_id(run_id: &str, stage: &str) -> String {
+        format!("{run_id}:{stage}")
+    }
+
+    /// Mirrors the tasks file into `tasks` and `tasks_fts`.
+    ///
+    /// Rows are only rewritten when a field changed, so repeated syncs keep
+    /// `updated_at` stable. Tasks missing from `tasks` are marked archived.
+    pub fn sync_tasks(&self, tasks: &[Task]) -> Result<TaskSyncReport> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .context("Failed to start task sync")?;
+        let now = current_timestamp();
+        let mut report = TaskSyncReport::default();
+
+        for task in tasks {
+            let row = TaskRow::new(task);
+            let existing: Option<TaskRow> = tx
+                .query_row(
+                    "SELECT title, content, status, priority, tags, meta FROM tasks WHERE id = ?1",
+                    [&task.id],
+                    |r| {
+                        Ok(TaskRow {
+                            title: r.get(0)?,
+                            content: r.get(1)?,
+                            status: r.get(2)?,
+                            priority: r.get(3)?,
+                            tags: r.get(4)?,
+                            meta: r.get(5)?,
+                        })
+                    },
+                )
+                .optional()
+                .with_context(|| format!("Failed to read task '{}'", task.id))?;
+            match existing {
+                Some(existing) if existing == row => {
+                    report.unchanged += 1;
+                    continue;
+                }
+                Some(_) => report.updated += 1,
+                None => report.added += 1,
+            }
+            tx.execute(
+                "INSERT INTO tasks (id, title, content, status, created_at, updated_at, priority, tags, meta)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7, ?8)
+                 ON CONFLICT(id) DO UPDATE SET
+                    title = excluded.title, content = excluded.content, status = excluded.status,
+                    updated_at = excluded.updated_at, priority = excluded.priority,
+                    tags = excluded.tags, meta = excluded.meta",
+                params![
+                    task.id,
+                    row.title,
+                    row.content,
+                    row.status,
+                    now,
+                    row.priority,
+                    row.tags,
+                    row.meta
+                ],
+            )
+            .with_context(|| format!("Failed to store task '{}'", task.id))?;
+            index_task(&tx, &task.id, &row.title, &fts_content(task))?;
+        }
+
+        let known: HashSet<&str> = tasks.iter().map(|task| task.id.as_str()).collect();
+        let stale: Vec<String> = {
+            let mut stmt = tx
+                .prepare("SELECT id FROM tasks WHERE status != ?1")
+                .context("Failed to list registry tasks")?;
+            let ids = stmt
+                .query_map([ARCHIVED_STATUS], |r| r.get::<_, String>(0))
+                .context("Failed to list registry tasks")?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            ids.into_iter()
+                .filter(|id| !known.contains(id.as_str()))
+                .collect()
+        };
+        for id in &stale {
+            tx.execute(
+                "UPDATE tasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![ARCHIVED_STATUS, now, id],
+            )
+            .with_context(|| format!("Failed to archive task '{id}'"))?;
+        }
+        report.archived = stale.len();
+
+        tx.commit().context("Failed to commit task sync")?;
+        Ok(report)
+    }
+
+    /// Stage rows ordered by start time, optionally limited to one task.
+    pub fn stage_rows(&self, task_id: Option<&str>) -> Result<Vec<StageRow>> {
+        let sql = format!(
+            "SELECT {STAGE_ROW_COLUMNS} FROM runs
+             WHERE ?1 IS NULL OR task_id = ?1
+             ORDER BY started_at, id"
+        );
+        self.query_stage_rows(&sql, [task_id])
+    }
+
+    /// Stage rows of a single run ordered by start time.
+    pub fn run_stage_rows(&self, run_id: &str) -> Result<Vec<StageRow>> {
+        let sql = format!(
+            "SELECT {STAGE_ROW_COLUMNS} FROM runs
+             WHERE id LIKE ?1 ESCAPE '\\'
+             ORDER BY started_at, id"
+        );
+        self.query_stage_rows(&sql, [format!("{}:%", escape_like(run_id))])
+    }
+
+    /// Files registered for the stages of a run.
+    pub fn run_artifacts(&self, run_id: &str) -> Result<Vec<ArtifactRow>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT run_id, kind, path, sha256 FROM artifacts
+                 WHERE run_id LIKE ?1 ESCAPE '\\' ORDER BY path",
+            )
+            .context("Failed to query artifacts")?;
+        let rows = stmt
+            .query_map([format!("{}:%", escape_like(run_id))], |r| {
+                Ok(ArtifactRow {
+                    stage_id: r.get(0)?,
+                    kind: r.get(1)?,
+                    path: r.get(2)?,
+                    sha256: r.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to query artifacts")?;
+        Ok(rows)
+    }
+
+    fn query_stage_rows(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<StageRow>> {
+        let mut stmt = self.conn.prepare(sql).context("Failed to query runs")?;
+        let rows = stmt
+            .query_map(params, |r| {
+                let id: String = r.get(0)?;
+                let meta: Option<String> = r.get(10)?;
+                Ok(StageRow {
+                    run_id: id.split(':').next().unwrap_or_default().to_string(),
+                    id,
+                    task_id: r.get(1)?,
+                    stage: r.get(2)?,
+                    started_at: r.get(3)?,
+                    finished_at: r.get(4)?,
+                    model: r.get(5)?,
+                    profile: r.get(6)?,
+                    tokens_in: r.get(7)?,
+                    tokens_out: r.get(8)?,
+                    success: r.get(9)?,
+                    meta: meta
+                        .and_then(|meta| serde_json::from_str(&meta).ok())
+                        .unwrap_or(Value::Null),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to query runs")?;
+        Ok(rows)
+    }
+
+    /// Runs an FTS5 query over task titles and descriptions ranked by bm25.
+    ///
+    /// Title matches weigh more than description matches.
+    pub fn search_tasks(
+        &self,
+        query: &str,
+        filter: &TaskSearchFilter<'_>,
+    ) -> Result<Vec<TaskSearchHit>> {
+        let mut sql = String::from(
+            "SELECT t.id, t.title, t.status, t.tags, bm25(tasks_fts, 10.0, 1.0) AS rank,
+                    snippet(tasks_fts, -1, '**', '**', '…', 12)
+             FROM tasks_fts JOIN tasks t ON t.rowid = tasks_fts.rowid
+             WHERE tasks_fts MATCH ?1",
+        );
+        let mut values = vec![SqlValue::from(query.to_string())];
+        if let Some(status) = filter.status {
+            values.push(SqlValue::from(status.to_string()));
+            sql.push_str(&format!(" AND t.status = ?{}", values.len()));
+        } else if !filter.include_archived {
+            values.push(SqlValue::from(ARCHIVED_STATUS.to_string()));
+            sql.push_str(&format!(" AND t.status != ?{}", values.len()));
+        }
+        for tag in filter.tags {
+            values.push(SqlValue::from(tag.clone()));
+            sql.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM json_each(t.tags) WHERE value = ?{})",
+                values.len()
+            ));
+        }
+        values.push(SqlValue::from(filter.limit as i64));
+        sql.push_str(&format!(" ORDER BY rank LIMIT ?{}", values.len()));
+
+        let invalid = || format!("Invalid search query '{query}'");
+        let mut stmt = self.conn.prepare(&sql).with_context(invalid)?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |r| {
+                let tags: Option<String> = r.get(3)?;
+                Ok(TaskSearchHit {
+                    id: r.get(0)?,
+                    title: r.get(1)?,
+                    status: r.get(2)?,
+                    tags: tags
+                        .and_then(|tags| serde_json::from_str(&tags).ok())
+                        .unwrap_or_default(),
+                    score: -r.get::<_, f64>(4)?,
+                    snippet: r.get(5)?,
+                })
+            })
+            .with_context(invalid)?;
+        let hits = rows
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .with_context(invalid)?;
+        Ok(hits)
+    }
+
+    /// Inserts or replaces the `runs` row describing a finished stage.
+    pub fn record_stage(&self, record: &StageRecord<'_>) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO runs
+                    (id, task_id, stage, started_at, finished_at, model, profile,
+                     tokens_in, tokens_out, success, meta)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    record.id,
+                    record.task_id,
+                    record.stage,
+                    record.started_at,
+                    record.finished_at,
+                    record.model,
+                    record.profile,
+                    record.tokens_in,
+                    record.tokens_out,
+                    record.success,
+                    record.meta.to_string(),
+                ],
+            )
+            .with_context(|| format!("Failed to record run stage '{}'", record.id))?;
+        Ok(())
+    }
+
+    /// Opens the `runs` row of a stage that is about to execute.
+    ///
+    /// `finished_at` stays empty until [`Registry::finish_stage`] is called,
+    /// so stages that never finished remain visible.
+    pub fn start_stage(&self, record: &StageStart<'_>) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO runs
+                    (id, task_id, stage, started_at, finished_at, model, profile, success, meta)
+                 VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?6, 0, ?7)",
+                params![
+                    record.id,
+                    record.task_id,
+                    record.stage,
+                    record.started_at,
+                    record.model,
+                    record.profile,
+                    record.meta.to_string(),
+                ],
+            )
+            .with_context(|| format!("Failed to record start of run stage '{}'", record.id))?;
+        Ok(())
+    }
+
+    /// Completes a row opened by [`Registry::start_stage`].
+    pub fn finish_stage(&self, finish: &StageFinish<'_>) -> Result<()> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE runs SET finished_at = ?2, model = COALESCE(?7, model), tokens_in = ?3,
+                    tokens_out = ?4, success = ?5, meta = ?6
+                 WHERE id = ?1",
+                params![
+                    finish.id,
+                    finish.finished_at,
+                    finish.tokens_in,
+                    finish.tokens_out,
+                    finish.success,
+                    finish.meta.to_string(),
+                    finish.model,
+                ],
+            )
+            .with_context(|| format!("Failed to record end of run stage '{}'", finish.id))?;
+        if updated == 0 {
+            bail!("Run stage '{}' was never started", finish.id);
+        }
+        Ok(())
+    }
+
+    /// Registers a file produced by a stage together with its SHA-256.
+    ///
+    /// `path` is stored as given; `file` is where it is read from.
+    pub fn record_artifact(
+        &self,
+        stage_id: &str,
+        kind: &str,
+        path: &str,
+        file: &Path,
+    ) -> Result<()> {
+        let contents = fs::read(file)
+            .with_context(|| format!("Failed to read artifact {}", file.display()))?;
+        let sha256 = hex::encode(Sha256::digest(&contents));
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO artifacts (id, run_id, kind, path, sha256)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![format!("{stage_id}:{path}"), stage_id, kind, path, sha256],
+            )
+            .with_context(|| format!("Failed to record artifact {path}"))?;
+        Ok(())
+    }
+}
+
+/// Column values of a `tasks` row derived from a [`Task`].
+#[derive(Debug, PartialEq)]
+struct TaskRow {
+    title: String,
+    content: Option<String>,
+    status: String,
+    priority: i64,
+    tags: String,
+    meta: String,
+}
+
+impl TaskRow {
+    fn new(task: &Task) -> Self {
+        let meta = json!({
+            "priority": task.priority,
+            "lang": task.lang,
+            "depends_on": task.depends_on,
+            "tags": task.tags,
+            "acceptance": task.acceptance,
+            "context": task.context,
+        });
+        Self {
+            title: task.title.clone(),
+            content: task.description.clone(),
+            status: task.status.as_str().to_string(),
+            priority: i64::from(task.priority_rank()),
+            tags: json!(task.tags).to_string(),
+            meta: meta.to_string(),
+        }
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Text indexed for a task: description followed by acceptance criteria.
+fn fts_content(task: &Task) -> String {
+    let mut content = task.description.clone().unwrap_or_default();
+    for item in &task.acceptance {
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(item);
+    }
+    content
+}
+
+/// Replaces the `tasks_fts` entry of a task; both tables share the rowid.
+fn index_task(conn: &Connection, id: &str, title: &str, content: &str) -> Result<()> {
+    let rowid: i64 = conn
+        .query_row("SELECT rowid FROM tasks WHERE id = ?1", [id], |r| r.get(0))
+        .with_context(|| format!("Failed to look up task '{id}'"))?;
+    conn.execute("DELETE FROM tasks_fts WHERE rowid = ?1", [rowid])
+        .and_then(|_| {
+            conn.execute(
+                "INSERT INTO tasks_fts (rowid, title, content) VALUES (?1, ?2, ?3)",
+                params![rowid, title, content],
+            )
+        })
+        .with_context(|| format!("Failed to index task '{id}'"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn open_requires_existing_database() {
+        let temp = tempdir().unwrap();
+        let err = Registry::open(&temp.path().join("registry.sqlite"))
+            .err()
+            .expect("missing registry");
+        assert!(err.to_string().contains("alisa init"));
+    }
+
+    fn registry(dir: &Path) -> Registry {
+        let path = dir.join("registry.sqlite");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(crate::commands::init::REGISTRY_SCHEMA_SQL)
+            .unwrap();
+        Registry::open(&path).unwrap()
+    }
+
+    fn task_set(body: &str) -> Vec<Task> {
+        crate::tasks::TaskSet::from_str(&format!("version = 1\n{body}"))
+            .unwrap()
+            .tasks
+    }
+
+    #[test]
+    fn sync_tasks_is_idempotent_and_archives_removed_tasks() {
+        let temp = tempdir().unwrap();
+        let registry = registry(temp.path());
+        let tasks = task_set(
+            r#"
+[[tasks]]
+id = "A-1"
+title = "Add login"
+description = "Email and password form"
+tags = ["auth"]
+acceptance = ["rejects bad passwords"]
+
+[[tasks]]
+id = "A-2"
+title = "Add logout"
+priority = "high"
+"#,
+        );
+
+        let first = registry.sync_tasks(&tasks).unwrap();
+        assert_eq!((first.added, first.updated, first.archived), (2, 0, 0));
+        let again = registry.sync_tasks(&tasks).unwrap();
+        assert_eq!((again.added, again.updated, again.unchanged), (0, 0, 2));
+
+        let (priority, meta): (i64, String) = registry
+            .conn
+            .query_row(
+                "SELECT priority, meta FROM tasks WHERE id = 'A-2'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(priority, 1);
+        assert_eq!(
+            serde_json::from_str::<Value>(&meta).unwrap()["priority"],
+            "high"
+        );
+
+        let mut edited = tasks[..1].to_vec();
+        edited[0].title = "Add sign-in".into();
+        let report = registry.sync_tasks(&edited).unwrap();
+        assert_eq!((report.updated, report.archived), (1, 1));
+
+        let status: String = registry
+            .conn
+            .query_row("SELECT status FROM tasks WHERE id = 'A-2'", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(status, ARCHIVED_STATUS);
+        let hits: Vec<String> = registry
+            .conn
+            .prepare("SELECT title FROM tasks_fts WHERE tasks_fts MATCH 'passwords OR sign'")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(hits, ["Add sign-in"]);
+        assert_eq!(registry.sync_tasks(&edited).unwrap().archived, 0);
+    }
+
+    #[test]
+    fn search_tasks_ranks_title_matches_first() {
+        let temp = tempdir().unwrap();
+        let registry = registry(temp.path());
+        let tasks = task_set(
+            r#"
+[[tasks]]
+id = "A-1"
+title = "Refresh tokens"
+description = "Rotate the login session"
+tags = ["auth"]
+
+[[tasks]]
+id = "A-2"
+title = "Add login page"
+status = "done"
+tags = ["auth", "ui"]
+
+[[tasks]]
+id = "B-1"
+title = "Login docs"
+"#,
+        );
+        registry.sync_tasks(&tasks).unwrap();
+
+        let all = TaskSearchFilter {
+            limit: 10,
+            ..TaskSearchFilter::default()
+        };
+        let hits = registry.search_tasks("login", &all).unwrap();
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!(ids.last(), Some(&"A-1"));
+        assert_eq!(hits[2].snippet, "Rotate the **login** session");
+
+        let tags = ["auth".to_string()];
+        let filtered = TaskSearchFilter {
+            status: Some("todo"),
+            tags: &tags,
+            ..all.clone()
+        };
+        let hits = registry.search_tasks("login", &filtered).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "A-1");
+
+        let err = registry.search_tasks("login AND", &all).unwrap_err();
+        assert!(err.to_string().contains("Invalid search query"));
+    }
+
+    #[test]
+    fn stage_rows_are_opened_then_finished_with_artifacts() {
+        let temp = tempdir().unwrap();
+        let registry = registry(temp.path());
+        registry
+            .sync_tasks(&task_set("[[tasks]]\nid = \"A-1\"\ntitle = \"A\"\n"))
+            .unwrap();
+
+        let id = Registry::stage_row_id("run_1", "plan");
+        registry
+            .start_stage(&StageStart {
+                id: id.clone(),
+                task_id: "A-1",
+                stage: "plan",
+                started_at: "2026-10-16T10:00:00Z",
+                model: Some("sonnet"),
+                profile: None,
+                meta: json!({}),
+            })
+            .unwrap();
+        let finished: Option<String> = registry
+            .conn
+            .query_row("SELECT finished_at FROM runs WHERE id = ?1", [&id], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(finished, None);
+
+        registry
+            .finish_stage(&StageFinish {
+                id: &id,
+                finished_at: "2026-10-16T10:00:05Z",
+                model: None,
+                tokens_in: 120,
+                tokens_out: 30,
+                success: true,
+                meta: json!({ "runner": "local" }),
+            })
+            .unwrap();
+        let row: (String, i64, i64, bool) = registry
+            .conn
+            .query_row(
+                "SELECT finished_at, tokens_in, tokens_out, success FROM runs WHERE id = ?1",
+                [&id],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(row, ("2026-10-16T10:00:05Z".to_string(), 120, 30, true));
+
+        let file = temp.path().join("stdout.log");
+        fs::write(&file, "hello\n").unwrap();
+        registry
+            .record_artifact(&id, "stdout", "runs/run_1/plan/stdout.log", &file)
+            .unwrap();
+        let sha: String = registry
+            .conn
+            .query_row(
+                "SELECT sha256 FROM artifacts WHERE run_id = ?1",
+                [&id],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            sha,
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+        );
+
+        let err = registry
+            .finish_stage(&StageFinish {
+                id: "run_2:plan",
+                finished_at: "",
+                model: None,
+                tokens_in: 0,
+                tokens_out: 0,
+                success: false,
+                meta: json!({}),
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("never started"));
+    }
+
+    #[test]
+    fn stage_row_ids_include_stage() {
+        assert_eq!(
+            Registry::stage_row_id("run_1", "review:lint"),
+            "run_1:review:lint"
+        );
+    }
+}
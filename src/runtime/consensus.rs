@@ -0,0 +1,198 @@
+use serde::Serialize;
+
+use crate::config::{ReviewConsensus, ReviewPipeline};
+
+/// Share of the total weight required by `weighted` consensus without an explicit threshold.
+pub const DEFAULT_WEIGHTED_THRESHOLD: f32 = 0.5;
+
+/// Weight of a stage missing from the pipeline `weights` map.
+pub const DEFAULT_STAGE_WEIGHT: f32 = 1.0;
+
+/// Outcome of a single review stage as seen by the aggregator.
+#[derive(Debug, Clone, Copy)]
+pub struct Vote<'a> {
+    pub stage: &'a str,
+    pub passed: bool,
+}
+
+/// Final decision of a review pipeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsensusDecision {
+    pub consensus: ReviewConsensus,
+    pub passed: bool,
+    /// Human-readable explanation of the decision.
+    pub reason: String,
+    /// Failing stages listed in `fail_on`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub vetoed_by: Vec<String>,
+    /// Passing share of the votes (majority) or of the total weight (weighted).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+}
+
+/// Aggregates stage votes according to the pipeline consensus and `fail_on` vetoes.
+pub fn evaluate(pipeline: &ReviewPipeline, votes: &[Vote<'_>]) -> ConsensusDecision {
+    let consensus = pipeline.consensus();
+    let failing: Vec<&str> = votes
+        .iter()
+        .filter(|vote| !vote.passed)
+        .map(|vote| vote.stage)
+        .collect();
+
+    let vetoed_by: Vec<String> = failing
+        .iter()
+        .filter(|stage| pipeline.fail_on.iter().any(|veto| veto == *stage))
+        .map(|stage| stage.to_string())
+        .collect();
+    if !vetoed_by.is_empty() {
+        return ConsensusDecision {
+            consensus,
+            passed: false,
+            reason: format!("vetoed by failing fail_on stages: {}", vetoed_by.join(", ")),
+            vetoed_by,
+            score: None,
+        };
+    }
+
+    let (passed, reason, score) = match consensus {
+        ReviewConsensus::Gate => {
+            if failing.is_empty() {
+                (true, "all stages passed".to_string(), None)
+            } else {
+                (
+                    false,
+                    format!("failing stages: {}", failing.join(", ")),
+                    None,
+                )
+            }
+        }
+        ReviewConsensus::Majority => {
+            let passing = votes.len() - failing.len();
+            let passed = passing * 2 > votes.len();
+            let reason = format!(
+                "{passing} of {} stages passed ({} majority)",
+                votes.len(),
+                if passed { "reached" } else { "no" }
+            );
+            (
+                passed,
+                reason,
+                Some(share(passing as f32, votes.len() as f32)),
+            )
+        }
+        ReviewConsensus::Weighted => {
+            let threshold = pipeline.threshold.unwrap_or(DEFAULT_WEIGHTED_THRESHOLD);
+            let weight = |stage: &str| {
+                pipeline
+                    .weights
+                    .get(stage)
+                    .copied()
+                    .unwrap_or(DEFAULT_STAGE_WEIGHT)
+            };
+            let total: f32 = votes.iter().map(|vote| weight(vote.stage)).sum();
+            let passing: f32 = votes
+                .iter()
+                .filter(|vote| vote.passed)
+                .map(|vote| weight(vote.stage))
+                .sum();
+            let score = share(passing, total);
+            let passed = total > 0.0 && score >= threshold;
+            let reason = format!(
+                "passing weight {passing:.2} of {total:.2} ({score:.2}) is {} threshold {threshold:.2}",
+                if passed { "at or above" } else { "below" }
+            );
+            (passed, reason, Some(score))
+        }
+    };
+
+    ConsensusDecision {
+        consensus,
+        passed,
+        reason,
+        vetoed_by,
+        score,
+    }
+}
+
+fn share(part: f32, total: f32) -> f32 {
+    if total > 0.0 { part / total } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn pipeline(consensus: ReviewConsensus) -> ReviewPipeline {
+        ReviewPipeline {
+            stages: vec!["build".into(), "lint".into(), "llm".into()],
+            consensus: Some(consensus),
+            ..ReviewPipeline::default()
+        }
+    }
+
+    fn votes(results: &[(&'static str, bool)]) -> Vec<Vote<'static>> {
+        results
+            .iter()
+            .map(|&(stage, passed)| Vote { stage, passed })
+            .collect()
+    }
+
+    #[test]
+    fn gate_fails_on_any_failing_stage() {
+        let decision = evaluate(
+            &ReviewPipeline::default(),
+            &votes(&[("build", true), ("lint", false)]),
+        );
+        assert_eq!(decision.consensus, ReviewConsensus::Gate);
+        assert!(!decision.passed);
+        assert_eq!(decision.reason, "failing stages: lint");
+
+        let decision = evaluate(&ReviewPipeline::default(), &votes(&[("build", true)]));
+        assert!(decision.passed);
+    }
+
+    #[test]
+    fn majority_requires_more_than_half() {
+        let pipeline = pipeline(ReviewConsensus::Majority);
+        let decision = evaluate(
+            &pipeline,
+            &votes(&[("build", true), ("lint", false), ("llm", true)]),
+        );
+        assert!(decision.passed);
+        assert_eq!(decision.reason, "2 of 3 stages passed (reached majority)");
+
+        let decision = evaluate(&pipeline, &votes(&[("build", true), ("lint", false)]));
+        assert!(!decision.passed);
+    }
+
+    #[test]
+    fn weighted_uses_weights_and_threshold() {
+        let mut pipeline = pipeline(ReviewConsensus::Weighted);
+        pipeline.weights = HashMap::from([("build".into(), 3.0), ("llm".into(), 0.5)]);
+        let results = votes(&[("build", true), ("lint", false), ("llm", false)]);
+
+        let decision = evaluate(&pipeline, &results);
+        assert!(decision.passed);
+        assert_eq!(decision.score, Some(3.0 / 4.5));
+
+        pipeline.threshold = Some(0.8);
+        let decision = evaluate(&pipeline, &results);
+        assert!(!decision.passed);
+        assert!(decision.reason.contains("below threshold 0.80"));
+    }
+
+    #[test]
+    fn fail_on_vetoes_any_consensus() {
+        let mut pipeline = pipeline(ReviewConsensus::Majority);
+        pipeline.fail_on = vec!["build".into()];
+
+        let decision = evaluate(
+            &pipeline,
+            &votes(&[("build", false), ("lint", true), ("llm", true)]),
+        );
+        assert!(!decision.passed);
+        assert_eq!(decision.vetoed_by, ["build"]);
+        assert_eq!(decision.reason, "vetoed by failing fail_on stages: build");
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::config::{PromptInput, RunnerDef};
+
+/// Placeholder in runner args replaced with the absolute path of the prompt file.
+pub const PROMPT_FILE_PLACEHOLDER: &str = "{prompt_file}";
+
+/// Placeholder in runner args replaced with the configured model name.
+pub const MODEL_PLACEHOLDER: &str = "{model}";
+
+/// Environment variable exposing the configured model to the runner.
+pub const MODEL_ENV: &str = "ALISA_MODEL";
+
+const PROMPT_FILE_NAME: &str = "prompt.md";
+const STDOUT_FILE_NAME: &str = "stdout.log";
+const STDERR_FILE_NAME: &str = "stderr.log";
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// Time processes get to exit after SIGTERM before they are killed.
+const KILL_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum ExecError {
+    #[error("failed to prepare {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("failed to spawn runner '{runner}' ({cmd}): {source}")]
+    Spawn {
+        runner: String,
+        cmd: String,
+        source: io::Error,
+    },
+    #[error("failed to wait for runner '{runner}': {source}")]
+    Wait { runner: String, source: io::Error },
+}
+
+/// Everything needed to launch a single runner invocation.
+#[derive(Debug, Clone, Copy)]
+pub struct ExecRequest<'a> {
+    pub runner_name: &'a str,
+    pub runner: &'a RunnerDef,
+    pub prompt: &'a str,
+    /// Directory the runner process starts in (usually the project root).
+    pub working_dir: &'a Path,
+    /// Directory that receives the prompt and captured output streams.
+    pub output_dir: &'a Path,
+    /// Flag that aborts the runner when raised (e.g. on Ctrl+C).
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+/// How the runner process ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitState {
+    /// The process exited on its own with the given code.
+    Exited(i32),
+    /// The process was terminated by a signal it did not trigger through us.
+    Signaled,
+    /// `timeout_ms` expired and the process group was killed.
+    TimedOut,
+    /// The cancel flag was raised and the process group was killed.
+    Cancelled,
+}
+
+/// Structured result of a runner invocation.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecOutcome {
+    pub runner: String,
+    pub model: Option<String>,
+    pub state: ExitState,
+    pub duration_ms: u64,
+    pub prompt_path: PathBuf,
+    pub stdout_path: PathBuf,
+    pub stderr_path: PathBuf,
+}
+
+impl ExecOutcome {
+    pub fn success(&self) -> bool {
+        self.state == ExitState::Exited(0)
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.state {
+            ExitState::Exited(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Reads the captured stdout, replacing invalid UTF-8 sequences.
+    pub fn read_stdout(&self) -> Result<String, ExecError> {
+        fs::read(&self.stdout_path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .map_err(|source| ExecError::Io {
+                path: self.stdout_path.clone(),
+                source,
+            })
+    }
+
+    /// Human-readable reason for a non-successful outcome.
+    pub fn failure_reason(&self, timeout_ms: Option<u64>) -> String {
+        match self.state {
+            ExitState::Exited(code) => format!("runner exited with status {code}"),
+            ExitState::Signaled => "runner was terminated by a signal".to_string(),
+            ExitState::TimedOut => format!(
+                "runner timed out after {} ms",
+                timeout_ms.unwrap_or(self.duration_ms)
+            ),
+            ExitState::Cancelled => "runner was cancelled".to_string(),
+        }
+    }
+}
+
+/// Launches the runner, feeds it the prompt and waits for completion or timeout.
+pub fn execute(request: &ExecRequest<'_>) -> Result<ExecOutcome, ExecError> {
+    let runner = request.runner;
+    fs::create_dir_all(request.output_dir).map_err(|source| ExecError::Io {
+        path: request.output_dir.to_path_buf(),
+        source,
+    })?;
+
+    let prompt_path = request.output_dir.join(PROMPT_FILE_NAME);
+    let stdout_path = request.output_dir.join(STDOUT_FILE_NAME);
+    let stderr_path = request.output_dir.join(STDERR_FILE_NAME);
+    write_file(&prompt_path, request.prompt.as_bytes())?;
+    let prompt_path = absolute(&prompt_path);
+
+    let mut command = Command::new(&runner.cmd);
+    command
+        .args(expand_args(runner, &prompt_path))
+        .envs(&runner.env)
+        .current_dir(request.working_dir)
+        .stdout(create_file(&stdout_path)?)
+        .stderr(create_file(&stderr_path)?);
+    if let Some(model) = runner.model.as_deref() {
+        command.env(MODEL_ENV, model);
+    }
+    match runner.prompt_input {
+        PromptInput::Stdin => command.stdin(Stdio::piped()),
+        PromptInput::File => command.stdin(Stdio::null()),
+    };
+    isolate_process_group(&mut command);
+
+    let started = Instant::now();
+    let mut child = command.spawn().map_err(|source| ExecError::Spawn {
+        runner: request.runner_name.to_string(),
+        cmd: runner.cmd.clone(),
+        source,
+    })?;
+
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = request.prompt.to_owned();
+        thread::spawn(move || {
+            // The runner may exit without consuming its whole prompt.
+            let _ = stdin.write_all(input.as_bytes());
+        })
+    });
+
+    let deadline = runner
+        .timeout_ms
+        .map(|ms| started + Duration::from_millis(ms));
+    let state =
+        wait_for_exit(&mut child, deadline, request.cancel).map_err(|source| ExecError::Wait {
+            runner: request.runner_name.to_string(),
+            source,
+        })?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+
+    Ok(ExecOutcome {
+        runner: request.runner_name.to_string(),
+        model: runner.model.clone(),
+        state,
+        duration_ms: started.elapsed().as_millis() as u64,
+        prompt_path,
+        stdout_path,
+        stderr_path,
+    })
+}
+
+fn expand_args(runner: &RunnerDef, prompt_path: &Path) -> Vec<String> {
+    let prompt_file = prompt_path.display().to_string();
+    let mut uses_prompt_file = false;
+    let mut args: Vec<String> = runner
+        .args
+        .iter()
+        .map(|arg| {
+            if arg.contains(PROMPT_FILE_PLACEHOLDER) {
+                uses_prompt_file = true;
+            }
+            let arg = arg.replace(PROMPT_FILE_PLACEHOLDER, &prompt_file);
+            match runner.model.as_deref() {
+                Some(model) => arg.replace(MODEL_PLACEHOLDER, model),
+                None => arg,
+            }
+        })
+        .collect();
+
+    if runner.prompt_input == PromptInput::File && !uses_prompt_file {
+        args.push(prompt_file);
+    }
+    args
+}
+
+fn wait_for_exit(
+    child: &mut Child,
+    deadline: Option<Instant>,
+    cancel: Option<&AtomicBool>,
+) -> io::Result<ExitState> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            // Helpers the runner left in the background must not outlive it.
+            kill_process_group(child)?;
+            return Ok(exit_state(status));
+        }
+
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            kill_process_group(child)?;
+            return Ok(ExitState::TimedOut);
+        }
+
+        if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+            kill_process_group(child)?;
+            return Ok(ExitState::Cancelled);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn exit_state(status: ExitStatus) -> ExitState {
+    match status.code() {
+        Some(code) => ExitState::Exited(code),
+        None => ExitState::Signaled,
+    }
+}
+
+/// Starts the runner in its own process group so that every helper process
+/// it spawned can be taken down with it, and terminal Ctrl+C is routed
+/// through us.
+#[cfg(unix)]
+fn isolate_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn isolate_process_group(_command: &mut Command) {}
+
+/// Sends SIGTERM to the runner's process group, SIGKILL to whatever is still
+/// there after [`KILL_GRACE`], and reaps the runner.
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) -> io::Result<()> {
+    let pgid = child.id() as libc::pid_t;
+    if signal_process_group(pgid, libc::SIGTERM)? {
+        let deadline = Instant::now() + KILL_GRACE;
+        loop {
+            // A reaped runner no longer counts as a member of its group.
+            child.try_wait()?;
+            if !signal_process_group(pgid, 0)? {
+                break;
+            }
+            if Instant::now() >= deadline {
+                signal_process_group(pgid, libc::SIGKILL)?;
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+    child.wait().map(|_| ())
+}
+
+/// Returns `false` when the group has no processes left.
+#[cfg(unix)]
+fn signal_process_group(pgid: libc::pid_t, signal: libc::c_int) -> io::Result<bool> {
+    let rc = unsafe { libc::kill(-pgid, signal) };
+    if rc == -1 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::ESRCH) {
+            return Ok(false);
+        }
+        return Err(err);
+    }
+    Ok(true)
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) -> io::Result<()> {
+    match child.kill() {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::InvalidInput => {}
+        Err(err) => return Err(err),
+    }
+    child.wait().map(|_| ())
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), ExecError> {
+    fs::write(path, contents).map_err(|source| ExecError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn create_file(path: &Path) -> Result<File, ExecError> {
+    File::create(path).map_err(|source| ExecError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn shell_runner(script: &str) -> RunnerDef {
+        RunnerDef {
+            cmd: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            ..RunnerDef::default()
+        }
+    }
+
+    fn run(runner: &RunnerDef, dir: &Path) -> ExecOutcome {
+        execute(&ExecRequest {
+            runner_name: "local",
+            runner,
+            prompt: "hello runner",
+            working_dir: dir,
+            output_dir: &dir.join("out"),
+            cancel: None,
+        })
+        .expect("runner executes")
+    }
+
+    #[test]
+    fn pipes_prompt_over_stdin_and_captures_streams() {
+        let temp = tempdir().unwrap();
+        let runner = shell_runner("cat; echo oops >&2; exit 3");
+
+        let outcome = run(&runner, temp.path());
+        assert_eq!(outcome.state, ExitState::Exited(3));
+        assert!(!outcome.success());
+        assert_eq!(outcome.read_stdout().unwrap(), "hello runner");
+        assert_eq!(fs::read_to_string(&outcome.stderr_path).unwrap(), "oops\n");
+        assert_eq!(
+            fs::read_to_string(&outcome.prompt_path).unwrap(),
+            "hello runner"
+        );
+    }
+
+    #[test]
+    fn passes_prompt_file_model_and_env() {
+        let temp = tempdir().unwrap();
+        let runner = RunnerDef {
+            cmd: "sh".into(),
+            args: vec![
+                "-c".into(),
+                "cat \"$1\"; echo \" $2 $ALISA_MODEL $EXTRA\"".into(),
+                "sh".into(),
+                PROMPT_FILE_PLACEHOLDER.into(),
+                "--model={model}".into(),
+            ],
+            model: Some("gpt-x".into()),
+            env: HashMap::from([("EXTRA".to_string(), "42".to_string())]),
+            prompt_input: PromptInput::File,
+            ..RunnerDef::default()
+        };
+
+        let outcome = run(&runner, temp.path());
+        assert!(outcome.success());
+        assert_eq!(
+            outcome.read_stdout().unwrap(),
+            "hello runner --model=gpt-x gpt-x 42\n"
+        );
+    }
+
+    #[test]
+    fn appends_prompt_file_when_placeholder_missing() {
+        let runner = RunnerDef {
+            cmd: "claude".into(),
+            args: vec!["-p".into()],
+            prompt_input: PromptInput::File,
+            ..RunnerDef::default()
+        };
+
+        let args = expand_args(&runner, Path::new("/tmp/run/prompt.md"));
+        assert_eq!(args, vec!["-p", "/tmp/run/prompt.md"]);
+    }
+
+    #[test]
+    fn timeout_kills_whole_process_group() {
+        let temp = tempdir().unwrap();
+        let marker = temp.path().join("marker");
+        let mut runner = shell_runner(&format!(
+            "(sleep 1; touch '{}') & sleep 30",
+            marker.display()
+        ));
+        runner.timeout_ms = Some(200);
+
+        let outcome = run(&runner, temp.path());
+        assert_eq!(outcome.state, ExitState::TimedOut);
+        assert!(outcome.duration_ms < 5_000);
+        assert!(outcome.failure_reason(runner.timeout_ms).contains("200 ms"));
+
+        thread::sleep(Duration::from_millis(1_500));
+        assert!(!marker.exists(), "background helper must be killed too");
+    }
+
+    #[test]
+    fn background_helpers_do_not_outlive_the_runner() {
+        let temp = tempdir().unwrap();
+        let marker = temp.path().join("marker");
+        let runner = shell_runner(&format!(
+            "(sleep 1; touch '{}') & exit 0",
+            marker.display()
+        ));
+
+        let outcome = run(&runner, temp.path());
+        assert!(outcome.success());
+
+        thread::sleep(Duration::from_millis(1_500));
+        assert!(!marker.exists(), "background helper must be killed");
+    }
+
+    #[test]
+    fn cancel_flag_stops_runner() {
+        let temp = tempdir().unwrap();
+        let runner = shell_runner("sleep 30");
+        let cancel = AtomicBool::new(true);
+
+        let outcome = execute(&ExecRequest {
+            runner_name: "local",
+            runner: &runner,
+            prompt: "",
+            working_dir: temp.path(),
+            output_dir: &temp.path().join("out"),
+            cancel: Some(&cancel),
+        })
+        .expect("runner executes");
+        assert_eq!(outcome.state, ExitState::Cancelled);
+    }
+
+    #[test]
+    fn reports_spawn_failures() {
+        let temp = tempdir().unwrap();
+        let runner = RunnerDef {
+            cmd: "definitely-not-a-real-runner".into(),
+            ..RunnerDef::default()
+        };
+
+        let err = execute(&ExecRequest {
+            runner_name: "ghost",
+            runner: &runner,
+            prompt: "",
+            working_dir: temp.path(),
+            output_dir: &temp.path().join("out"),
+            cancel: None,
+        })
+        .expect_err("spawn must fail");
+        assert!(matches!(err, ExecError::Spawn { .. }));
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::patch::PatchStats;
+use crate::config::Limits;
+
+/// Rough characters-per-token ratio used when runners do not report usage.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimates the number of tokens in `text`.
+pub fn estimate_tokens(text: &str) -> u64 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u64
+}
+
+/// What a code stage produced, measured for limit checks.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ChangeMeasure {
+    pub files: usize,
+    pub changed_lines: usize,
+    pub prompt_tokens: u64,
+    pub response_tokens: u64,
+}
+
+impl ChangeMeasure {
+    pub fn new(stats: PatchStats, prompt_tokens: u64, response_tokens: u64) -> Self {
+        Self {
+            files: stats.files,
+            changed_lines: stats.changed_lines(),
+            prompt_tokens,
+            response_tokens,
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.response_tokens
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitViolation {
+    pub limit: &'static str,
+    pub actual: u64,
+    pub max: u64,
+}
+
+/// Result of checking a measured change against the effective limits.
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitReport {
+    pub profile: Option<String>,
+    pub measured: ChangeMeasure,
+    pub violations: Vec<LimitViolation>,
+}
+
+impl LimitReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for LimitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.profile {
+            Some(profile) => write!(f, "change exceeds limits of profile '{profile}':")?,
+            None => write!(f, "change exceeds configured limits:")?,
+        }
+        for violation in &self.violations {
+            write!(
+                f,
+                "\n  {}: {} (limit {})",
+                violation.limit, violation.actual, violation.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks every configured limit against the measured change.
+pub fn check(limits: &Limits, profile: Option<&str>, measured: ChangeMeasure) -> LimitReport {
+    let checks = [
+        ("max_files", limits.max_files, measured.files as u64),
+        (
+            "max_changed_lines",
+            limits.max_changed_lines,
+            measured.changed_lines as u64,
+        ),
+        ("max_tokens", limits.max_tokens, measured.total_tokens()),
+    ];
+    let violations = checks
+        .into_iter()
+        .filter_map(|(limit, max, actual)| {
+            let max = u64::from(max?);
+            (actual > max).then_some(LimitViolation { limit, actual, max })
+        })
+        .collect();
+
+    LimitReport {
+        profile: profile.map(str::to_string),
+        measured,
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure() -> ChangeMeasure {
+        ChangeMeasure::new(
+            PatchStats {
+                files: 3,
+                added: 40,
+                removed: 20,
+            },
+            900,
+            300,
+        )
+    }
+
+    #[test]
+    fn estimates_tokens_from_characters() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn reports_each_exceeded_limit() {
+        let limits = Limits {
+            max_files: Some(2),
+            max_tokens: Some(1000),
+            max_changed_lines: Some(60),
+        };
+
+        let report = check(&limits, Some("fast"), measure());
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "change exceeds limits of profile 'fast':\n  max_files: 3 (limit 2)\n  max_tokens: 1200 (limit 1000)"
+        );
+    }
+
+    #[test]
+    fn unset_limits_are_not_checked() {
+        let report = check(&Limits::default(), None, measure());
+        assert!(report.passed());
+    }
+}
@@ -1 +1,8 @@
+pub mod consensus;
+pub mod executor;
+pub mod limits;
+pub mod patch;
+pub mod prompt;
 pub mod resolver;
+pub mod review;
+pub mod verdict;
@@ -0,0 +1,633 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PatchError {
+    #[error("malformed hunk header on line {line}: {header}")]
+    BadHunkHeader { line: usize, header: String },
+    #[error("hunk starting on line {line} for {path} ends early")]
+    TruncatedHunk { line: usize, path: String },
+    #[error("hunk on line {line} appears before any file header")]
+    HunkWithoutFile { line: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum ApplyError {
+    #[error("{path}: path must be relative and stay inside the project")]
+    UnsafePath { path: String },
+    #[error("{path}: file to create already exists")]
+    AlreadyExists { path: String },
+    #[error("{path}: file to patch does not exist")]
+    Missing { path: String },
+    #[error("{path}: hunk #{hunk} (expected at line {line}) does not apply")]
+    HunkFailed {
+        path: String,
+        hunk: usize,
+        line: usize,
+    },
+    #[error("{path}: deleted file still has content after applying the patch")]
+    DeleteMismatch { path: String },
+    #[error("failed to update {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+}
+
+/// How a patch changes a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChange {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+impl FileChange {
+    /// Single-letter marker as used by `git status --short`.
+    pub fn marker(&self) -> char {
+        match self {
+            FileChange::Added => 'A',
+            FileChange::Modified => 'M',
+            FileChange::Deleted => 'D',
+            FileChange::Renamed => 'R',
+        }
+    }
+}
+
+/// A unified diff touching one or more files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    pub files: Vec<FilePatch>,
+}
+
+/// Changes to a single file. `None` paths stand for `/dev/null` (file added or deleted).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Size of a patch as checked against `Limits`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PatchStats {
+    pub files: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl PatchStats {
+    pub fn changed_lines(&self) -> usize {
+        self.added + self.removed
+    }
+}
+
+impl Patch {
+    /// Pulls the unified diff out of runner output: the contents of ```diff/```patch
+    /// fences when present, otherwise everything from the first file header on.
+    pub fn extract(output: &str) -> Option<String> {
+        let mut fenced = String::new();
+        let mut in_fence = false;
+        for line in output.lines() {
+            let trimmed = line.trim_start();
+            if let Some(info) = trimmed.strip_prefix("```") {
+                if in_fence {
+                    in_fence = false;
+                } else if matches!(info.trim(), "diff" | "patch") {
+                    in_fence = true;
+                }
+                continue;
+            }
+            if in_fence {
+                fenced.push_str(line);
+                fenced.push('\n');
+            }
+        }
+        if !fenced.trim().is_empty() {
+            return Some(fenced);
+        }
+
+        let lines: Vec<&str> = output.lines().collect();
+        let start = lines.iter().enumerate().position(|(idx, line)| {
+            line.starts_with("diff --git ")
+                || (line.starts_with("--- ")
+                    && lines
+                        .get(idx + 1)
+                        .is_some_and(|next| next.starts_with("+++ ")))
+        })?;
+        let mut diff = lines[start..].join("\n");
+        diff.push('\n');
+        Some(diff)
+    }
+
+    /// Parses a unified diff. Lines outside file headers and hunks (`diff --git`,
+    /// `index`, prose) are ignored.
+    pub fn parse(text: &str) -> Result<Self, PatchError> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut files: Vec<FilePatch> = Vec::new();
+        let mut idx = 0;
+
+        while idx < lines.len() {
+            let line = lines[idx];
+            if let Some(old) = line.strip_prefix("--- ")
+                && let Some(new) = lines
+                    .get(idx + 1)
+                    .and_then(|next| next.strip_prefix("+++ "))
+            {
+                files.push(FilePatch {
+                    old_path: header_path(old, "a/"),
+                    new_path: header_path(new, "b/"),
+                    hunks: Vec::new(),
+                });
+                idx += 2;
+                continue;
+            }
+            if line.starts_with("@@") {
+                let header_line = idx + 1;
+                let file = files
+                    .last_mut()
+                    .ok_or(PatchError::HunkWithoutFile { line: header_line })?;
+                let (old_start, old_len, new_start, new_len) =
+                    parse_hunk_header(line).ok_or_else(|| PatchError::BadHunkHeader {
+                        line: header_line,
+                        header: line.to_string(),
+                    })?;
+                let mut hunk = Hunk {
+                    old_start,
+                    old_len,
+                    new_start,
+                    new_len,
+                    lines: Vec::new(),
+                };
+                let (mut old_seen, mut new_seen) = (0, 0);
+                idx += 1;
+                while old_seen < old_len || new_seen < new_len {
+                    let Some(body) = lines.get(idx) else {
+                        return Err(PatchError::TruncatedHunk {
+                            line: header_line,
+                            path: file.path().to_string(),
+                        });
+                    };
+                    idx += 1;
+                    let hunk_line = match body.chars().next() {
+                        Some('+') => {
+                            new_seen += 1;
+                            HunkLine::Added(body[1..].to_string())
+                        }
+                        Some('-') => {
+                            old_seen += 1;
+                            HunkLine::Removed(body[1..].to_string())
+                        }
+                        Some('\\') => continue,
+                        // Some tools strip the leading space of empty context lines.
+                        Some(' ') | None => {
+                            old_seen += 1;
+                            new_seen += 1;
+                            HunkLine::Context(body.get(1..).unwrap_or("").to_string())
+                        }
+                        Some(_) => {
+                            return Err(PatchError::TruncatedHunk {
+                                line: header_line,
+                                path: file.path().to_string(),
+                            });
+                        }
+                    };
+                    hunk.lines.push(hunk_line);
+                }
+                file.hunks.push(hunk);
+                continue;
+            }
+            idx += 1;
+        }
+
+        Ok(Self { files })
+    }
+
+    pub fn stats(&self) -> PatchStats {
+        let mut stats = PatchStats {
+            files: self.files.len(),
+            ..PatchStats::default()
+        };
+        for line in self
+            .files
+            .iter()
+            .flat_map(|file| &file.hunks)
+            .flat_map(|hunk| &hunk.lines)
+        {
+            match line {
+                HunkLine::Added(_) => stats.added += 1,
+                HunkLine::Removed(_) => stats.removed += 1,
+                HunkLine::Context(_) => {}
+            }
+        }
+        stats
+    }
+}
+
+impl Patch {
+    /// Applies every file of the patch below `root`.
+    ///
+    /// All hunks are checked before anything is written, and files already written
+    /// are restored if a later write fails, so the tree is either fully patched or
+    /// left untouched.
+    pub fn apply(&self, root: &Path) -> Result<(), ApplyError> {
+        let mut writes: Vec<PlannedWrite> = Vec::new();
+        for file in &self.files {
+            plan_file(root, file, &mut writes)?;
+        }
+
+        for (done, write) in writes.iter().enumerate() {
+            if let Err(err) = write.perform() {
+                for previous in writes[..done].iter().rev() {
+                    previous.revert();
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FilePatch {
+    pub fn change(&self) -> FileChange {
+        match (&self.old_path, &self.new_path) {
+            (None, _) => FileChange::Added,
+            (_, None) => FileChange::Deleted,
+            (Some(old), Some(new)) if old != new => FileChange::Renamed,
+            _ => FileChange::Modified,
+        }
+    }
+
+    pub fn stats(&self) -> PatchStats {
+        Patch {
+            files: vec![self.clone()],
+        }
+        .stats()
+    }
+
+    /// Path of the file after the change (or before it, for deletions).
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("/dev/null")
+    }
+}
+
+/// A single file write (or removal) together with what to restore on rollback.
+struct PlannedWrite {
+    path: PathBuf,
+    original: Option<String>,
+    content: Option<String>,
+}
+
+impl PlannedWrite {
+    fn perform(&self) -> Result<(), ApplyError> {
+        let io_error = |source| ApplyError::Io {
+            path: self.path.clone(),
+            source,
+        };
+        match &self.content {
+            Some(content) => {
+                if let Some(parent) = self.path.parent() {
+                    fs::create_dir_all(parent).map_err(io_error)?;
+                }
+                fs::write(&self.path, content).map_err(io_error)
+            }
+            None => fs::remove_file(&self.path).map_err(io_error),
+        }
+    }
+
+    fn revert(&self) {
+        let result = match &self.original {
+            Some(original) => fs::write(&self.path, original),
+            None => fs::remove_file(&self.path),
+        };
+        if let Err(err) = result {
+            eprintln!(
+                "[warn] Failed to restore {} after a failed patch: {err}",
+                self.path.display()
+            );
+        }
+    }
+}
+
+fn plan_file(
+    root: &Path,
+    file: &FilePatch,
+    writes: &mut Vec<PlannedWrite>,
+) -> Result<(), ApplyError> {
+    let source = file
+        .old_path
+        .as_deref()
+        .map(|path| resolve_path(root, path))
+        .transpose()?;
+    let target = file
+        .new_path
+        .as_deref()
+        .map(|path| resolve_path(root, path))
+        .transpose()?;
+
+    let original = match &source {
+        Some(path) => Some(
+            current_content(writes, path)?.ok_or_else(|| ApplyError::Missing {
+                path: file.old_path.clone().unwrap_or_default(),
+            })?,
+        ),
+        None => {
+            if let Some(path) = &target
+                && current_content(writes, path)?.is_some()
+            {
+                return Err(ApplyError::AlreadyExists {
+                    path: file.path().to_string(),
+                });
+            }
+            None
+        }
+    };
+    let patched =
+        apply_hunks(original.as_deref().unwrap_or(""), &file.hunks).map_err(|(hunk, line)| {
+            ApplyError::HunkFailed {
+                path: file.path().to_string(),
+                hunk,
+                line,
+            }
+        })?;
+
+    let mut record = |path: PathBuf, content: Option<String>| -> Result<(), ApplyError> {
+        let original = current_content(writes, &path)?;
+        writes.push(PlannedWrite {
+            path,
+            original,
+            content,
+        });
+        Ok(())
+    };
+    match (source, target) {
+        (Some(source), None) => {
+            if !patched.is_empty() {
+                return Err(ApplyError::DeleteMismatch {
+                    path: file.path().to_string(),
+                });
+            }
+            record(source, None)
+        }
+        (Some(source), Some(target)) if source != target => {
+            record(target, Some(patched))?;
+            record(source, None)
+        }
+        (_, Some(target)) => record(target, Some(patched)),
+        (None, None) => Ok(()),
+    }
+}
+
+/// Content of `path` as left by the writes planned so far, or as it is on disk.
+fn current_content(writes: &[PlannedWrite], path: &Path) -> Result<Option<String>, ApplyError> {
+    if let Some(planned) = writes.iter().rev().find(|write| write.path == path) {
+        return Ok(planned.content.clone());
+    }
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(ApplyError::Io {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+fn resolve_path(root: &Path, path: &str) -> Result<PathBuf, ApplyError> {
+    let relative = Path::new(path);
+    let safe = !path.is_empty()
+        && relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !safe {
+        return Err(ApplyError::UnsafePath {
+            path: path.to_string(),
+        });
+    }
+    Ok(root.join(relative))
+}
+
+/// Applies hunks to `original`, locating each one at its recorded position or the
+/// nearest offset where its context matches. Fails with the 1-based hunk number and
+/// expected line of the first hunk that does not match.
+fn apply_hunks(original: &str, hunks: &[Hunk]) -> Result<String, (usize, usize)> {
+    let lines: Vec<&str> = original.lines().collect();
+    let trailing_newline = original.is_empty() || original.ends_with('\n');
+    let mut output: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut pos = 0;
+
+    for (idx, hunk) in hunks.iter().enumerate() {
+        let old: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Removed(text) => Some(text.as_str()),
+                HunkLine::Added(_) => None,
+            })
+            .collect();
+        // Pure insertions record the line *after* which they go.
+        let expected = if hunk.old_len == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let at = find_hunk(&lines, &old, expected, pos).ok_or((idx + 1, hunk.old_start))?;
+
+        output.extend_from_slice(&lines[pos..at]);
+        output.extend(hunk.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Added(text) => Some(text.as_str()),
+            HunkLine::Removed(_) => None,
+        }));
+        pos = at + old.len();
+    }
+    output.extend_from_slice(&lines[pos..]);
+
+    let mut patched = output.join("\n");
+    if trailing_newline && !output.is_empty() {
+        patched.push('\n');
+    }
+    Ok(patched)
+}
+
+fn find_hunk(lines: &[&str], old: &[&str], expected: usize, min: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(old.len())?;
+    let matches = |at: usize| at >= min && at <= last && lines[at..at + old.len()] == *old;
+    (0..=lines.len()).find_map(|offset| {
+        [expected.checked_add(offset), expected.checked_sub(offset)]
+            .into_iter()
+            .flatten()
+            .find(|&at| matches(at))
+    })
+}
+
+fn header_path(header: &str, prefix: &str) -> Option<String> {
+    // Drop the optional timestamp separated by a tab.
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// Parses `@@ -old_start[,old_len] +new_start[,new_len] @@`.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let mut parts = line.strip_prefix("@@ ")?.split_whitespace();
+    let (old_start, old_len) = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_len) = parse_range(parts.next()?.strip_prefix('+')?)?;
+    (parts.next()? == "@@").then_some((old_start, old_len, new_start, new_len))
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ fn main() {
+-    old();
++    new();
++    more();
+ }
+--- /dev/null
++++ b/NOTES.md
+@@ -0,0 +1 @@
++hello
+";
+
+    #[test]
+    fn parses_files_hunks_and_stats() {
+        let patch = Patch::parse(DIFF).expect("parses");
+        assert_eq!(patch.files.len(), 2);
+        assert_eq!(patch.files[0].path(), "src/lib.rs");
+        assert_eq!(patch.files[1].old_path, None);
+        assert_eq!(patch.files[1].path(), "NOTES.md");
+        assert_eq!(
+            patch.files[0].hunks[0].lines[1],
+            HunkLine::Removed("    old();".into())
+        );
+        assert_eq!(
+            patch.stats(),
+            PatchStats {
+                files: 2,
+                added: 3,
+                removed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn extracts_diff_from_fences_or_prose() {
+        let fenced = format!("Here is the change:\n\n```diff\n{DIFF}```\nDone.\n");
+        assert_eq!(Patch::extract(&fenced).as_deref(), Some(DIFF));
+
+        let prose = format!("Summary of work\n- item\n{DIFF}");
+        let extracted = Patch::extract(&prose).expect("diff found");
+        assert!(extracted.starts_with("diff --git"));
+
+        assert_eq!(Patch::extract("no changes\n- just a list\n"), None);
+    }
+
+    #[test]
+    fn applies_patch_to_tree() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "// header\nfn main() {\n    old();\n}\n",
+        )
+        .unwrap();
+
+        let patch = Patch::parse(DIFF).unwrap();
+        assert_eq!(patch.files[0].change(), FileChange::Modified);
+        assert_eq!(patch.files[1].change(), FileChange::Added);
+        patch.apply(root).expect("applies with offset");
+
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "// header\nfn main() {\n    new();\n    more();\n}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("NOTES.md")).unwrap(),
+            "hello\n"
+        );
+    }
+
+    #[test]
+    fn failed_hunk_leaves_tree_untouched() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn main() {\n    other();\n}\n").unwrap();
+
+        // NOTES.md would be created first if files were applied one by one.
+        let reordered = "--- /dev/null\n+++ b/NOTES.md\n@@ -0,0 +1 @@\n+hello\n\
+                         --- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    old();\n+    new();\n }\n";
+        let err = Patch::parse(reordered)
+            .unwrap()
+            .apply(root)
+            .expect_err("hunk fails");
+        assert!(matches!(err, ApplyError::HunkFailed { hunk: 1, .. }));
+        assert!(!root.join("NOTES.md").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "fn main() {\n    other();\n}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_paths_outside_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let patch = Patch::parse("--- /dev/null\n+++ b/../evil\n@@ -0,0 +1 @@\n+x\n").unwrap();
+        assert!(matches!(
+            patch.apply(temp.path()),
+            Err(ApplyError::UnsafePath { .. })
+        ));
+    }
+
+    #[test]
+    fn reports_truncated_hunks() {
+        let err = Patch::parse("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-a\n").expect_err("truncated");
+        assert_eq!(
+            err,
+            PatchError::TruncatedHunk {
+                line: 3,
+                path: "x".into()
+            }
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use minijinja::Environment;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::review::StageVerdict;
+use crate::{
+    config::{RoleKind, RunnerDef},
+    rag::DocHit,
+    tasks::Task,
+};
+
+/// File extension of role templates inside `prompt_dir`.
+pub const TEMPLATE_EXTENSION: &str = "md";
+
+const PLAN_TEMPLATE: &str = r#"# Task {{ task.id }}: {{ task.title }}
+
+{% if task.description %}
+{{ task.description | trim }}
+
+{% endif %}
+{% if task.acceptance %}
+## Acceptance criteria
+
+{% for item in task.acceptance %}
+- {{ item }}
+{% endfor %}
+
+{% endif %}
+{% include "context" %}
+{% include "docs" %}
+## Your role: plan
+
+Produce a step-by-step implementation plan for this task. Do not write the code yet.
+"#;
+
+const CODE_TEMPLATE: &str = r#"# Task {{ task.id }}: {{ task.title }}
+
+{% if task.description %}
+{{ task.description | trim }}
+
+{% endif %}
+{% if task.acceptance %}
+## Acceptance criteria
+
+{% for item in task.acceptance %}
+- {{ item }}
+{% endfor %}
+
+{% endif %}
+{% include "context" %}
+{% include "docs" %}
+{% if stages.plan %}
+## Output of the plan stage
+
+{{ stages.plan | trim }}
+
+{% endif %}
+## Your role: code
+
+Implement the plan above.
+"#;
+
+const REVIEW_TEMPLATE: &str = r#"# Task {{ task.id }}: {{ task.title }}
+
+{% if task.description %}
+{{ task.description | trim }}
+
+{% endif %}
+{% if task.acceptance %}
+## Acceptance criteria
+
+{% for item in task.acceptance %}
+- {{ item }}
+{% endfor %}
+
+{% endif %}
+{% for name, output in stages | items %}
+## Output of the {{ name }} stage
+
+{{ output | trim }}
+
+{% endfor %}
+## Your role: review
+
+Check the change against the acceptance criteria and report whether it passes.
+"#;
+
+const ARBITER_TEMPLATE: &str = r#"# Task {{ task.id }}: {{ task.title }}
+
+{% if task.acceptance %}
+## Acceptance criteria
+
+{% for item in task.acceptance %}
+- {{ item }}
+{% endfor %}
+
+{% endif %}
+## Review verdicts
+
+{% for verdict in verdicts %}
+- {{ verdict.stage }} ({{ verdict.kind }}): {{ "pass" if verdict.passed else "fail" }} — {{ verdict.summary }}
+{% endfor %}
+
+## Your role: arbiter
+
+Weigh the verdicts above and decide whether the change should be accepted.
+"#;
+
+const CONTEXT_PARTIAL: &str = r#"{% if task.context.scope or task.context.code or task.context.docs %}
+## Context
+
+{% if task.context.scope %}
+Scope: {{ task.context.scope | join(", ") }}
+{% endif %}
+{% if task.context.code %}
+Relevant code: {{ task.context.code | join(", ") }}
+{% endif %}
+{% if task.context.docs %}
+Relevant docs: {{ task.context.docs | join(", ") }}
+{% endif %}
+
+{% endif %}
+"#;
+
+const DOCS_PARTIAL: &str = r#"{% if docs %}
+## Reference documentation
+
+{% for doc in docs %}
+### {{ doc.source }}:{{ doc.start_line }}-{{ doc.end_line }}
+
+{{ doc.content | trim }}
+
+{% endfor %}
+{% endif %}
+"#;
+
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("prompt directory {path} does not exist")]
+    MissingPromptDir { path: PathBuf },
+    #[error("failed to read prompt template {path}: {source}")]
+    ReadFailed {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to render prompt template {source_name}: {source}")]
+    RenderFailed {
+        source_name: String,
+        source: minijinja::Error,
+    },
+}
+
+/// Where a prompt template came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSource {
+    File(PathBuf),
+    Builtin(&'static str),
+}
+
+impl fmt::Display for TemplateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateSource::File(path) => write!(f, "{}", path.display()),
+            TemplateSource::Builtin(name) => write!(f, "builtin:{name}"),
+        }
+    }
+}
+
+/// A loaded prompt template that can be rendered with a [`PromptContext`].
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    pub source: TemplateSource,
+    pub body: String,
+}
+
+/// Variables available to prompt templates.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptContext<'a> {
+    pub task: &'a Task,
+    /// Free-form `[tasks.context]` keys (also flattened into `task.context`).
+    pub extra: &'a BTreeMap<String, toml::Value>,
+    pub role: &'a str,
+    pub profile: Option<&'a str>,
+    pub runner: &'a str,
+    pub model: Option<&'a str>,
+    /// Outputs of the stages that already ran, keyed by stage name.
+    pub stages: BTreeMap<String, String>,
+    /// Verdicts of review stages that already ran in the current pipeline.
+    pub verdicts: &'a [StageVerdict],
+    /// Documentation chunks retrieved for the task.
+    pub docs: &'a [DocHit],
+}
+
+impl PromptTemplate {
+    /// Loads `<prompt_dir>/<role>.md` for the runner, falling back to the builtin template.
+    pub fn for_role(
+        project_root: &Path,
+        runner: &RunnerDef,
+        role: RoleKind,
+    ) -> Result<Self, PromptError> {
+        Ok(Self::from_prompt_dir(project_root, runner, role.as_str())?
+            .unwrap_or_else(|| Self::builtin(role)))
+    }
+
+    /// Loads `<prompt_dir>/arbiter.md` for the runner, falling back to the builtin template.
+    pub fn for_arbiter(project_root: &Path, runner: &RunnerDef) -> Result<Self, PromptError> {
+        Ok(
+            Self::from_prompt_dir(project_root, runner, "arbiter")?.unwrap_or_else(|| Self {
+                source: TemplateSource::Builtin("arbiter"),
+                body: ARBITER_TEMPLATE.to_string(),
+            }),
+        )
+    }
+
+    fn from_prompt_dir(
+        project_root: &Path,
+        runner: &RunnerDef,
+        name: &str,
+    ) -> Result<Option<Self>, PromptError> {
+        let Some(dir) = runner.prompt_dir.as_deref() else {
+            return Ok(None);
+        };
+        let dir = project_root.join(dir);
+        if !dir.is_dir() {
+            return Err(PromptError::MissingPromptDir { path: dir });
+        }
+        let path = dir.join(format!("{name}.{TEMPLATE_EXTENSION}"));
+        if path.exists() {
+            Self::from_file(&path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, PromptError> {
+        let body = fs::read_to_string(path).map_err(|source| PromptError::ReadFailed {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self {
+            source: TemplateSource::File(path.to_path_buf()),
+            body,
+        })
+    }
+
+    pub fn builtin(role: RoleKind) -> Self {
+        let body = match role {
+            RoleKind::Plan => PLAN_TEMPLATE,
+            RoleKind::Code => CODE_TEMPLATE,
+            RoleKind::Review => REVIEW_TEMPLATE,
+        };
+        Self {
+            source: TemplateSource::Builtin(role.as_str()),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn render(&self, context: &PromptContext<'_>) -> Result<String, PromptError> {
+        let source_name = self.source.to_string();
+        let render_error = |source| PromptError::RenderFailed {
+            source_name: source_name.clone(),
+            source,
+        };
+
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_keep_trailing_newline(true);
+        env.add_template("context", CONTEXT_PARTIAL)
+            .map_err(render_error)?;
+        env.add_template("docs", DOCS_PARTIAL)
+            .map_err(render_error)?;
+        env.add_template("prompt", &self.body)
+            .map_err(render_error)?;
+        env.get_template("prompt")
+            .and_then(|template| template.render(context))
+            .map_err(render_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::TaskSet;
+    use tempfile::tempdir;
+
+    fn task() -> Task {
+        TaskSet::from_str(
+            r#"
+version = 1
+
+[[tasks]]
+id = "A-1"
+title = "Add login"
+description = "Implement the login form."
+acceptance = ["form validates email"]
+tags = ["auth", "ui"]
+
+[tasks.context]
+code = ["src/login.rs"]
+ticket = "JIRA-7"
+"#,
+        )
+        .expect("tasks parse")
+        .tasks
+        .remove(0)
+    }
+
+    fn context<'a>(task: &'a Task, role: &'a str) -> PromptContext<'a> {
+        PromptContext {
+            task,
+            extra: &task.context.extra,
+            role,
+            profile: Some("default"),
+            runner: "claude",
+            model: Some("sonnet"),
+            stages: BTreeMap::from([("plan".to_string(), "1. add form\n".to_string())]),
+            verdicts: &[],
+            docs: &[],
+        }
+    }
+
+    #[test]
+    fn builtin_code_template_includes_task_and_plan() {
+        let task = task();
+        let prompt = PromptTemplate::builtin(RoleKind::Code)
+            .render(&context(&task, "code"))
+            .expect("renders");
+
+        assert!(prompt.starts_with("# Task A-1: Add login\n\nImplement the login form.\n"));
+        assert!(prompt.contains("- form validates email\n"));
+        assert!(prompt.contains("Relevant code: src/login.rs\n"));
+        assert!(prompt.contains("## Output of the plan stage\n\n1. add form\n"));
+        assert!(prompt.contains("## Your role: code"));
+    }
+
+    #[test]
+    fn retrieved_docs_are_rendered_with_their_location() {
+        let task = task();
+        let docs = [DocHit {
+            id: "docs/auth.md:3-5".into(),
+            source: "docs/auth.md".into(),
+            start_line: 3,
+            end_line: 5,
+            heading: Some("Login".into()),
+            score: 1.5,
+            tokens: 4,
+            content: "# Login\nUse email.\n".into(),
+        }];
+        let mut context = context(&task, "plan");
+        context.docs = &docs;
+        let prompt = PromptTemplate::builtin(RoleKind::Plan)
+            .render(&context)
+            .expect("renders");
+
+        assert!(prompt.contains(
+            "## Reference documentation\n\n### docs/auth.md:3-5\n\n# Login\nUse email.\n\n## Your role: plan"
+        ));
+        let review = PromptTemplate::builtin(RoleKind::Review)
+            .render(&context)
+            .expect("renders");
+        assert!(!review.contains("Reference documentation"));
+    }
+
+    #[test]
+    fn custom_template_sees_tags_extra_and_profile() {
+        let temp = tempdir().unwrap();
+        let prompts = temp.path().join("prompts");
+        fs::create_dir_all(&prompts).unwrap();
+        fs::write(
+            prompts.join("plan.md"),
+            "{{ task.tags | join(',') }} {{ extra.ticket }}/{{ task.context.ticket }} {{ profile }} {{ model }}",
+        )
+        .unwrap();
+        let runner = RunnerDef {
+            prompt_dir: Some("prompts".into()),
+            ..RunnerDef::default()
+        };
+
+        let task = task();
+        let template =
+            PromptTemplate::for_role(temp.path(), &runner, RoleKind::Plan).expect("template");
+        assert_eq!(
+            template.source,
+            TemplateSource::File(prompts.join("plan.md"))
+        );
+        let prompt = template.render(&context(&task, "plan")).expect("renders");
+        assert_eq!(prompt, "auth,ui JIRA-7/JIRA-7 default sonnet");
+
+        let fallback =
+            PromptTemplate::for_role(temp.path(), &runner, RoleKind::Review).expect("template");
+        assert_eq!(fallback.source, TemplateSource::Builtin("review"));
+    }
+
+    #[test]
+    fn missing_prompt_dir_is_reported() {
+        let temp = tempdir().unwrap();
+        let runner = RunnerDef {
+            prompt_dir: Some("nope".into()),
+            ..RunnerDef::default()
+        };
+
+        let err = PromptTemplate::for_role(temp.path(), &runner, RoleKind::Plan)
+            .expect_err("missing dir");
+        assert!(matches!(err, PromptError::MissingPromptDir { .. }));
+    }
+
+    #[test]
+    fn render_errors_name_the_template() {
+        let task = task();
+        let template = PromptTemplate {
+            source: TemplateSource::File("prompts/plan.md".into()),
+            body: "{% if %}".into(),
+        };
+
+        let err = template
+            .render(&context(&task, "plan"))
+            .expect_err("syntax error");
+        assert!(err.to_string().contains("prompts/plan.md"));
+    }
+}
@@ -4,8 +4,11 @@ use globset::Glob;
 use thiserror::Error;
 
 use crate::{
-    config::{Config, ReviewPipeline, ReviewStage, RoleKind, RoutingRule},
-    tasks::{Task, TaskLlmOverrides},
+    config::{
+        ConditionValues, Config, ReviewPipeline, ReviewStage, RoleKind, RoutingConditions,
+        RoutingRule, Specificity,
+    },
+    tasks::{Task, TaskLlmOverrides, TaskStatus},
 };
 
 #[derive(Debug, Clone, Default)]
@@ -35,6 +38,9 @@ pub struct TaskMeta {
     pub lang: Option<String>,
     pub llm: Option<TaskLlmOverrides>,
     pub paths: Vec<String>,
+    pub tags: Vec<String>,
+    pub priority: Option<String>,
+    pub status: Option<TaskStatus>,
 }
 
 impl TaskMeta {
@@ -54,6 +60,9 @@ impl From<&Task> for TaskMeta {
             lang: task.lang.clone(),
             llm: task.llm.clone(),
             paths,
+            tags: task.tags.clone(),
+            priority: task.priority.clone(),
+            status: Some(task.status),
         }
     }
 }
@@ -64,6 +73,81 @@ pub struct ResolvedRunners {
     pub plan: String,
     pub code: String,
     pub review: String,
+    /// How each role was decided, in resolution order.
+    pub trace: Vec<RoleTrace>,
+}
+
+/// Layer of the precedence chain that picked a role's runner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecisionSource {
+    /// `--plan-llm`, `--code-llm` or `--review-llm`.
+    CliRole,
+    /// `--llm`.
+    CliLlm,
+    /// The task's `llm` table.
+    Task,
+    /// A `[[routing]]` rule, by position in the config.
+    Routing { index: usize },
+    /// The roles of the active profile.
+    Profile { name: String },
+    /// The top-level `[roles]` table.
+    Roles,
+}
+
+/// One condition of a routing rule and whether the task satisfied it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionCheck {
+    /// The condition as written, e.g. `lang = rust`.
+    pub condition: String,
+    /// What the task had instead, if anything.
+    pub actual: Option<String>,
+    pub passed: bool,
+    /// Checks of the blocks inside an `any`, `all` or `not` condition.
+    pub nested: Vec<ConditionCheck>,
+}
+
+impl ConditionCheck {
+    fn value(condition: String, actual: Option<String>, passed: bool) -> Self {
+        Self {
+            condition,
+            actual,
+            passed,
+            nested: Vec::new(),
+        }
+    }
+
+    fn block(condition: &str, nested: Vec<ConditionCheck>, passed: bool) -> Self {
+        Self {
+            condition: condition.to_string(),
+            actual: None,
+            passed,
+            nested,
+        }
+    }
+}
+
+/// A routing rule for the role that was tested against the task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleCheck {
+    pub index: usize,
+    pub conditions: Vec<ConditionCheck>,
+    pub matched: bool,
+    pub priority: i32,
+    pub specificity: Specificity,
+}
+
+/// How the runner of one role was chosen.
+#[derive(Debug, Clone)]
+pub struct RoleTrace {
+    pub role: RoleKind,
+    pub runner: String,
+    pub source: DecisionSource,
+    /// Profile active while the role was resolved.
+    pub profile: Option<String>,
+    /// Every routing rule for the role that was tested.
+    pub rules: Vec<RuleCheck>,
+    /// Profile the matching routing rule switched to.
+    pub profile_switch: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -109,56 +193,51 @@ pub fn resolve_runners(
     let lang = cli
         .lang
         .as_deref()
-        .or_else(|| task.lang.as_deref())
+        .or(task.lang.as_deref())
         .map(|value| value.to_ascii_lowercase());
     let mut current_profile = initial_profile.clone();
-
-    let plan = resolve_role(
-        RoleKind::Plan,
-        config,
-        cli,
-        task,
-        lang.as_deref(),
-        current_profile.as_deref(),
-    )?;
-    if let Some(new_profile) = plan.profile_override {
-        ensure_profile(config, &new_profile)?;
-        current_profile = Some(new_profile);
-    }
-
-    let code = resolve_role(
-        RoleKind::Code,
-        config,
-        cli,
-        task,
-        lang.as_deref(),
-        current_profile.as_deref(),
-    )?;
-    if let Some(new_profile) = code.profile_override {
-        ensure_profile(config, &new_profile)?;
-        current_profile = Some(new_profile);
-    }
-
-    let review = resolve_role(
-        RoleKind::Review,
-        config,
-        cli,
-        task,
-        lang.as_deref(),
-        current_profile.as_deref(),
-    )?;
-    if let Some(new_profile) = review.profile_override {
-        ensure_profile(config, &new_profile)?;
-        current_profile = Some(new_profile);
+    let mut trace = Vec::new();
+
+    for role in [RoleKind::Plan, RoleKind::Code, RoleKind::Review] {
+        let resolution = resolve_role(
+            role,
+            config,
+            cli,
+            task,
+            lang.as_deref(),
+            current_profile.as_deref(),
+        )?;
+        if let Some(new_profile) = &resolution.profile_override {
+            ensure_profile(config, new_profile)?;
+        }
+        trace.push(RoleTrace {
+            role,
+            runner: resolution.runner,
+            source: resolution.source,
+            profile: current_profile.clone(),
+            rules: resolution.rules,
+            profile_switch: resolution.profile_override.clone(),
+        });
+        if let Some(new_profile) = resolution.profile_override {
+            current_profile = Some(new_profile);
+        }
     }
 
     let final_profile = current_profile.or(initial_profile);
+    let runner = |role: RoleKind| {
+        trace
+            .iter()
+            .find(|decision| decision.role == role)
+            .map(|decision| decision.runner.clone())
+            .expect("every role is resolved")
+    };
 
     Ok(ResolvedRunners {
         profile: final_profile,
-        plan: plan.runner,
-        code: code.runner,
-        review: review.runner,
+        plan: runner(RoleKind::Plan),
+        code: runner(RoleKind::Code),
+        review: runner(RoleKind::Review),
+        trace,
     })
 }
 
@@ -171,7 +250,7 @@ pub fn resolve_review_pipeline<'a>(
         .pipeline
         .as_deref()
         .or_else(|| task.llm.as_ref().and_then(|llm| llm.pipeline.as_deref()))
-        .or_else(|| config.review.default_pipeline.as_deref())
+        .or(config.review.default_pipeline.as_deref())
         .ok_or(ResolveError::PipelineNotSpecified)?;
 
     let pipeline = config
@@ -208,6 +287,19 @@ pub fn resolve_review_pipeline<'a>(
 struct RoleResolution {
     runner: String,
     profile_override: Option<String>,
+    source: DecisionSource,
+    rules: Vec<RuleCheck>,
+}
+
+impl RoleResolution {
+    fn new(runner: &str, source: DecisionSource, rules: Vec<RuleCheck>) -> Self {
+        Self {
+            runner: runner.to_string(),
+            profile_override: None,
+            source,
+            rules,
+        }
+    }
 }
 
 fn resolve_role(
@@ -220,35 +312,31 @@ fn resolve_role(
 ) -> Result<RoleResolution, ResolveError> {
     if let Some(name) = cli.role_override(role) {
         ensure_runner(config, name)?;
-        return Ok(RoleResolution {
-            runner: name.to_string(),
-            profile_override: None,
-        });
+        return Ok(RoleResolution::new(name, DecisionSource::CliRole, Vec::new()));
     }
 
     if let Some(name) = cli.llm.as_deref() {
         ensure_runner(config, name)?;
-        return Ok(RoleResolution {
-            runner: name.to_string(),
-            profile_override: None,
-        });
+        return Ok(RoleResolution::new(name, DecisionSource::CliLlm, Vec::new()));
     }
 
-    if let Some(overrides) = task.llm.as_ref() {
-        if let Some(name) = overrides.runner_for(role) {
-            ensure_runner(config, name)?;
-            return Ok(RoleResolution {
-                runner: name.to_string(),
-                profile_override: None,
-            });
-        }
+    if let Some(overrides) = task.llm.as_ref()
+        && let Some(name) = overrides.runner_for(role)
+    {
+        ensure_runner(config, name)?;
+        return Ok(RoleResolution::new(name, DecisionSource::Task, Vec::new()));
     }
 
-    if let Some(rule) = match_routing_rule(config, role, lang, profile, task)? {
+    let routing = match_routing_rule(config, role, lang, profile, task)?;
+    if let Some((index, rule)) = routing.rule {
         ensure_runner(config, &rule.use_runner)?;
         return Ok(RoleResolution {
-            runner: rule.use_runner.clone(),
             profile_override: rule.profile.clone(),
+            ..RoleResolution::new(
+                &rule.use_runner,
+                DecisionSource::Routing { index },
+                routing.checks,
+            )
         });
     }
 
@@ -260,19 +348,23 @@ fn resolve_role(
             })?;
         if let Some(runner) = profile_cfg.roles.runner_for(role) {
             ensure_runner(config, runner)?;
-            return Ok(RoleResolution {
-                runner: runner.to_string(),
-                profile_override: None,
-            });
+            return Ok(RoleResolution::new(
+                runner,
+                DecisionSource::Profile {
+                    name: profile_name.to_string(),
+                },
+                routing.checks,
+            ));
         }
     }
 
     if let Some(runner) = config.roles.runner_for(role) {
         ensure_runner(config, runner)?;
-        return Ok(RoleResolution {
-            runner: runner.to_string(),
-            profile_override: None,
-        });
+        return Ok(RoleResolution::new(
+            runner,
+            DecisionSource::Roles,
+            routing.checks,
+        ));
     }
 
     Err(ResolveError::MissingRunner {
@@ -320,61 +412,199 @@ fn ensure_profile(config: &Config, name: &str) -> Result<(), ResolveError> {
     }
 }
 
+/// The highest-ranked routing rule for `role` whose conditions all hold,
+/// with the checks of every rule for the role.
+struct RoutingMatch<'a> {
+    rule: Option<(usize, &'a RoutingRule)>,
+    checks: Vec<RuleCheck>,
+}
+
 fn match_routing_rule<'a>(
     config: &'a Config,
     role: RoleKind,
     lang: Option<&str>,
     profile: Option<&str>,
     task: &TaskMeta,
-) -> Result<Option<&'a RoutingRule>, ResolveError> {
-    for rule in &config.routing {
+) -> Result<RoutingMatch<'a>, ResolveError> {
+    let mut checks = Vec::new();
+    let mut selected: Option<(usize, &RoutingRule)> = None;
+    for (index, rule) in config.routing.iter().enumerate() {
         if rule.role != role {
             continue;
         }
 
-        if let Some(expected_lang) = rule.when.lang.as_deref() {
-            match lang {
-                Some(current_lang) if current_lang == expected_lang.to_ascii_lowercase() => {}
-                Some(_) | None => continue,
-            }
+        let conditions = check_conditions(&rule.when, lang, profile, task)?;
+        let matched = conditions.iter().all(|check| check.passed);
+        let (priority, specificity) = rule.rank();
+        checks.push(RuleCheck {
+            index,
+            conditions,
+            matched,
+            priority,
+            specificity,
+        });
+        // Rules are visited in file order, so a tie keeps the earlier rule.
+        if matched && selected.is_none_or(|(_, best)| rule.rank() > best.rank()) {
+            selected = Some((index, rule));
         }
+    }
 
-        if let Some(expected_profile) = rule.when.profile.as_deref() {
-            if profile != Some(expected_profile) {
-                continue;
-            }
-        }
+    Ok(RoutingMatch {
+        rule: selected,
+        checks,
+    })
+}
 
-        if let Some(task_id_glob) = rule.when.task_id.as_deref() {
-            if let Some(task_id) = task.id.as_deref() {
-                if !glob_matches(task_id_glob, task_id)? {
-                    continue;
-                }
-            } else {
-                continue;
+/// Tests every condition of a block; a block without conditions holds.
+fn check_conditions(
+    when: &RoutingConditions,
+    lang: Option<&str>,
+    profile: Option<&str>,
+    task: &TaskMeta,
+) -> Result<Vec<ConditionCheck>, ResolveError> {
+    let mut checks = Vec::new();
+
+    if let Some(expected) = &when.lang {
+        checks.push(ConditionCheck::value(
+            describe_values("lang", "=", "in", expected),
+            lang.map(str::to_string),
+            lang.is_some_and(|current| {
+                expected
+                    .values()
+                    .iter()
+                    .any(|value| current == value.to_ascii_lowercase())
+            }),
+        ));
+    }
+
+    if let Some(expected) = &when.profile {
+        checks.push(ConditionCheck::value(
+            describe_values("profile", "=", "in", expected),
+            profile.map(str::to_string),
+            profile.is_some_and(|current| expected.values().iter().any(|value| value == current)),
+        ));
+    }
+
+    if let Some(globs) = &when.task_id {
+        let mut passed = false;
+        if let Some(task_id) = task.id.as_deref() {
+            for glob in globs.values() {
+                passed |= glob_matches(glob, task_id)?;
             }
         }
+        checks.push(ConditionCheck::value(
+            describe_values("task_id", "matches", "matches any of", globs),
+            task.id.clone(),
+            passed,
+        ));
+    }
 
-        if let Some(path_glob) = rule.when.path.as_deref() {
-            if task.paths.is_empty() {
-                continue;
-            }
-            let mut matched = false;
-            for path in &task.paths {
-                if glob_matches(path_glob, path)? {
-                    matched = true;
-                    break;
+    if let Some(globs) = &when.path {
+        let mut matched = None;
+        'paths: for path in &task.paths {
+            for glob in globs.values() {
+                if glob_matches(glob, path)? {
+                    matched = Some(path.clone());
+                    break 'paths;
                 }
             }
-            if !matched {
-                continue;
-            }
         }
+        checks.push(ConditionCheck::value(
+            describe_values("path", "matches", "matches any of", globs),
+            matched
+                .clone()
+                .or_else(|| (!task.paths.is_empty()).then(|| task.paths.join(", "))),
+            matched.is_some(),
+        ));
+    }
 
-        return Ok(Some(rule));
+    if let Some(expected) = &when.tags {
+        checks.push(ConditionCheck::value(
+            describe_values("tags", "include", "include any of", expected),
+            (!task.tags.is_empty()).then(|| task.tags.join(", ")),
+            task.tags.iter().any(|tag| {
+                expected
+                    .values()
+                    .iter()
+                    .any(|value| value.eq_ignore_ascii_case(tag))
+            }),
+        ));
     }
 
-    Ok(None)
+    if let Some(expected) = &when.priority {
+        checks.push(ConditionCheck::value(
+            describe_values("priority", "=", "in", expected),
+            task.priority.clone(),
+            task.priority.as_ref().is_some_and(|priority| {
+                expected
+                    .values()
+                    .iter()
+                    .any(|value| value.eq_ignore_ascii_case(priority))
+            }),
+        ));
+    }
+
+    if let Some(expected) = &when.status {
+        let status = task.status.map(|status| status.as_str());
+        checks.push(ConditionCheck::value(
+            describe_values("status", "=", "in", expected),
+            status.map(str::to_string),
+            status.is_some_and(|status| {
+                expected
+                    .values()
+                    .iter()
+                    .any(|value| value.eq_ignore_ascii_case(status))
+            }),
+        ));
+    }
+
+    if let Some(blocks) = &when.any {
+        let nested = check_blocks(blocks, lang, profile, task)?;
+        let passed = nested.iter().any(|check| check.passed);
+        checks.push(ConditionCheck::block("any of", nested, passed));
+    }
+
+    if let Some(blocks) = &when.all {
+        let nested = check_blocks(blocks, lang, profile, task)?;
+        let passed = nested.iter().all(|check| check.passed);
+        checks.push(ConditionCheck::block("all of", nested, passed));
+    }
+
+    if let Some(block) = &when.not {
+        let nested = check_conditions(block, lang, profile, task)?;
+        let passed = !nested.iter().all(|check| check.passed);
+        checks.push(ConditionCheck::block("not", nested, passed));
+    }
+
+    Ok(checks)
+}
+
+/// One check per block of an `any` or `all` list; blocks with several
+/// conditions are grouped under an `all of` check.
+fn check_blocks(
+    blocks: &[RoutingConditions],
+    lang: Option<&str>,
+    profile: Option<&str>,
+    task: &TaskMeta,
+) -> Result<Vec<ConditionCheck>, ResolveError> {
+    let mut checks = Vec::new();
+    for block in blocks {
+        let mut nested = check_conditions(block, lang, profile, task)?;
+        if nested.len() == 1 {
+            checks.extend(nested.pop());
+        } else {
+            let passed = nested.iter().all(|check| check.passed);
+            checks.push(ConditionCheck::block("all of", nested, passed));
+        }
+    }
+    Ok(checks)
+}
+
+fn describe_values(key: &str, one: &str, many: &str, values: &ConditionValues) -> String {
+    match values {
+        ConditionValues::One(value) => format!("{key} {one} {value}"),
+        ConditionValues::Any(values) => format!("{key} {many} [{}]", values.join(", ")),
+    }
 }
 
 fn glob_matches(pattern: &str, value: &str) -> Result<bool, ResolveError> {
@@ -439,19 +669,22 @@ runner = "claude"
 "#;
 
     fn base_task() -> TaskMeta {
-        let mut task = TaskMeta::default();
-        task.id = Some("A-1".into());
-        task.lang = Some("rust".into());
-        task.paths.push("src/auth/lib.rs".into());
-        task
+        TaskMeta {
+            id: Some("A-1".into()),
+            lang: Some("rust".into()),
+            paths: vec!["src/auth/lib.rs".into()],
+            ..TaskMeta::default()
+        }
     }
 
     #[test]
     fn cli_overrides_take_priority() {
         let config = Config::from_str(CONFIG).expect("valid config");
-        let mut cli = CliRoleOverrides::default();
-        cli.plan_llm = Some("gpt4".into());
-        cli.llm = Some("codex".into());
+        let cli = CliRoleOverrides {
+            plan_llm: Some("gpt4".into()),
+            llm: Some("codex".into()),
+            ..CliRoleOverrides::default()
+        };
         let task = base_task();
 
         let resolved = resolve_runners(&config, &task, &cli).expect("resolved");
@@ -474,6 +707,212 @@ runner = "claude"
         assert_eq!(resolved.review, "claude");
     }
 
+    #[test]
+    fn trace_records_the_deciding_layer_and_rule_checks() {
+        let config = Config::from_str(CONFIG).expect("valid config");
+        let cli = CliRoleOverrides {
+            plan_llm: Some("gpt4".into()),
+            ..CliRoleOverrides::default()
+        };
+
+        let resolved = resolve_runners(&config, &base_task(), &cli).expect("resolved");
+        let sources: Vec<_> = resolved
+            .trace
+            .iter()
+            .map(|decision| (decision.role, decision.source.clone()))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                (RoleKind::Plan, DecisionSource::CliRole),
+                (RoleKind::Code, DecisionSource::Routing { index: 0 }),
+                (
+                    RoleKind::Review,
+                    DecisionSource::Profile {
+                        name: "big_repo".into()
+                    }
+                ),
+            ]
+        );
+
+        let code = &resolved.trace[1];
+        assert_eq!(code.profile.as_deref(), Some("default"));
+        assert_eq!(code.profile_switch.as_deref(), Some("big_repo"));
+        assert_eq!(
+            code.rules,
+            [RuleCheck {
+                index: 0,
+                conditions: vec![ConditionCheck::value(
+                    "lang = rust".into(),
+                    Some("rust".into()),
+                    true,
+                )],
+                matched: true,
+                priority: 0,
+                specificity: Specificity {
+                    lang: true,
+                    ..Specificity::default()
+                },
+            }]
+        );
+        assert!(resolved.trace[0].rules.is_empty());
+    }
+
+    #[test]
+    fn trace_keeps_failed_rule_checks_when_falling_back() {
+        let config = Config::from_str(CONFIG).expect("valid config");
+        let task = TaskMeta {
+            lang: Some("go".into()),
+            ..base_task()
+        };
+
+        let resolved =
+            resolve_runners(&config, &task, &CliRoleOverrides::default()).expect("resolved");
+        let code = &resolved.trace[1];
+        assert_eq!(code.runner, "codex");
+        assert_eq!(
+            code.source,
+            DecisionSource::Profile {
+                name: "default".into()
+            }
+        );
+        assert_eq!(code.rules.len(), 1);
+        assert!(!code.rules[0].matched);
+        assert_eq!(code.rules[0].conditions[0].actual.as_deref(), Some("go"));
+    }
+
+    #[test]
+    fn conditions_support_lists_and_combinators() {
+        let config = Config::from_str(&format!(
+            "{CONFIG}{}",
+            r#"
+[[routing]]
+role = "plan"
+use = "gpt4"
+when.tags = ["security", "auth"]
+when.not = { status = "done" }
+when.any = [{ priority = ["high", "critical"] }, { lang = "go", path = "src/**" }]
+"#
+        ))
+        .expect("valid config");
+        let mut task = TaskMeta {
+            tags: vec!["auth".into()],
+            priority: Some("high".into()),
+            status: Some(TaskStatus::Todo),
+            ..base_task()
+        };
+        let resolve = |task: &TaskMeta| {
+            resolve_runners(&config, task, &CliRoleOverrides::default())
+                .expect("resolved")
+                .plan
+        };
+
+        assert_eq!(resolve(&task), "gpt4");
+
+        task.status = Some(TaskStatus::Done);
+        assert_eq!(resolve(&task), "claude");
+
+        task.status = Some(TaskStatus::Doing);
+        task.priority = Some("low".into());
+        assert_eq!(resolve(&task), "claude");
+
+        task.lang = Some("Go".into());
+        assert_eq!(resolve(&task), "gpt4");
+
+        task.tags = vec!["ui".into()];
+        let resolved =
+            resolve_runners(&config, &task, &CliRoleOverrides::default()).expect("resolved");
+        assert_eq!(resolved.plan, "claude");
+        let checks = &resolved.trace[0].rules[0].conditions;
+        assert_eq!(checks[0].condition, "tags include any of [security, auth]");
+        assert!(!checks[0].passed);
+        assert_eq!(checks[1].condition, "any of");
+        assert_eq!(checks[1].nested[1].condition, "all of");
+        assert!(checks[1].nested[1].passed);
+    }
+
+    #[test]
+    fn value_conditions_ignore_case() {
+        let config = Config::from_str(&format!(
+            "{CONFIG}{}",
+            r#"
+[[routing]]
+role = "plan"
+use = "gpt4"
+when.tags = "Security"
+when.priority = "HIGH"
+when.status = "Todo"
+"#
+        ))
+        .expect("valid config");
+        let task = TaskMeta {
+            tags: vec!["security".into()],
+            priority: Some("High".into()),
+            status: Some(TaskStatus::Todo),
+            ..base_task()
+        };
+
+        let resolved =
+            resolve_runners(&config, &task, &CliRoleOverrides::default()).expect("resolved");
+        assert_eq!(resolved.plan, "gpt4");
+    }
+
+    #[test]
+    fn matching_rules_rank_by_priority_then_specificity_then_order() {
+        let rules = |extra: &str| {
+            Config::from_str(&format!("{CONFIG}{extra}")).expect("valid config")
+        };
+        let code = |config: &Config| {
+            let resolved = resolve_runners(config, &base_task(), &CliRoleOverrides::default())
+                .expect("resolved");
+            (resolved.code, resolved.trace[1].source.clone())
+        };
+
+        let config = rules(
+            r#"
+[[routing]]
+role = "code"
+use = "claude"
+when.task_id = "A-*"
+"#,
+        );
+        assert_eq!(
+            code(&config),
+            ("claude".into(), DecisionSource::Routing { index: 1 })
+        );
+
+        let config = rules(
+            r#"
+[[routing]]
+role = "code"
+use = "claude"
+when.lang = "rust"
+"#,
+        );
+        assert_eq!(
+            code(&config),
+            ("gpt4".into(), DecisionSource::Routing { index: 0 })
+        );
+
+        let config = rules(
+            r#"
+[[routing]]
+role = "code"
+use = "codex"
+priority = 1
+
+[[routing]]
+role = "code"
+use = "claude"
+priority = 1
+"#,
+        );
+        assert_eq!(
+            code(&config),
+            ("codex".into(), DecisionSource::Routing { index: 1 })
+        );
+    }
+
     #[test]
     fn pipeline_resolution_prefers_cli_then_task_then_default() {
         let mut config = Config::from_str(CONFIG).expect("valid config");
@@ -484,6 +923,7 @@ runner = "claude"
                 consensus: None,
                 fail_on: Vec::new(),
                 weights: std::collections::HashMap::new(),
+                threshold: None,
             },
         );
 
@@ -495,8 +935,10 @@ runner = "claude"
             pipeline: Some("security".into()),
         });
 
-        let mut cli = CliRoleOverrides::default();
-        cli.pipeline = Some("strict".into());
+        let mut cli = CliRoleOverrides {
+            pipeline: Some("strict".into()),
+            ..CliRoleOverrides::default()
+        };
 
         let resolved = resolve_review_pipeline(&config, &task, &cli).expect("pipeline");
         assert_eq!(resolved.name, "strict");
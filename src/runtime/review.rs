@@ -0,0 +1,552 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::{
+    consensus::{self, ConsensusDecision, Vote},
+    executor::{self, ExecError, ExecOutcome, ExecRequest, ExitState},
+    prompt::{PromptContext, PromptError, PromptTemplate},
+    resolver::{ResolvedPipeline, ResolvedStage},
+    verdict::{self, SchemaError, VerdictSchema},
+};
+use crate::{
+    config::{Config, ReviewStageKind, RoleKind, RunnerDef, review_stage_dir_name},
+    metadata::current_timestamp,
+    tasks::Task,
+};
+
+/// File written into every review stage directory.
+pub const VERDICT_FILE_NAME: &str = "verdict.json";
+
+/// Summary of all verdicts written into the review directory.
+pub const VERDICTS_FILE_NAME: &str = "verdicts.json";
+
+#[derive(Debug, Error)]
+pub enum ReviewError {
+    #[error("review stage '{stage}' references unknown runner '{runner}'")]
+    UnknownRunner { stage: String, runner: String },
+    #[error("review stage '{stage}' of type exec has no cmd")]
+    MissingCommand { stage: String },
+    #[error("review stage '{stage}' of type '{kind}' has no runner")]
+    MissingRunner { stage: String, kind: &'static str },
+    #[error("failed to write {path}: {source}")]
+    WriteFailed {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to serialize verdict: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error(transparent)]
+    Exec(#[from] ExecError),
+    #[error(transparent)]
+    Prompt(#[from] PromptError),
+    #[error(transparent)]
+    Schema(#[from] SchemaError),
+    #[error("review stage '{stage}' was cancelled")]
+    Cancelled { stage: String },
+}
+
+/// Inputs shared by every stage of a review pipeline.
+#[derive(Debug, Clone, Copy)]
+pub struct ReviewContext<'a> {
+    pub config: &'a Config,
+    pub task: &'a Task,
+    pub profile: Option<&'a str>,
+    pub project_root: &'a Path,
+    /// The run's `review` stage directory; each pipeline stage gets a subdirectory.
+    pub review_dir: &'a Path,
+    /// Outputs of the plan/code stages keyed by stage name.
+    pub stages: &'a BTreeMap<String, String>,
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+/// Result of a single review stage.
+#[derive(Debug, Clone, Serialize)]
+pub struct StageVerdict {
+    pub stage: String,
+    pub kind: ReviewStageKind,
+    pub passed: bool,
+    pub summary: String,
+    /// Structured verdict parsed from the reviewer output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    /// Problems that did not fail the stage, e.g. a heuristic fallback.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    pub runner: Option<String>,
+    pub started_at: String,
+    pub finished_at: String,
+    pub exec: ExecOutcome,
+}
+
+/// Verdicts of every stage in a pipeline, in execution order, and the consensus over them.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewOutcome {
+    pub pipeline: String,
+    pub verdicts: Vec<StageVerdict>,
+    pub decision: ConsensusDecision,
+}
+
+impl ReviewOutcome {
+    /// True when the pipeline consensus accepted the change.
+    pub fn passed(&self) -> bool {
+        self.decision.passed
+    }
+}
+
+/// Executes every stage of the pipeline in order and persists their verdicts.
+pub fn run_pipeline(
+    pipeline: &ResolvedPipeline<'_>,
+    ctx: &ReviewContext<'_>,
+) -> Result<ReviewOutcome, ReviewError> {
+    let mut verdicts = Vec::with_capacity(pipeline.stages.len());
+    for stage in &pipeline.stages {
+        let verdict = run_stage(stage, ctx, &verdicts)?;
+        write_json(
+            &stage_dir(ctx.review_dir, &stage.name).join(VERDICT_FILE_NAME),
+            &verdict,
+        )?;
+        if verdict.exec.state == ExitState::Cancelled {
+            return Err(ReviewError::Cancelled {
+                stage: stage.name.clone(),
+            });
+        }
+        verdicts.push(verdict);
+    }
+
+    let votes: Vec<Vote<'_>> = verdicts
+        .iter()
+        .map(|verdict| Vote {
+            stage: &verdict.stage,
+            passed: verdict.passed,
+        })
+        .collect();
+    let decision = consensus::evaluate(pipeline.pipeline, &votes);
+    let outcome = ReviewOutcome {
+        pipeline: pipeline.name.clone(),
+        verdicts,
+        decision,
+    };
+    write_json(&ctx.review_dir.join(VERDICTS_FILE_NAME), &outcome)?;
+    Ok(outcome)
+}
+
+fn run_stage(
+    stage: &ResolvedStage<'_>,
+    ctx: &ReviewContext<'_>,
+    previous: &[StageVerdict],
+) -> Result<StageVerdict, ReviewError> {
+    let started_at = current_timestamp();
+    let dir = stage_dir(ctx.review_dir, &stage.name);
+
+    let (runner_name, exec, judgement) = match stage.stage.kind {
+        ReviewStageKind::Exec => {
+            let runner = exec_runner(stage)?;
+            let exec = executor::execute(&ExecRequest {
+                runner_name: &stage.name,
+                runner: &runner,
+                prompt: "",
+                working_dir: ctx.project_root,
+                output_dir: &dir,
+                cancel: ctx.cancel,
+            })?;
+            let judgement = if exec.success() {
+                Judgement::passed(format!("`{}` succeeded", runner.cmd))
+            } else {
+                Judgement::failed(format!(
+                    "`{}` failed: {}",
+                    runner.cmd,
+                    exec.failure_reason(None)
+                ))
+            };
+            (None, exec, judgement)
+        }
+        ReviewStageKind::Llm | ReviewStageKind::Arbiter => {
+            let (runner_name, runner) = stage_runner(stage, ctx.config)?;
+            let template = stage_template(stage, ctx.project_root, runner)?;
+            let prompt = template.render(&PromptContext {
+                task: ctx.task,
+                extra: &ctx.task.context.extra,
+                role: RoleKind::Review.as_str(),
+                profile: ctx.profile,
+                runner: runner_name,
+                model: runner.model.as_deref(),
+                stages: ctx.stages.clone(),
+                verdicts: previous,
+                docs: &[],
+            })?;
+            let exec = executor::execute(&ExecRequest {
+                runner_name,
+                runner,
+                prompt: &prompt,
+                working_dir: ctx.project_root,
+                output_dir: &dir,
+                cancel: ctx.cancel,
+            })?;
+            let judgement = if exec.success() {
+                let schema = stage
+                    .stage
+                    .schema
+                    .as_deref()
+                    .map(|path| VerdictSchema::load(&ctx.project_root.join(path)))
+                    .transpose()?;
+                judge_output(stage, &exec.read_stdout()?, schema.as_ref())
+            } else {
+                Judgement::failed(exec.failure_reason(runner.timeout_ms))
+            };
+            (Some(runner_name.to_string()), exec, judgement)
+        }
+    };
+
+    Ok(StageVerdict {
+        stage: stage.name.clone(),
+        kind: stage.stage.kind,
+        passed: judgement.passed,
+        summary: judgement.summary,
+        details: judgement.details,
+        warnings: judgement.warnings,
+        runner: runner_name,
+        started_at,
+        finished_at: current_timestamp(),
+        exec,
+    })
+}
+
+/// Pass/fail decision of a stage before it is turned into a [`StageVerdict`].
+struct Judgement {
+    passed: bool,
+    summary: String,
+    details: Option<Value>,
+    warnings: Vec<String>,
+}
+
+impl Judgement {
+    fn passed(summary: String) -> Self {
+        Self {
+            passed: true,
+            summary,
+            details: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn failed(summary: String) -> Self {
+        Self {
+            passed: false,
+            ..Self::passed(summary)
+        }
+    }
+}
+
+/// Decides an llm/arbiter stage from its output: a structured verdict when one is
+/// available, otherwise the heuristic (which `strict` stages refuse).
+fn judge_output(
+    stage: &ResolvedStage<'_>,
+    output: &str,
+    schema: Option<&VerdictSchema>,
+) -> Judgement {
+    let fallback = match verdict::assess(output, schema) {
+        Ok(Some(structured)) => {
+            let summary = structured.summary.unwrap_or_else(|| {
+                if structured.passed {
+                    "reviewer approved the change".to_string()
+                } else {
+                    "reviewer rejected the change".to_string()
+                }
+            });
+            return Judgement {
+                passed: structured.passed,
+                summary,
+                details: Some(structured.value),
+                warnings: Vec::new(),
+            };
+        }
+        Ok(None) if stage.stage.strict => {
+            return Judgement::failed("no structured verdict found in reviewer output".into());
+        }
+        Ok(None) => None,
+        Err(reason) if stage.stage.strict => return Judgement::failed(reason),
+        Err(reason) => Some(format!("{reason}; falling back to heuristic verdict")),
+    };
+
+    let mut judgement = match heuristic_verdict(output) {
+        Some(true) => Judgement::passed("reviewer approved the change".to_string()),
+        Some(false) => Judgement::failed("reviewer rejected the change".to_string()),
+        None => Judgement::failed("no verdict found in reviewer output".to_string()),
+    };
+    judgement.warnings.extend(fallback);
+    judgement
+}
+
+fn exec_runner(stage: &ResolvedStage<'_>) -> Result<RunnerDef, ReviewError> {
+    let missing = || ReviewError::MissingCommand {
+        stage: stage.name.clone(),
+    };
+    let (cmd, args) = stage
+        .stage
+        .cmd
+        .as_deref()
+        .and_then(|cmd| cmd.split_first())
+        .ok_or_else(missing)?;
+    Ok(RunnerDef {
+        cmd: cmd.clone(),
+        args: args.to_vec(),
+        ..RunnerDef::default()
+    })
+}
+
+fn stage_runner<'a>(
+    stage: &ResolvedStage<'_>,
+    config: &'a Config,
+) -> Result<(&'a str, &'a RunnerDef), ReviewError> {
+    let name = stage
+        .stage
+        .runner
+        .as_deref()
+        .ok_or_else(|| ReviewError::MissingRunner {
+            stage: stage.name.clone(),
+            kind: stage.stage.kind.as_str(),
+        })?;
+    config
+        .runners
+        .get_key_value(name)
+        .map(|(name, runner)| (name.as_str(), runner))
+        .ok_or_else(|| ReviewError::UnknownRunner {
+            stage: stage.name.clone(),
+            runner: name.to_string(),
+        })
+}
+
+fn stage_template(
+    stage: &ResolvedStage<'_>,
+    project_root: &Path,
+    runner: &RunnerDef,
+) -> Result<PromptTemplate, PromptError> {
+    match (stage.stage.prompt.as_deref(), stage.stage.kind) {
+        (Some(path), _) => PromptTemplate::from_file(&project_root.join(path)),
+        (None, ReviewStageKind::Arbiter) => PromptTemplate::for_arbiter(project_root, runner),
+        (None, _) => PromptTemplate::for_role(project_root, runner, RoleKind::Review),
+    }
+}
+
+/// Directory for a pipeline stage; stage names are reduced to a safe path component.
+fn stage_dir(review_dir: &Path, stage: &str) -> PathBuf {
+    review_dir.join(review_stage_dir_name(stage))
+}
+
+/// Looks for an explicit pass/fail marker in free-form reviewer output, starting
+/// from the end where models usually put their conclusion.
+pub fn heuristic_verdict(output: &str) -> Option<bool> {
+    const PASS: &[&str] = &[
+        "pass", "passed", "approve", "approved", "lgtm", "accept", "accepted",
+    ];
+    const FAIL: &[&str] = &[
+        "fail",
+        "failed",
+        "reject",
+        "rejected",
+        "changes requested",
+        "request changes",
+    ];
+
+    for line in output.lines().rev() {
+        let normalized = line
+            .trim()
+            .trim_matches(|ch: char| matches!(ch, '*' | '#' | '>' | '-' | '`' | '_' | '.' | '!'))
+            .trim()
+            .to_ascii_lowercase();
+        let verdict = normalized
+            .strip_prefix("verdict:")
+            .or_else(|| normalized.strip_prefix("verdict -"))
+            .map(str::trim)
+            .unwrap_or(&normalized)
+            .trim_matches(|ch: char| matches!(ch, '*' | '`' | '_' | '.' | '!'))
+            .trim();
+
+        if PASS.contains(&verdict) {
+            return Some(true);
+        }
+        if FAIL.contains(&verdict) {
+            return Some(false);
+        }
+    }
+    None
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), ReviewError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|source| ReviewError::WriteFailed {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    let data = serde_json::to_vec_pretty(value)?;
+    fs::write(path, data).map_err(|source| ReviewError::WriteFailed {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ReviewStage,
+        runtime::resolver::{CliRoleOverrides, TaskMeta, resolve_review_pipeline},
+        tasks::TaskSet,
+    };
+    use tempfile::tempdir;
+
+    #[test]
+    fn strict_stages_reject_unstructured_output() {
+        let temp = tempdir().unwrap();
+        fs::write(
+            temp.path().join("schema.json"),
+            r#"{"type": "object", "required": ["passed"]}"#,
+        )
+        .unwrap();
+        let schema = VerdictSchema::load(&temp.path().join("schema.json")).unwrap();
+        let mut config = ReviewStage {
+            kind: ReviewStageKind::Llm,
+            strict: true,
+            ..ReviewStage::default()
+        };
+        let output = "{\"verdict\": \"pass\"}\nVerdict: PASS";
+
+        let stage = ResolvedStage {
+            name: "llm".into(),
+            stage: &config,
+        };
+        let judgement = judge_output(&stage, output, Some(&schema));
+        assert!(!judgement.passed);
+        assert!(
+            judgement
+                .summary
+                .contains("\"passed\" is a required property")
+        );
+
+        config.strict = false;
+        let stage = ResolvedStage {
+            name: "llm".into(),
+            stage: &config,
+        };
+        let judgement = judge_output(&stage, output, Some(&schema));
+        assert!(judgement.passed);
+        assert_eq!(judgement.warnings.len(), 1);
+        assert!(judgement.warnings[0].ends_with("falling back to heuristic verdict"));
+
+        let judgement = judge_output(
+            &stage,
+            "{\"passed\": false, \"summary\": \"no tests\"}",
+            Some(&schema),
+        );
+        assert!(!judgement.passed);
+        assert_eq!(judgement.summary, "no tests");
+        assert!(judgement.details.is_some());
+    }
+
+    #[test]
+    fn heuristic_reads_last_marker() {
+        assert_eq!(
+            heuristic_verdict("looks fine\n\n**Verdict: PASS**\n"),
+            Some(true)
+        );
+        assert_eq!(heuristic_verdict("## Verdict: fail."), Some(false));
+        assert_eq!(heuristic_verdict("Verdict - approved"), Some(true));
+        assert_eq!(heuristic_verdict("rejected\nLGTM"), Some(true));
+        assert_eq!(heuristic_verdict("no conclusion here"), None);
+    }
+
+    #[test]
+    fn stage_dirs_are_single_components() {
+        let root = Path::new("/review");
+        assert_eq!(stage_dir(root, "llm.criteria"), root.join("llm.criteria"));
+        assert_eq!(stage_dir(root, "../evil"), root.join(".._evil"));
+        assert_eq!(stage_dir(root, ".."), root.join("_"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_exec_llm_and_arbiter_stages() {
+        let temp = tempdir().unwrap();
+        let config = Config::from_str(
+            r#"
+[runners.reviewer]
+cmd = "sh"
+args = ["-c", "cat >/dev/null; echo 'Verdict: PASS'"]
+
+[runners.judge]
+cmd = "sh"
+args = ["-c", "grep -c ': fail' >&2; echo REJECTED"]
+
+[roles]
+review = "reviewer"
+
+[review]
+default_pipeline = "full"
+
+[review.pipelines.full]
+stages = ["build", "llm", "arbiter"]
+
+[review.stages.build]
+type = "exec"
+cmd = ["sh", "-c", "exit 2"]
+
+[review.stages.llm]
+type = "llm"
+runner = "reviewer"
+
+[review.stages.arbiter]
+type = "arbiter"
+runner = "judge"
+"#,
+        )
+        .expect("config");
+        let task = TaskSet::from_str("version = 1\n[[tasks]]\nid = \"A-1\"\ntitle = \"T\"\n")
+            .unwrap()
+            .tasks
+            .remove(0);
+        let pipeline = resolve_review_pipeline(
+            &config,
+            &TaskMeta::from(&task),
+            &CliRoleOverrides::default(),
+        )
+        .expect("pipeline");
+        let review_dir = temp.path().join("review");
+        let stages = BTreeMap::new();
+
+        let outcome = run_pipeline(
+            &pipeline,
+            &ReviewContext {
+                config: &config,
+                task: &task,
+                profile: None,
+                project_root: temp.path(),
+                review_dir: &review_dir,
+                stages: &stages,
+                cancel: None,
+            },
+        )
+        .expect("pipeline runs");
+
+        let passed: Vec<_> = outcome.verdicts.iter().map(|v| v.passed).collect();
+        assert_eq!(passed, vec![false, true, false]);
+        assert!(!outcome.passed());
+        assert_eq!(outcome.decision.reason, "failing stages: build, arbiter");
+        assert!(review_dir.join("build").join(VERDICT_FILE_NAME).exists());
+        assert!(review_dir.join(VERDICTS_FILE_NAME).exists());
+
+        let arbiter_prompt = fs::read_to_string(review_dir.join("arbiter/prompt.md")).unwrap();
+        assert!(arbiter_prompt.contains("- build (exec): fail"));
+        assert!(arbiter_prompt.contains("- llm (llm): pass"));
+        let arbiter_stderr = fs::read_to_string(review_dir.join("arbiter/stderr.log")).unwrap();
+        assert_eq!(arbiter_stderr.trim(), "1");
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use jsonschema::Validator;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::review::heuristic_verdict;
+
+/// Maximum number of schema violations quoted in a nonconformance message.
+const MAX_REPORTED_VIOLATIONS: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum SchemaError {
+    #[error("failed to read verdict schema {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("verdict schema {path} is not valid JSON: {source}")]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("verdict schema {path} is not a valid JSON Schema: {reason}")]
+    Invalid { path: PathBuf, reason: String },
+}
+
+/// JSON Schema that structured reviewer output must conform to.
+pub struct VerdictSchema {
+    validator: Validator,
+}
+
+impl VerdictSchema {
+    pub fn load(path: &Path) -> Result<Self, SchemaError> {
+        let data = fs::read_to_string(path).map_err(|source| SchemaError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let schema: Value = serde_json::from_str(&data).map_err(|source| SchemaError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let validator = jsonschema::validator_for(&schema).map_err(|err| SchemaError::Invalid {
+            path: path.to_path_buf(),
+            reason: err.to_string(),
+        })?;
+        Ok(Self { validator })
+    }
+
+    /// Returns a description of the first few violations, if any.
+    fn violations(&self, value: &Value) -> Option<String> {
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(value)
+            .map(|err| {
+                let path = err.instance_path.to_string();
+                if path.is_empty() {
+                    err.to_string()
+                } else {
+                    format!("{path}: {err}")
+                }
+            })
+            .collect();
+        if errors.is_empty() {
+            return None;
+        }
+        let mut message = errors
+            .iter()
+            .take(MAX_REPORTED_VIOLATIONS)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("; ");
+        if errors.len() > MAX_REPORTED_VIOLATIONS {
+            message.push_str(&format!(
+                " (and {} more)",
+                errors.len() - MAX_REPORTED_VIOLATIONS
+            ));
+        }
+        Some(message)
+    }
+}
+
+/// A pass/fail decision read from JSON reviewer output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredVerdict {
+    pub passed: bool,
+    pub summary: Option<String>,
+    /// The parsed JSON document.
+    pub value: Value,
+}
+
+/// Reads a structured verdict from reviewer output.
+///
+/// Returns `Ok(None)` when the output contains no JSON and no schema is required,
+/// and `Err` with the reason when the output does not conform.
+pub fn assess(
+    output: &str,
+    schema: Option<&VerdictSchema>,
+) -> Result<Option<StructuredVerdict>, String> {
+    let Some(value) = extract_json(output) else {
+        return match schema {
+            Some(_) => Err("no JSON verdict found in reviewer output".to_string()),
+            None => Ok(None),
+        };
+    };
+    if let Some(violations) = schema.and_then(|schema| schema.violations(&value)) {
+        return Err(format!("verdict does not match schema: {violations}"));
+    }
+    interpret(value)
+        .map(Some)
+        .ok_or_else(|| "verdict JSON has no `passed` or `verdict` field".to_string())
+}
+
+/// Finds the JSON object in reviewer output: the whole output, the last fenced
+/// code block that parses, or the outermost braces.
+pub fn extract_json(output: &str) -> Option<Value> {
+    let parse = |text: &str| {
+        serde_json::from_str::<Value>(text.trim())
+            .ok()
+            .filter(Value::is_object)
+    };
+
+    if let Some(value) = parse(output) {
+        return Some(value);
+    }
+    if let Some(value) = fenced_blocks(output).into_iter().rev().find_map(parse) {
+        return Some(value);
+    }
+    let start = output.find('{')?;
+    let end = output.rfind('}')?;
+    (start < end).then(|| parse(&output[start..=end])).flatten()
+}
+
+fn fenced_blocks(output: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut rest = output;
+    while let Some(open) = rest.find("```") {
+        let after_fence = &rest[open + 3..];
+        // Skip the info string (e.g. `json`) on the opening fence line.
+        let Some(body_start) = after_fence.find('\n') else {
+            break;
+        };
+        let body = &after_fence[body_start + 1..];
+        let Some(close) = body.find("```") else {
+            break;
+        };
+        blocks.push(&body[..close]);
+        rest = &body[close + 3..];
+    }
+    blocks
+}
+
+fn interpret(value: Value) -> Option<StructuredVerdict> {
+    let passed = ["passed", "pass", "approved"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_bool))
+        .or_else(|| {
+            ["verdict", "status", "decision"]
+                .iter()
+                .find_map(|key| value.get(key).and_then(Value::as_str))
+                .and_then(heuristic_verdict)
+        })?;
+    let summary = ["summary", "reason"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_str))
+        .map(str::to_string);
+    Some(StructuredVerdict {
+        passed,
+        summary,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn schema() -> (tempfile::TempDir, VerdictSchema) {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("verdict.schema.json");
+        fs::write(
+            &path,
+            r#"{
+                "type": "object",
+                "required": ["verdict", "summary"],
+                "properties": {
+                    "verdict": { "enum": ["pass", "fail"] },
+                    "summary": { "type": "string" }
+                }
+            }"#,
+        )
+        .unwrap();
+        let schema = VerdictSchema::load(&path).expect("schema loads");
+        (temp, schema)
+    }
+
+    #[test]
+    fn extracts_json_from_fenced_blocks() {
+        let output = "Looks good.\n\n```json\n{\"verdict\": \"pass\", \"summary\": \"ok\"}\n```\n";
+        assert_eq!(
+            extract_json(output),
+            Some(json!({"verdict": "pass", "summary": "ok"}))
+        );
+        assert_eq!(
+            extract_json("{\"passed\": false}"),
+            Some(json!({"passed": false}))
+        );
+        assert_eq!(
+            extract_json("verdict follows: {\"passed\": true} done"),
+            Some(json!({"passed": true}))
+        );
+        assert_eq!(extract_json("no json here"), None);
+    }
+
+    #[test]
+    fn conforming_output_yields_structured_verdict() {
+        let (_temp, schema) = schema();
+        let verdict = assess(
+            "```\n{\"verdict\": \"fail\", \"summary\": \"missing tests\"}\n```",
+            Some(&schema),
+        )
+        .expect("conforms")
+        .expect("structured");
+        assert!(!verdict.passed);
+        assert_eq!(verdict.summary.as_deref(), Some("missing tests"));
+    }
+
+    #[test]
+    fn nonconforming_output_reports_violations() {
+        let (_temp, schema) = schema();
+        let err = assess("{\"verdict\": \"maybe\"}", Some(&schema)).expect_err("violates");
+        assert!(err.starts_with("verdict does not match schema"));
+        assert!(err.contains("summary"));
+
+        let err = assess("Verdict: PASS", Some(&schema)).expect_err("no json");
+        assert_eq!(err, "no JSON verdict found in reviewer output");
+    }
+
+    #[test]
+    fn plain_output_without_schema_is_not_structured() {
+        assert_eq!(assess("Verdict: PASS", None), Ok(None));
+        assert!(assess("{\"note\": 1}", None).is_err());
+    }
+
+    #[test]
+    fn invalid_schema_is_reported() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("bad.json");
+        fs::write(&path, r#"{"type": 12}"#).unwrap();
+        assert!(matches!(
+            VerdictSchema::load(&path),
+            Err(SchemaError::Invalid { .. })
+        ));
+    }
+}
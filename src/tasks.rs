@@ -2,8 +2,9 @@
 
 use std::{
     collections::{BTreeMap, HashSet},
-    fs,
+    fmt, fs,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 use serde::{Deserialize, Serialize};
@@ -12,6 +13,12 @@ use toml::Value;
 
 use crate::config::RoleKind;
 
+mod edit;
+mod graph;
+
+pub use edit::{TaskDocument, TaskEditError};
+pub(crate) use graph::priority_rank;
+
 pub const TASK_FILE_VERSION: u32 = 1;
 
 #[derive(Debug, Error)]
@@ -24,6 +31,10 @@ pub enum TaskLoadError {
     UnsupportedVersion { expected: u32, found: u32 },
     #[error("duplicate task id '{id}'")]
     DuplicateTaskId { id: String },
+    #[error("task '{task}' depends on unknown task '{dependency}'")]
+    UnknownDependency { task: String, dependency: String },
+    #[error("dependency cycle: {}", path.join(" -> "))]
+    DependencyCycle { path: Vec<String> },
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -75,6 +86,7 @@ impl TaskSet {
                 });
             }
         }
+        graph::validate(&file.tasks)?;
 
         Ok(TaskSet {
             version: file.version,
@@ -88,7 +100,7 @@ impl TaskSet {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub title: String,
@@ -108,22 +120,54 @@ pub struct Task {
     pub llm: Option<TaskLlmOverrides>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
+    #[default]
     Todo,
     Doing,
     Done,
     Blocked,
 }
 
-impl Default for TaskStatus {
-    fn default() -> Self {
-        TaskStatus::Todo
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 4] = [
+        TaskStatus::Todo,
+        TaskStatus::Doing,
+        TaskStatus::Done,
+        TaskStatus::Blocked,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "todo",
+            TaskStatus::Doing => "doing",
+            TaskStatus::Done => "done",
+            TaskStatus::Blocked => "blocked",
+        }
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        TaskStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .ok_or_else(|| {
+                format!("unknown task status '{value}' (expected todo, doing, done or blocked)")
+            })
     }
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TaskContext {
     #[serde(default)]
     pub code: Vec<String>,
@@ -0,0 +1,271 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Item, Table, value};
+
+use super::{TASK_FILE_VERSION, Task, TaskLoadError, TaskSet, TaskStatus};
+
+#[derive(Debug, Error)]
+pub enum TaskEditError {
+    #[error("failed to read tasks at {path}: {source}")]
+    ReadFailed { path: PathBuf, source: io::Error },
+    #[error("failed to parse tasks at {path}: {source}")]
+    ParseFailed {
+        path: PathBuf,
+        source: Box<toml_edit::TomlError>,
+    },
+    #[error("`tasks` in {path} is not an array of [[tasks]] tables")]
+    NotTaskArray { path: PathBuf },
+    #[error("task '{id}' already exists")]
+    DuplicateTaskId { id: String },
+    #[error("task '{id}' not found")]
+    UnknownTask { id: String },
+    #[error("edited tasks file would be invalid: {0}")]
+    Invalid(#[from] TaskLoadError),
+    #[error("failed to write tasks at {path}: {source}")]
+    WriteFailed { path: PathBuf, source: io::Error },
+}
+
+/// `tasks.toml` opened for editing with its formatting and comments preserved.
+pub struct TaskDocument {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl TaskDocument {
+    /// Opens the tasks file, starting an empty one when it does not exist yet.
+    pub fn open(path: &Path) -> Result<Self, TaskEditError> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                format!("version = {TASK_FILE_VERSION}\n")
+            }
+            Err(source) => {
+                return Err(TaskEditError::ReadFailed {
+                    path: path.to_path_buf(),
+                    source,
+                });
+            }
+        };
+        let doc = data
+            .parse::<DocumentMut>()
+            .map_err(|source| TaskEditError::ParseFailed {
+                path: path.to_path_buf(),
+                source: Box::new(source),
+            })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            doc,
+        })
+    }
+
+    /// Appends `task` as a new `[[tasks]]` entry at the end of the file.
+    pub fn append(&mut self, task: &Task) -> Result<(), TaskEditError> {
+        if self.position(&task.id)?.is_some() {
+            return Err(TaskEditError::DuplicateTaskId {
+                id: task.id.clone(),
+            });
+        }
+        let mut table = task_table(task);
+        table.decor_mut().set_prefix("\n");
+        self.tasks_mut()?.push(table);
+        Ok(())
+    }
+
+    /// Rewrites the `status` key of an existing task in place.
+    pub fn set_status(&mut self, id: &str, status: TaskStatus) -> Result<(), TaskEditError> {
+        let index = self
+            .position(id)?
+            .ok_or_else(|| TaskEditError::UnknownTask { id: id.to_string() })?;
+        let table = self
+            .tasks_mut()?
+            .get_mut(index)
+            .expect("position returned a valid index");
+        match table.get_mut("status").and_then(Item::as_value_mut) {
+            Some(existing) => {
+                let decor = existing.decor().clone();
+                *existing = status.as_str().into();
+                *existing.decor_mut() = decor;
+            }
+            None => {
+                table.insert("status", value(status.as_str()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the edited document with the regular loader.
+    pub fn validate(&self) -> Result<TaskSet, TaskEditError> {
+        Ok(TaskSet::from_str(&self.doc.to_string())?)
+    }
+
+    /// Validates the document and writes it back atomically.
+    pub fn save(&self) -> Result<TaskSet, TaskEditError> {
+        let set = self.validate()?;
+        let write_error = |source| TaskEditError::WriteFailed {
+            path: self.path.clone(),
+            source,
+        };
+        let tmp = self.path.with_extension("toml.tmp");
+        fs::write(&tmp, self.doc.to_string()).map_err(write_error)?;
+        fs::rename(&tmp, &self.path).map_err(write_error)?;
+        Ok(set)
+    }
+
+    fn position(&self, id: &str) -> Result<Option<usize>, TaskEditError> {
+        let Some(item) = self.doc.get("tasks") else {
+            return Ok(None);
+        };
+        let tasks = item
+            .as_array_of_tables()
+            .ok_or_else(|| TaskEditError::NotTaskArray {
+                path: self.path.clone(),
+            })?;
+        Ok(tasks
+            .iter()
+            .position(|table| table.get("id").and_then(Item::as_str) == Some(id)))
+    }
+
+    fn tasks_mut(&mut self) -> Result<&mut ArrayOfTables, TaskEditError> {
+        let path = self.path.clone();
+        self.doc
+            .entry("tasks")
+            .or_insert(Item::ArrayOfTables(ArrayOfTables::new()))
+            .as_array_of_tables_mut()
+            .ok_or(TaskEditError::NotTaskArray { path })
+    }
+}
+
+impl std::fmt::Display for TaskDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+/// Builds the `[[tasks]]` table for a new task, skipping empty fields.
+fn task_table(task: &Task) -> Table {
+    let mut table = Table::new();
+    table.insert("id", value(&task.id));
+    table.insert("title", value(&task.title));
+    table.insert("status", value(task.status.as_str()));
+    if let Some(priority) = &task.priority {
+        table.insert("priority", value(priority));
+    }
+    if let Some(lang) = &task.lang {
+        table.insert("lang", value(lang));
+    }
+    for (key, items) in [("tags", &task.tags), ("depends_on", &task.depends_on)] {
+        if !items.is_empty() {
+            table.insert(key, value(string_array(items)));
+        }
+    }
+    if let Some(description) = &task.description {
+        table.insert("description", value(description));
+    }
+    if !task.acceptance.is_empty() {
+        table.insert("acceptance", value(string_array(&task.acceptance)));
+    }
+    table
+}
+
+fn string_array(items: &[String]) -> Array {
+    items.iter().map(String::as_str).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const TASKS: &str = r#"# Project backlog
+version = 1
+
+[[tasks]]
+id = "A-1"
+title = "Add login"   # keep this comment
+status = "todo"
+
+# Later work
+[[tasks]]
+id = "A-2"
+title = "Add logout"
+"#;
+
+    fn new_task(id: &str) -> Task {
+        let mut task = TaskSet::from_str("version = 1\n[[tasks]]\nid = \"X\"\ntitle = \"X\"\n")
+            .unwrap()
+            .tasks
+            .remove(0);
+        task.id = id.to_string();
+        task.title = "Reset password".to_string();
+        task.tags = vec!["auth".into()];
+        task.priority = Some("high".into());
+        task
+    }
+
+    #[test]
+    fn append_keeps_comments_and_formatting() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("tasks.toml");
+        fs::write(&path, TASKS).unwrap();
+
+        let mut doc = TaskDocument::open(&path).unwrap();
+        doc.append(&new_task("A-3")).unwrap();
+        let set = doc.save().unwrap();
+        assert_eq!(set.tasks.len(), 3);
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with(TASKS));
+        assert!(written.ends_with(
+            "\n[[tasks]]\nid = \"A-3\"\ntitle = \"Reset password\"\nstatus = \"todo\"\npriority = \"high\"\ntags = [\"auth\"]\n"
+        ));
+    }
+
+    #[test]
+    fn append_rejects_duplicate_ids() {
+        let mut doc = TaskDocument {
+            path: "tasks.toml".into(),
+            doc: TASKS.parse().unwrap(),
+        };
+        assert!(matches!(
+            doc.append(&new_task("A-1")),
+            Err(TaskEditError::DuplicateTaskId { .. })
+        ));
+    }
+
+    #[test]
+    fn set_status_updates_in_place() {
+        let mut doc = TaskDocument {
+            path: "tasks.toml".into(),
+            doc: TASKS.parse().unwrap(),
+        };
+        doc.set_status("A-1", TaskStatus::Done).unwrap();
+        doc.set_status("A-2", TaskStatus::Blocked).unwrap();
+
+        let text = doc.to_string();
+        assert!(text.contains("title = \"Add login\"   # keep this comment\nstatus = \"done\"\n"));
+        assert!(text.contains("title = \"Add logout\"\nstatus = \"blocked\"\n"));
+        assert_eq!(doc.validate().unwrap().tasks[1].status, TaskStatus::Blocked);
+
+        assert!(matches!(
+            doc.set_status("Z-9", TaskStatus::Done),
+            Err(TaskEditError::UnknownTask { .. })
+        ));
+    }
+
+    #[test]
+    fn missing_file_starts_new_document() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("tasks.toml");
+
+        let mut doc = TaskDocument::open(&path).unwrap();
+        doc.append(&new_task("B-1")).unwrap();
+        doc.save().unwrap();
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("version = 1\n\n[[tasks]]\nid = \"B-1\""));
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use super::{Task, TaskLoadError, TaskSet, TaskStatus};
+
+/// Checks that every dependency exists and that dependencies form no cycle.
+pub(super) fn validate(tasks: &[Task]) -> Result<(), TaskLoadError> {
+    let index: HashMap<&str, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(position, task)| (task.id.as_str(), position))
+        .collect();
+
+    let mut edges = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        let mut deps = Vec::with_capacity(task.depends_on.len());
+        for dependency in &task.depends_on {
+            let Some(&position) = index.get(dependency.as_str()) else {
+                return Err(TaskLoadError::UnknownDependency {
+                    task: task.id.clone(),
+                    dependency: dependency.clone(),
+                });
+            };
+            deps.push(position);
+        }
+        edges.push(deps);
+    }
+
+    if let Some(cycle) = find_cycle(&edges) {
+        return Err(TaskLoadError::DependencyCycle {
+            path: cycle.into_iter().map(|i| tasks[i].id.clone()).collect(),
+        });
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Unvisited,
+    InProgress,
+    Done,
+}
+
+/// Depth-first search in file order; returns the first cycle found, closed
+/// by repeating its first task.
+fn find_cycle(edges: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let mut marks = vec![Mark::Unvisited; edges.len()];
+    for start in 0..edges.len() {
+        if marks[start] != Mark::Unvisited {
+            continue;
+        }
+        // Stack of (node, next edge to follow); it doubles as the current path.
+        let mut stack = vec![(start, 0)];
+        marks[start] = Mark::InProgress;
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let Some(&dep) = edges[node].get(*next) else {
+                marks[node] = Mark::Done;
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            match marks[dep] {
+                Mark::Unvisited => {
+                    marks[dep] = Mark::InProgress;
+                    stack.push((dep, 0));
+                }
+                Mark::InProgress => {
+                    let from = stack.iter().position(|(n, _)| *n == dep)?;
+                    let mut cycle: Vec<usize> = stack[from..].iter().map(|(n, _)| *n).collect();
+                    cycle.push(dep);
+                    return Some(cycle);
+                }
+                Mark::Done => {}
+            }
+        }
+    }
+    None
+}
+
+impl TaskSet {
+    /// `todo` tasks whose dependencies are all `done`, in file order.
+    pub fn ready(&self) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|task| task.status == TaskStatus::Todo)
+            .filter(|task| {
+                task.depends_on.iter().all(|id| {
+                    self.find(id)
+                        .is_some_and(|dep| dep.status == TaskStatus::Done)
+                })
+            })
+            .collect()
+    }
+
+    /// The most urgent ready task; ties keep file order.
+    pub fn next_ready(&self) -> Option<&Task> {
+        self.ready()
+            .into_iter()
+            .min_by_key(|task| task.priority_rank())
+    }
+}
+
+impl Task {
+    /// Sort key for `priority` where lower is more urgent.
+    ///
+    /// Understands `critical`/`high`/`medium`/`low`, `P0`..`Pn` and plain
+    /// numbers; tasks without a recognised priority come last.
+    pub fn priority_rank(&self) -> u32 {
+        self.priority
+            .as_deref()
+            .and_then(priority_rank)
+            .unwrap_or(u32::MAX)
+    }
+}
+
+pub(crate) fn priority_rank(priority: &str) -> Option<u32> {
+    let priority = priority.trim().to_ascii_lowercase();
+    match priority.as_str() {
+        "critical" | "urgent" => Some(0),
+        "high" => Some(1),
+        "medium" | "normal" => Some(2),
+        "low" => Some(3),
+        other => other.strip_prefix('p').unwrap_or(other).parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(body: &str) -> Result<TaskSet, TaskLoadError> {
+        TaskSet::from_str(&format!("version = 1\n{body}"))
+    }
+
+    #[test]
+    fn rejects_unknown_dependencies() {
+        let err = tasks("[[tasks]]\nid = \"A\"\ntitle = \"A\"\ndepends_on = [\"B\"]\n")
+            .expect_err("missing dependency fails");
+        assert_eq!(err.to_string(), "task 'A' depends on unknown task 'B'");
+    }
+
+    #[test]
+    fn reports_cycle_path() {
+        let err = tasks(
+            r#"
+[[tasks]]
+id = "A"
+title = "A"
+depends_on = ["B"]
+
+[[tasks]]
+id = "B"
+title = "B"
+depends_on = ["C"]
+
+[[tasks]]
+id = "C"
+title = "C"
+depends_on = ["A"]
+"#,
+        )
+        .expect_err("cycle fails");
+        assert_eq!(err.to_string(), "dependency cycle: A -> B -> C -> A");
+
+        let err = tasks("[[tasks]]\nid = \"S\"\ntitle = \"S\"\ndepends_on = [\"S\"]\n")
+            .expect_err("self dependency fails");
+        assert_eq!(err.to_string(), "dependency cycle: S -> S");
+    }
+
+    #[test]
+    fn ready_tasks_wait_for_done_dependencies() {
+        let set = tasks(
+            r#"
+[[tasks]]
+id = "A"
+title = "A"
+status = "done"
+
+[[tasks]]
+id = "B"
+title = "B"
+depends_on = ["A"]
+priority = "low"
+
+[[tasks]]
+id = "C"
+title = "C"
+depends_on = ["B"]
+priority = "critical"
+
+[[tasks]]
+id = "D"
+title = "D"
+priority = "P1"
+
+[[tasks]]
+id = "E"
+title = "E"
+status = "doing"
+"#,
+        )
+        .unwrap();
+
+        let ready: Vec<&str> = set.ready().iter().map(|task| task.id.as_str()).collect();
+        assert_eq!(ready, ["B", "D"]);
+        assert_eq!(set.next_ready().map(|task| task.id.as_str()), Some("D"));
+    }
+
+    #[test]
+    fn ranks_priority_labels() {
+        for (label, rank) in [
+            ("High", Some(1)),
+            ("P0", Some(0)),
+            ("7", Some(7)),
+            ("soon", None),
+        ] {
+            assert_eq!(priority_rank(label), rank, "{label}");
+        }
+    }
+}
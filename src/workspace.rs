@@ -8,6 +8,9 @@ use std::{
 
 use anyhow::{Context, Result, bail};
 use fs2::FileExt;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
 
 /// Name of the workspace directory that lives inside every project root.
 pub const WORKSPACE_DIR_NAME: &str = ".alisa";
@@ -48,10 +51,13 @@ pub struct Workspace {
 pub struct WorkspaceLock {
     file: File,
     path: PathBuf,
+    workspace: Workspace,
 }
 
 impl Drop for WorkspaceLock {
     fn drop(&mut self) {
+        // Recorded while the lock is still held so the audit trail stays ordered.
+        audit::emit(&self.workspace, lock_event(audit::EVENT_LOCK_RELEASED));
         let mut attempt = 0usize;
         let path = self.path.clone();
         if let Err(err) = unlock_with_retry(|| {
@@ -76,11 +82,20 @@ impl Drop for WorkspaceLock {
 }
 
 impl WorkspaceLock {
-    fn new(file: File, path: PathBuf) -> Self {
-        Self { file, path }
+    fn new(file: File, path: PathBuf, workspace: Workspace) -> Self {
+        audit::emit(&workspace, lock_event(audit::EVENT_LOCK_ACQUIRED));
+        Self {
+            file,
+            path,
+            workspace,
+        }
     }
 }
 
+fn lock_event(event: &str) -> AuditEvent {
+    AuditEvent::new(event).data(json!({ "pid": std::process::id() }))
+}
+
 const UNLOCK_RETRY_ATTEMPTS: usize = 3;
 const UNLOCK_RETRY_DELAY: Duration = Duration::from_millis(50);
 
@@ -118,6 +133,11 @@ impl Workspace {
         Ok(Self::new(cwd))
     }
 
+    /// Returns the project root that contains `.alisa`.
+    pub fn project_root(&self) -> &Path {
+        &self.project_root
+    }
+
     /// Returns the absolute path to `.alisa` inside the project root.
     pub fn workspace_root(&self) -> PathBuf {
         self.project_root.join(WORKSPACE_DIR_NAME)
@@ -149,6 +169,12 @@ impl Workspace {
             .expect("lock path is a fixed entry inside the workspace")
     }
 
+    /// Path to the lock that serializes writers of the audit trail.
+    pub fn audit_lock_path(&self) -> PathBuf {
+        self.join("locks/audit.lock")
+            .expect("audit lock path is a fixed entry inside the workspace")
+    }
+
     /// Path to the registry SQLite database.
     pub fn registry_path(&self) -> PathBuf {
         self.join("state/registry.sqlite")
@@ -209,14 +235,12 @@ impl Workspace {
     }
 
     /// Path to the directory that stores per-run artifacts.
-    #[allow(dead_code)]
     pub fn runs_root(&self) -> PathBuf {
         self.join("state/runs")
             .expect("runs directory is a fixed entry inside the workspace")
     }
 
     /// Path to a specific run directory.
-    #[allow(dead_code)]
     pub fn run_dir(&self, run_id: &str) -> Result<PathBuf> {
         let mut path = self.runs_root();
         let component = Self::sanitize_single_component(run_id, "run id")?;
@@ -225,7 +249,6 @@ impl Workspace {
     }
 
     /// Path to a run subdirectory matching a stage (plan/impl/review/etc).
-    #[allow(dead_code)]
     pub fn run_stage_dir(&self, run_id: &str, stage: &str) -> Result<PathBuf> {
         let mut path = self.run_dir(run_id)?;
         let component = Self::sanitize_single_component(stage, "stage")?;
@@ -256,6 +279,7 @@ impl Workspace {
             .read(true)
             .write(true)
             .create(true)
+            .truncate(false)
             .open(path)
     }
 
@@ -337,7 +361,7 @@ impl Workspace {
         let file = self.prepare_lock_file()?;
         let lock_path = self.lock_path();
         match file.try_lock_exclusive() {
-            Ok(()) => Ok(Some(WorkspaceLock::new(file, lock_path))),
+            Ok(()) => Ok(Some(WorkspaceLock::new(file, lock_path, self.clone()))),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
             Err(err) => Err(err).with_context(|| {
                 format!(
@@ -436,7 +460,7 @@ mod tests {
             .expect("lock should be recreated");
         drop(guard);
 
-        let metadata = fs::metadata(&workspace.lock_path()).expect("lock metadata");
+        let metadata = fs::metadata(workspace.lock_path()).expect("lock metadata");
         assert!(
             metadata.is_file(),
             "lock path must be a regular file after recovery"
@@ -467,7 +491,7 @@ mod tests {
         let path = workspace
             .run_stage_dir("run-123", "plan")
             .expect("valid stage path");
-        assert!(path.starts_with(&workspace.runs_root()));
+        assert!(path.starts_with(workspace.runs_root()));
         assert!(path.ends_with(Path::new("state/runs/run-123/plan")));
     }
 
@@ -0,0 +1,239 @@
+#![cfg(unix)]
+
+use predicates::str::contains;
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+use time::{Duration, OffsetDateTime, UtcOffset, format_description::well_known::Rfc3339};
+
+mod common;
+
+use common::{DIFF_SCRIPT, RUNNER_SCRIPT, alisa, init};
+
+const CONFIG: &str = r#"
+[runners.local]
+cmd = "sh"
+args = ["runner.sh"]
+
+[runners.coder]
+cmd = "sh"
+args = ["coder.sh"]
+
+[roles]
+plan = "local"
+code = "coder"
+review = "local"
+
+[apply]
+confirm = false
+"#;
+
+fn setup(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(root.join("runner.sh"), RUNNER_SCRIPT)?;
+    fs::write(root.join("coder.sh"), DIFF_SCRIPT)?;
+    fs::write(root.join("alisa.toml"), CONFIG)?;
+    fs::write(
+        root.join("tasks.toml"),
+        "version = 1\n\n[[tasks]]\nid = \"A-1\"\ntitle = \"Notes\"\n",
+    )?;
+    reset_sources(root)?;
+    init(root);
+    Ok(())
+}
+
+/// Puts back the files [`DIFF_SCRIPT`] patches.
+fn reset_sources(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(root.join("src/a.rs"), "old\n")?;
+    if root.join("src/b.rs").exists() {
+        fs::remove_file(root.join("src/b.rs"))?;
+    }
+    Ok(())
+}
+
+/// Events of the only daily audit file, in file order.
+fn daily_events(root: &Path) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let files: Vec<_> = fs::read_dir(root.join(".alisa/audit/runs"))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(files.len(), 1, "one daily file expected");
+    Ok(fs::read_to_string(&files[0])?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn commands_append_indexed_audit_events() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+    alisa(temp.path()).args(["run", "A-1"]).assert().success();
+    fs::write(
+        temp.path().join("alisa.toml"),
+        CONFIG.replace("confirm = false", "confirm = false\n# edited"),
+    )?;
+    reset_sources(temp.path())?;
+    alisa(temp.path()).args(["run", "A-1"]).assert().success();
+
+    let events = daily_events(temp.path())?;
+    let names: Vec<&str> = events
+        .iter()
+        .map(|event| event["event"].as_str().unwrap())
+        .collect();
+    let run = [
+        "lock.acquired",
+        "run.started",
+        "patch.applied",
+        "run.finished",
+        "lock.released",
+    ];
+    let mut expected = vec!["init", "config.changed", "lock.released"];
+    expected.extend(run);
+    expected.push("lock.acquired");
+    expected.push("config.changed");
+    expected.extend(&run[1..]);
+    assert_eq!(names, expected);
+
+    let finished = &events[6];
+    assert_eq!(finished["task_id"], "A-1");
+    assert_eq!(finished["data"]["success"], true);
+    assert_eq!(events[5]["data"]["files"][0], "src/a.rs");
+    assert!(events[9]["data"]["previous"].is_string());
+
+    let conn = rusqlite::Connection::open(temp.path().join(".alisa/audit/audit_index.sqlite"))?;
+    let daily = fs::read_dir(temp.path().join(".alisa/audit/runs"))?
+        .next()
+        .unwrap()?
+        .path();
+    let contents = fs::read(&daily)?;
+    let indexed = conn
+        .prepare("SELECT offset, event, run_id FROM events ORDER BY offset")?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(indexed.len(), events.len());
+    for ((offset, event, run_id), line) in indexed.iter().zip(&events) {
+        let at_offset: Value = serde_json::from_slice(
+            contents[*offset as usize..]
+                .split(|byte| *byte == b'\n')
+                .next()
+                .unwrap(),
+        )?;
+        assert_eq!(&at_offset, line);
+        assert_eq!(line["event"], event.as_str());
+        assert_eq!(line["run_id"].as_str(), run_id.as_deref());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn audit_query_filters_events() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+    alisa(temp.path()).args(["run", "A-1"]).assert().success();
+
+    let output = alisa(temp.path())
+        .args(["audit", "query", "--task", "A-1", "--ndjson"])
+        .args(["--event", "run.finished", "--since", "2000-01-01"])
+        .output()?;
+    assert!(output.status.success());
+    let lines: Vec<Value> = String::from_utf8(output.stdout)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["event"], "run.finished");
+    assert_eq!(lines[0]["data"]["success"], true);
+
+    alisa(temp.path())
+        .args(["audit", "query", "--event", "patch.applied"])
+        .assert()
+        .success()
+        .stdout(contains("TIME"))
+        .stdout(contains("patch.applied  A-1"))
+        .stdout(contains("\"files\":[\"src/a.rs\",\"src/b.rs\"]"));
+    alisa(temp.path())
+        .args(["audit", "query", "--until", "2000-01-01"])
+        .assert()
+        .success()
+        .stdout("No matching events.\n");
+
+    Ok(())
+}
+
+#[test]
+fn audit_query_compares_bounds_with_offsets_as_utc() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    // An hour from now in UTC-5: later than every event, although its text
+    // sorts before their UTC timestamps.
+    let bound = (OffsetDateTime::now_utc() + Duration::hours(1))
+        .to_offset(UtcOffset::from_hms(-5, 0, 0)?)
+        .format(&Rfc3339)?;
+    alisa(temp.path())
+        .args(["audit", "query", "--since", &bound])
+        .assert()
+        .success()
+        .stdout("No matching events.\n");
+    alisa(temp.path())
+        .args(["audit", "query", "--until", &bound])
+        .assert()
+        .success()
+        .stdout(contains("init"));
+
+    Ok(())
+}
+
+#[test]
+fn audit_verify_detects_tampering() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+    alisa(temp.path()).args(["run", "A-1"]).assert().success();
+
+    alisa(temp.path())
+        .args(["audit", "verify"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "[ok] Audit chain intact: 8 event(s) across 1 day(s).",
+        ));
+    alisa(temp.path())
+        .args(["init", "--check", "--verify-audit"])
+        .assert()
+        .success();
+
+    let daily = fs::read_dir(temp.path().join(".alisa/audit/runs"))?
+        .next()
+        .unwrap()?
+        .path();
+    let contents = fs::read_to_string(&daily)?;
+    fs::write(
+        &daily,
+        contents.replacen("\"success\":true", "\"success\":false", 1),
+    )?;
+
+    alisa(temp.path())
+        .args(["audit", "verify"])
+        .assert()
+        .code(1)
+        .stderr(contains("audit chain broken at "))
+        .stderr(contains("previous hash is"));
+    alisa(temp.path())
+        .args(["init", "--check", "--verify-audit"])
+        .assert()
+        .code(1)
+        .stderr(contains("Audit chain broken at"));
+    alisa(temp.path())
+        .args(["init", "--check"])
+        .assert()
+        .success();
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Fixtures shared by the integration tests.
+
+// Every test crate compiles this module but uses only part of it.
+#![allow(dead_code)]
+
+use std::{fs, path::Path};
+
+/// Runner that echoes the role heading of its prompt.
+pub const RUNNER_SCRIPT: &str = r#"#!/bin/sh
+echo "stage output for: $(grep '^## Your role')"
+"#;
+
+/// Runner that fails with exit code 7 and `boom` on stderr.
+pub const FAILING_SCRIPT: &str = r#"#!/bin/sh
+cat >/dev/null
+echo "boom" >&2
+exit 7
+"#;
+
+/// Runner answering with a diff that changes `src/a.rs` (`old` to `new`) and
+/// creates `src/b.rs`.
+pub const DIFF_SCRIPT: &str = r#"#!/bin/sh
+cat >/dev/null
+cat <<'DIFF'
+Changes:
+```diff
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +1 @@
+-old
++new
+--- /dev/null
++++ b/src/b.rs
+@@ -0,0 +1,2 @@
++one
++two
+```
+DIFF
+"#;
+
+pub const TASKS: &str = r#"
+version = 1
+
+[[tasks]]
+id = "A-1"
+title = "Add login"
+description = "Implement the login form."
+acceptance = ["form validates email"]
+
+[[tasks]]
+id = "B-1"
+title = "Docs"
+"#;
+
+/// `alisa` run from `root`.
+pub fn alisa(root: &Path) -> assert_cmd::Command {
+    let mut cmd = assert_cmd::cargo::cargo_bin_cmd!("alisa");
+    cmd.current_dir(root);
+    cmd
+}
+
+/// Writes [`TASKS`] and a config running plan and code with
+/// [`RUNNER_SCRIPT`] on model `small`, and review with `review_script` on
+/// model `large`.
+pub fn write_config(root: &Path, review_script: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(root.join("runner.sh"), RUNNER_SCRIPT)?;
+    fs::write(root.join("review.sh"), review_script)?;
+    fs::write(
+        root.join("alisa.toml"),
+        r#"
+[runners.local]
+cmd = "sh"
+args = ["runner.sh"]
+model = "small"
+
+[runners.reviewer]
+cmd = "sh"
+args = ["review.sh"]
+model = "large"
+
+[roles]
+plan = "local"
+code = "local"
+review = "reviewer"
+"#,
+    )?;
+    fs::write(root.join("tasks.toml"), TASKS)?;
+    Ok(())
+}
+
+pub fn init(root: &Path) {
+    alisa(root).arg("init").assert().success();
+}
@@ -0,0 +1,209 @@
+use predicates::str::contains;
+use serde_json::Value;
+use std::fs;
+use tempfile::tempdir;
+
+mod common;
+
+use common::{alisa, init};
+
+const CONFIG: &str = r#"
+[runners.local]
+cmd = "sh"
+
+[roles]
+plan = "local"
+code = "local"
+
+[limits]
+max_files = 5
+max_tokens = 8000
+"#;
+
+const LOCAL: &str = r#"
+[runners.local]
+args = ["fast.sh"]
+
+[limits]
+max_tokens = 4000
+"#;
+
+#[test]
+fn config_show_merges_layers_and_reports_origins() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    fs::write(temp.path().join("alisa.toml"), CONFIG)?;
+    fs::write(temp.path().join("alisa.local.toml"), LOCAL)?;
+
+    alisa(temp.path())
+        .args(["config", "show", "--origin"])
+        .env("ALISA_ROLES__REVIEW", "local")
+        .assert()
+        .success()
+        .stdout(contains(
+            "[limits]\nmax_files = 5  # alisa.toml\nmax_tokens = 4000  # alisa.local.toml\n",
+        ))
+        .stdout(contains("review = \"local\"  # env ALISA_ROLES__REVIEW\n"))
+        .stdout(contains(
+            "[runners.local]\nargs = [\"fast.sh\"]  # alisa.local.toml\ncmd = \"sh\"  # alisa.toml\n",
+        ));
+
+    alisa(temp.path())
+        .args(["config", "show"])
+        .assert()
+        .success()
+        .stdout(contains("max_tokens = 4000\n"));
+
+    alisa(temp.path())
+        .args(["config", "show"])
+        .env("ALISA_ROLES__CODE", "remote")
+        .assert()
+        .code(4)
+        .stderr(contains("role 'code' references unknown runner 'remote'"));
+
+    Ok(())
+}
+
+#[test]
+fn commands_use_the_effective_configuration() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    fs::write(temp.path().join("alisa.toml"), CONFIG)?;
+    fs::write(temp.path().join("alisa.local.toml"), LOCAL)?;
+    init(temp.path());
+
+    let search = |env: Option<&str>| -> Result<Value, Box<dyn std::error::Error>> {
+        let mut cmd = alisa(temp.path());
+        cmd.args(["rag", "search", "anything", "--json"]);
+        if let Some(tokens) = env {
+            cmd.env("ALISA_LIMITS__MAX_TOKENS", tokens);
+        }
+        let output = cmd.output()?;
+        assert!(output.status.success());
+        Ok(serde_json::from_slice(&output.stdout)?)
+    };
+    assert_eq!(search(None)?["budget"], 1000);
+    assert_eq!(search(Some("2000"))?["budget"], 500);
+
+    Ok(())
+}
+
+#[test]
+fn config_validate_reports_located_problems() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    fs::write(temp.path().join("alisa.toml"), CONFIG)?;
+
+    alisa(temp.path())
+        .args(["config", "validate"])
+        .assert()
+        .success()
+        .stdout("[ok] Configuration is valid (alisa.toml)\n");
+
+    alisa(temp.path())
+        .args(["config", "validate"])
+        .env("ALISA_LIMITS__MAX_FILES", "many")
+        .assert()
+        .code(4)
+        .stderr(contains(
+            "error: invalid type: string \"many\", expected u32\n --> env ALISA_LIMITS__MAX_FILES\n  = key: limits.max_files\n",
+        ));
+
+    fs::write(
+        temp.path().join("alisa.local.toml"),
+        "[roles]\nreview = \"remote\"\n",
+    )?;
+    alisa(temp.path())
+        .args(["config", "validate"])
+        .assert()
+        .code(4)
+        .stderr(contains(
+            "error: role 'review' references unknown runner 'remote'\n --> alisa.local.toml:2:10\n  |\n2 | review = \"remote\"\n  |          ^^^^^^^^\n  = key: roles.review\n",
+        ))
+        .stderr(contains("invalid configuration: 1 problem(s) found"));
+
+    fs::write(temp.path().join("alisa.local.toml"), "[roles\n")?;
+    alisa(temp.path())
+        .args(["config", "validate"])
+        .assert()
+        .code(4)
+        .stderr(contains(" --> alisa.local.toml:1:"));
+
+    fs::remove_file(temp.path().join("alisa.local.toml"))?;
+    let rule = "\n[[routing]]\nrole = \"code\"\nuse = \"local\"\nwhen.lang = \"rust\"\n";
+    fs::write(
+        temp.path().join("alisa.toml"),
+        format!("{CONFIG}{rule}{rule}"),
+    )?;
+    alisa(temp.path())
+        .args(["config", "validate"])
+        .assert()
+        .success()
+        .stdout("[ok] Configuration is valid (alisa.toml) with 1 warning(s)\n")
+        .stderr(contains(
+            "warning: routing rule #1 for role 'code' is shadowed by rule #0 and never applies\n  --> alisa.toml:18:1\n",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn config_explain_describes_each_section() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    let config = format!(
+        "{CONFIG}{}",
+        r#"
+[profiles.fast.roles]
+code = "local"
+
+[[routing]]
+role = "code"
+use = "local"
+profile = "fast"
+when.lang = "rust"
+when.task_id = "A-*"
+
+[[routing]]
+role = "review"
+use = "local"
+priority = 2
+when.tags = ["auth", "security"]
+when.any = [{ priority = "high" }, { lang = "go", status = ["todo", "doing"] }]
+when.not = { status = "done" }
+
+[review]
+default_pipeline = "strict"
+
+[review.pipelines.strict]
+stages = ["build", "llm"]
+fail_on = ["build"]
+
+[review.stages.build]
+type = "exec"
+cmd = ["cargo", "check"]
+
+[review.stages.llm]
+type = "llm"
+runner = "local"
+"#
+    );
+    fs::write(temp.path().join("alisa.toml"), config)?;
+
+    alisa(temp.path())
+        .args(["config", "explain"])
+        .assert()
+        .success()
+        .stdout(contains("Runners\n  local: runs `sh`, prompt on stdin\n"))
+        .stdout(contains("  plan → local, code → local\n  limits: max_files 5, max_tokens 8000\n"))
+        .stdout(contains("Profiles\n  fast: code → local\n"))
+        .stdout(contains(
+            "  #0: code runs on local when the language is rust and the task id matches A-*, then switches to profile fast\n",
+        ))
+        .stdout(contains(
+            "  #1 (priority 2): review runs on local when the task is tagged one of auth, security and (the priority is high or (the language is go and the status is one of todo, doing)) and not the status is done\n",
+        ))
+        .stdout(contains(
+            "Review pipelines (default: strict)\n  strict: build → llm; passes when every stage passes; fails whenever build fails\n",
+        ))
+        .stdout(contains("  build: runs `cargo check` and passes on exit code 0\n"))
+        .stdout(contains("  llm: asks local for a verdict\n"));
+
+    Ok(())
+}
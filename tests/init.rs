@@ -35,7 +35,7 @@ fn init_creates_workspace_structure() -> Result<(), Box<dyn std::error::Error>>
     );
     assert_eq!(
         manifest.get("schema_version"),
-        Some(&Value::String("1.0".into()))
+        Some(&Value::String("1.1".into()))
     );
 
     Ok(())
@@ -0,0 +1,132 @@
+use predicates::str::contains;
+use rusqlite::Connection;
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+mod common;
+
+use common::{alisa, init};
+
+/// Turns a fresh workspace into one written by a 1.0 binary: no chain
+/// hashes in the audit files, no `chain_heads` table, 1.0 markers.
+fn downgrade(workspace: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    for entry in fs::read_dir(workspace.join("audit"))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "ndjson") {
+            let mut content = String::new();
+            for line in fs::read_to_string(&path)?.lines() {
+                let mut event: Value = serde_json::from_str(line)?;
+                event.as_object_mut().unwrap().remove("prev");
+                content.push_str(&format!("{event}\n"));
+            }
+            fs::write(&path, content)?;
+        }
+    }
+    Connection::open(workspace.join("audit/audit_index.sqlite"))?
+        .execute_batch("DROP TABLE chain_heads;")?;
+    fs::write(workspace.join("migrations/version.txt"), "1.0\n")?;
+    let manifest_path = workspace.join("manifest.json");
+    let mut manifest: Value = serde_json::from_slice(&fs::read(&manifest_path)?)?;
+    manifest["schema_version"] = "1.0".into();
+    fs::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(())
+}
+
+#[test]
+fn migrate_upgrades_an_old_workspace_in_place() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    let workspace = temp.path().join(".alisa");
+    init(temp.path());
+    init(temp.path());
+    downgrade(&workspace)?;
+
+    alisa(temp.path())
+        .arg("init")
+        .assert()
+        .code(2)
+        .stderr(contains("run `alisa migrate`"));
+
+    let before = fs::read(workspace.join("audit/audit_index.sqlite"))?;
+    alisa(temp.path())
+        .args(["migrate", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(contains("[plan] 1.0 → 1.1: Hash-chain the audit trail"))
+        .stdout(contains("[plan]   Back up the audit index"))
+        .stdout(contains("[plan]   Chain audit events written before 1.1"));
+    assert_eq!(
+        fs::read(workspace.join("audit/audit_index.sqlite"))?,
+        before
+    );
+    assert_eq!(
+        fs::read_to_string(workspace.join("migrations/version.txt"))?,
+        "1.0\n"
+    );
+    assert!(!workspace.join("migrations/backups").exists());
+
+    alisa(temp.path())
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout(contains("[ok] Migrated 1.0 → 1.1"))
+        .stdout(contains("[ok] Backups saved to"));
+
+    let backups: Vec<_> =
+        fs::read_dir(workspace.join("migrations/backups"))?.collect::<Result<_, _>>()?;
+    assert_eq!(backups.len(), 1);
+    let backup_name = backups[0].file_name();
+    assert!(backup_name.to_string_lossy().starts_with("1.0-1.1-"));
+    let backup_tables: i64 = Connection::open(backups[0].path().join("audit_index.sqlite"))?
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = 'chain_heads'",
+            [],
+            |row| row.get(0),
+        )?;
+    assert_eq!(backup_tables, 0);
+
+    assert_eq!(
+        fs::read_to_string(workspace.join("migrations/version.txt"))?,
+        "1.1\n"
+    );
+    let manifest: Value = serde_json::from_slice(&fs::read(workspace.join("manifest.json"))?)?;
+    assert_eq!(manifest["schema_version"], "1.1");
+
+    alisa(temp.path())
+        .args(["audit", "verify"])
+        .assert()
+        .success()
+        .stdout(contains("[ok] Audit chain intact"));
+    alisa(temp.path())
+        .args(["init", "--check", "--verify-audit"])
+        .assert()
+        .success();
+    alisa(temp.path())
+        .args(["audit", "query", "--event", "schema.migrated"])
+        .assert()
+        .success()
+        .stdout(contains("schema.migrated"));
+
+    alisa(temp.path())
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout("[ok] Workspace schema is up to date (1.1).\n");
+
+    Ok(())
+}
+
+#[test]
+fn migrate_rejects_unknown_schema_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    init(temp.path());
+    fs::write(temp.path().join(".alisa/migrations/version.txt"), "9.0\n")?;
+
+    alisa(temp.path())
+        .arg("migrate")
+        .assert()
+        .code(1)
+        .stderr(contains("no migration path from schema version 9.0 to 1.1"));
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use predicates::str::contains;
+use rusqlite::Connection;
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+mod common;
+
+use common::{alisa, init};
+
+const CONFIG: &str = r#"
+[runners.local]
+cmd = "sh"
+
+[roles]
+plan = "local"
+code = "local"
+review = "local"
+"#;
+
+const TASKS: &str = r#"version = 1
+
+[[tasks]]
+id = "A-1"
+title = "Add login"
+
+[tasks.context]
+docs = ["src/auth.rs", "missing.md"]
+"#;
+
+fn setup(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(root.join("alisa.toml"), CONFIG)?;
+    fs::write(root.join("tasks.toml"), TASKS)?;
+    fs::create_dir_all(root.join("docs/guide"))?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(
+        root.join("docs/guide/auth.md"),
+        "# Authentication\nTokens expire after an hour.\n\n## Sessions\nSessions are stored in Redis.\n",
+    )?;
+    fs::write(root.join("docs/logo.png"), [0u8, 159, 146, 150])?;
+    fs::write(
+        root.join("src/auth.rs"),
+        "pub fn login() {\n    issue_token();\n}\n",
+    )?;
+    init(root);
+    Ok(())
+}
+
+#[test]
+fn rag_index_chunks_docs_and_skips_unchanged_files() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    alisa(temp.path())
+        .args(["rag", "index"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "[ok] Indexed 2 file(s) into 3 chunk(s): 0 unchanged, 0 removed",
+        ))
+        .stderr(contains("[warn] Docs path missing.md does not exist"));
+
+    let conn = Connection::open(temp.path().join(".alisa/cache/rag/index.sqlite"))?;
+    let rows = conn
+        .prepare("SELECT id, source, meta FROM docs ORDER BY id")?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let ids: Vec<&str> = rows.iter().map(|(id, _, _)| id.as_str()).collect();
+    assert_eq!(
+        ids,
+        [
+            "docs/guide/auth.md:1-2",
+            "docs/guide/auth.md:4-5",
+            "src/auth.rs:1-3"
+        ]
+    );
+    let meta: Value = serde_json::from_str(&rows[1].2)?;
+    assert_eq!(meta["kind"], "markdown");
+    assert_eq!(meta["heading"], "Sessions");
+    assert_eq!(meta["start_line"], 4);
+    assert_eq!(meta["end_line"], 5);
+    let hits: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM docs_fts WHERE docs_fts MATCH 'redis'",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(hits, 1);
+
+    alisa(temp.path())
+        .args(["rag", "index"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "[ok] Indexed 0 file(s) into 0 chunk(s): 2 unchanged, 0 removed",
+        ));
+
+    fs::remove_file(temp.path().join("docs/guide/auth.md"))?;
+    fs::write(
+        temp.path().join("src/auth.rs"),
+        "pub fn login() {\n    issue_token();\n    audit();\n}\n",
+    )?;
+    alisa(temp.path())
+        .args(["rag", "index"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "[ok] Indexed 1 file(s) into 1 chunk(s): 0 unchanged, 1 removed",
+        ));
+    alisa(temp.path())
+        .args(["rag", "index", "--force"])
+        .assert()
+        .success()
+        .stdout(contains("[ok] Indexed 1 file(s) into 1 chunk(s)"));
+
+    Ok(())
+}
+
+#[test]
+fn rag_search_shows_scores_and_locations() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+    alisa(temp.path()).args(["rag", "index"]).assert().success();
+
+    alisa(temp.path())
+        .args(["rag", "search", "How long do tokens last?"])
+        .assert()
+        .success()
+        .stdout(contains("docs/guide/auth.md:1-2  ("))
+        .stdout(contains("# Authentication"))
+        .stdout(contains("Tokens expire after an hour."))
+        .stdout(contains("of 2000 tokens"));
+
+    let output = alisa(temp.path())
+        .args(["rag", "search", "redis sessions", "--json"])
+        .output()?;
+    assert!(output.status.success());
+    let result: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(result["budget"], 2000);
+    assert_eq!(result["hits"][0]["id"], "docs/guide/auth.md:4-5");
+    assert!(result["hits"][0]["score"].as_f64().unwrap() > 0.0);
+
+    alisa(temp.path())
+        .args(["rag", "search", "kubernetes"])
+        .assert()
+        .success()
+        .stdout("No matching docs.\n");
+
+    Ok(())
+}
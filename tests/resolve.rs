@@ -0,0 +1,167 @@
+use predicates::str::contains;
+use std::fs;
+use tempfile::tempdir;
+
+mod common;
+
+use common::alisa;
+
+const CONFIG: &str = r#"
+[runners.local]
+cmd = "sh"
+
+[runners.remote]
+cmd = "sh"
+
+[roles]
+plan = "local"
+code = "local"
+review = "local"
+
+[profiles.default.roles]
+code = "local"
+
+[profiles.heavy.roles]
+review = "remote"
+
+[[routing]]
+role = "code"
+use = "remote"
+when.lang = "go"
+
+[[routing]]
+role = "code"
+use = "remote"
+profile = "heavy"
+when.lang = "rust"
+when.task_id = "A-*"
+"#;
+
+const TASKS: &str = r#"
+version = 1
+
+[[tasks]]
+id = "A-1"
+title = "Add login"
+lang = "rust"
+"#;
+
+#[test]
+fn resolve_prints_the_decision_trace() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    fs::write(temp.path().join("alisa.toml"), CONFIG)?;
+    fs::write(temp.path().join("tasks.toml"), TASKS)?;
+
+    alisa(temp.path())
+        .args(["resolve", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains("Task A-1 (lang: rust, profile: default)\n"))
+        .stdout(contains("plan → local [global roles]\n"))
+        .stdout(contains(
+            "code → remote [routing rule #1]\n  rule #0: skipped (priority 0, specificity lang)\n    ✗ lang = go (task: rust)\n  rule #1: selected (priority 0, specificity task_id + lang)\n    ✓ lang = rust (task: rust)\n    ✓ task_id matches A-* (task: A-1)\n  switches to profile heavy\n",
+        ))
+        .stdout(contains("review → remote [profile heavy]\n"))
+        .stdout(contains("Final profile: heavy\n"));
+
+    alisa(temp.path())
+        .args(["resolve", "A-1", "--lang", "python", "--profile", "heavy"])
+        .assert()
+        .success()
+        .stdout(contains("Task A-1 (lang: python, profile: heavy)\n"))
+        .stdout(contains(
+            "code → local [global roles]\n  rule #0: skipped (priority 0, specificity lang)\n    ✗ lang = go (task: python)\n  rule #1: skipped (priority 0, specificity task_id + lang)\n    ✗ lang = rust (task: python)\n    ✓ task_id matches A-* (task: A-1)\n",
+        ));
+
+    alisa(temp.path())
+        .args(["resolve", "B-9"])
+        .assert()
+        .code(1)
+        .stderr(contains("task 'B-9' is not defined in the tasks file"));
+
+    Ok(())
+}
+
+#[test]
+fn resolve_shows_nested_condition_checks() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    let config = format!(
+        "{CONFIG}{}",
+        r#"
+[[routing]]
+role = "plan"
+use = "remote"
+when.tags = ["auth", "security"]
+when.not = { status = "done" }
+"#
+    );
+    fs::write(temp.path().join("alisa.toml"), config)?;
+    fs::write(
+        temp.path().join("tasks.toml"),
+        format!("{TASKS}tags = [\"auth\"]\nstatus = \"done\"\n"),
+    )?;
+
+    alisa(temp.path())
+        .args(["resolve", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "plan → local [global roles]\n  rule #2: skipped (priority 0, specificity 2 other)\n    ✓ tags include any of [auth, security] (task: auth)\n    ✗ not\n      ✓ status = done (task: done)\n",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn resolve_ranks_matching_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    let config = r#"
+[runners.local]
+cmd = "sh"
+
+[runners.remote]
+cmd = "sh"
+
+[runners.fallback]
+cmd = "sh"
+
+[roles]
+plan = "local"
+code = "local"
+review = "local"
+
+[[routing]]
+role = "code"
+use = "fallback"
+when.lang = "rust"
+
+[[routing]]
+role = "code"
+use = "remote"
+when.task_id = "A-*"
+"#;
+    fs::write(temp.path().join("alisa.toml"), config)?;
+    fs::write(temp.path().join("tasks.toml"), TASKS)?;
+
+    alisa(temp.path())
+        .args(["resolve", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "code → remote [routing rule #1]\n  rule #0: matched, outranked (priority 0, specificity lang)\n",
+        ));
+
+    fs::write(
+        temp.path().join("alisa.toml"),
+        config.replace("use = \"fallback\"\n", "use = \"fallback\"\npriority = 5\n"),
+    )?;
+    alisa(temp.path())
+        .args(["resolve", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "code → fallback [routing rule #0]\n  rule #0: selected (priority 5, specificity lang)\n",
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,521 @@
+#![cfg(unix)]
+
+use predicates::str::contains;
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+mod common;
+
+use common::{DIFF_SCRIPT, FAILING_SCRIPT, RUNNER_SCRIPT, alisa, init, write_config};
+
+fn single_run_dir(root: &Path) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    let mut runs = fs::read_dir(root.join(".alisa/state/runs"))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(runs.len(), 1, "exactly one run directory expected");
+    Ok(runs.remove(0))
+}
+
+#[test]
+fn run_executes_all_stages() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains("[review] ok"));
+
+    let run_dir = single_run_dir(temp.path())?;
+    for stage in ["plan", "code", "review"] {
+        let stage_dir = run_dir.join(stage);
+        assert!(stage_dir.join("prompt.md").exists());
+        assert!(stage_dir.join("stderr.log").exists());
+        let stdout = fs::read_to_string(stage_dir.join("stdout.log"))?;
+        assert_eq!(
+            stdout.trim(),
+            format!("stage output for: ## Your role: {stage}")
+        );
+    }
+
+    let code_prompt = fs::read_to_string(run_dir.join("code/prompt.md"))?;
+    assert!(code_prompt.contains("## Output of the plan stage"));
+    let code_result: Value = serde_json::from_slice(&fs::read(run_dir.join("code/result.json"))?)?;
+    assert_eq!(code_result["template"], "builtin:code");
+
+    let summary: Value = serde_json::from_slice(&fs::read(run_dir.join("run.json"))?)?;
+    assert_eq!(summary["task_id"], "A-1");
+    assert_eq!(summary["success"], true);
+    assert_eq!(summary["runners"]["review"], "reviewer");
+
+    Ok(())
+}
+
+#[test]
+fn run_renders_templates_from_prompt_dir() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+    let config = fs::read_to_string(temp.path().join("alisa.toml"))?;
+    fs::write(
+        temp.path().join("alisa.toml"),
+        config.replace(
+            "args = [\"runner.sh\"]\n",
+            "args = [\"runner.sh\"]\nprompt_dir = \"prompts\"\n",
+        ),
+    )?;
+    fs::create_dir_all(temp.path().join("prompts"))?;
+    fs::write(
+        temp.path().join("prompts/plan.md"),
+        "Plan {{ task.id }} for {{ profile or 'no profile' }}\n## Your role: custom plan\n",
+    )?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1"])
+        .assert()
+        .success();
+
+    let run_dir = single_run_dir(temp.path())?;
+    assert_eq!(
+        fs::read_to_string(run_dir.join("plan/prompt.md"))?,
+        "Plan A-1 for no profile\n## Your role: custom plan\n"
+    );
+    let code_prompt = fs::read_to_string(run_dir.join("code/prompt.md"))?;
+    assert!(code_prompt.contains("stage output for: ## Your role: custom plan"));
+
+    Ok(())
+}
+
+#[test]
+fn run_reports_failing_stage() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), FAILING_SCRIPT)?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1"])
+        .assert()
+        .code(1)
+        .stderr(contains("stage 'review' failed"));
+
+    let run_dir = single_run_dir(temp.path())?;
+    let stderr = fs::read_to_string(run_dir.join("review/stderr.log"))?;
+    assert_eq!(stderr.trim(), "boom");
+    let summary: Value = serde_json::from_slice(&fs::read(run_dir.join("run.json"))?)?;
+    assert_eq!(summary["success"], false);
+
+    let conn = rusqlite::Connection::open(temp.path().join(".alisa/state/registry.sqlite"))?;
+    let review: (bool, Option<String>) = conn.query_row(
+        "SELECT success, finished_at FROM runs WHERE stage = 'review'",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    assert!(!review.0);
+    assert!(review.1.is_some(), "failed stages are finished too");
+
+    Ok(())
+}
+
+#[test]
+fn run_records_stages_and_artifacts_in_registry() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1"])
+        .assert()
+        .success();
+    let run_dir = single_run_dir(temp.path())?;
+    let run_id = run_dir.file_name().unwrap().to_string_lossy().to_string();
+
+    let conn = rusqlite::Connection::open(temp.path().join(".alisa/state/registry.sqlite"))?;
+    let rows = conn
+        .prepare(
+            "SELECT id, task_id, stage, finished_at IS NOT NULL, tokens_in > 0, tokens_out > 0, success
+             FROM runs ORDER BY started_at, stage",
+        )?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, bool>(5)?,
+                row.get::<_, bool>(6)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(rows.len(), 3);
+    for (id, task_id, stage, finished, tokens_in, tokens_out, success) in rows {
+        assert_eq!(id, format!("{run_id}:{stage}"));
+        assert_eq!(task_id, "A-1");
+        assert!(finished && tokens_in && tokens_out && success, "{stage}");
+    }
+
+    let artifacts = conn
+        .prepare("SELECT kind, path, sha256 FROM artifacts WHERE run_id = ?1 ORDER BY path")?
+        .query_map([format!("{run_id}:plan")], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let kinds: Vec<&str> = artifacts.iter().map(|(kind, _, _)| kind.as_str()).collect();
+    assert_eq!(kinds, ["prompt", "result", "stderr", "stdout"]);
+    let (_, path, sha256) = &artifacts[3];
+    assert_eq!(path, &format!(".alisa/state/runs/{run_id}/plan/stdout.log"));
+    assert_eq!(sha256.len(), 64);
+
+    Ok(())
+}
+
+#[test]
+fn run_rejects_unknown_task() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "Z-9"])
+        .assert()
+        .failure()
+        .stderr(contains("task 'Z-9'"));
+
+    Ok(())
+}
+
+#[test]
+fn run_requires_initialized_workspace() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+
+    alisa(temp.path())
+        .args(["run", "A-1"])
+        .assert()
+        .failure()
+        .stderr(contains("alisa init"));
+
+    Ok(())
+}
+
+#[test]
+fn run_executes_review_pipeline() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(
+        temp.path(),
+        "#!/bin/sh\ncat >/dev/null\necho 'Verdict: PASS'\n",
+    )?;
+    let config = fs::read_to_string(temp.path().join("alisa.toml"))?;
+    fs::write(
+        temp.path().join("alisa.toml"),
+        format!(
+            r#"{config}
+[review]
+default_pipeline = "strict"
+
+[review.pipelines.strict]
+stages = ["build", "llm"]
+
+[review.stages.build]
+type = "exec"
+cmd = ["sh", "-c", "echo build ok"]
+
+[review.stages.llm]
+type = "llm"
+runner = "reviewer"
+"#
+        ),
+    )?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains("[review] build passed"))
+        .stdout(contains("[review] llm passed"));
+
+    let run_dir = single_run_dir(temp.path())?;
+    let verdict: Value =
+        serde_json::from_slice(&fs::read(run_dir.join("review/build/verdict.json"))?)?;
+    assert_eq!(verdict["passed"], true);
+    assert!(run_dir.join("review/verdicts.json").exists());
+    let summary: Value = serde_json::from_slice(&fs::read(run_dir.join("run.json"))?)?;
+    assert_eq!(summary["stages"][2]["pipeline"], "strict");
+
+    let conn = rusqlite::Connection::open(temp.path().join(".alisa/state/registry.sqlite"))?;
+    let stages = conn
+        .prepare("SELECT stage FROM runs WHERE success = 1 ORDER BY stage")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        stages,
+        ["code", "plan", "review", "review:build", "review:llm"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn run_fails_when_pipeline_rejects() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+    let config = fs::read_to_string(temp.path().join("alisa.toml"))?;
+    fs::write(
+        temp.path().join("alisa.toml"),
+        format!(
+            r#"{config}
+[review.pipelines.ci]
+stages = ["tests"]
+
+[review.stages.tests]
+type = "exec"
+cmd = ["sh", "-c", "exit 3"]
+"#
+        ),
+    )?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1", "--pipeline", "ci"])
+        .assert()
+        .code(1)
+        .stderr(contains(
+            "pipeline 'ci' rejected the change (failing stages: tests)",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn run_accepts_majority_despite_failing_stage() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+    let config = fs::read_to_string(temp.path().join("alisa.toml"))?;
+    fs::write(
+        temp.path().join("alisa.toml"),
+        format!(
+            r#"{config}
+[review.pipelines.vote]
+stages = ["build", "lint", "docs"]
+consensus = "majority"
+
+[review.stages.build]
+type = "exec"
+cmd = ["true"]
+
+[review.stages.lint]
+type = "exec"
+cmd = ["false"]
+
+[review.stages.docs]
+type = "exec"
+cmd = ["true"]
+"#
+        ),
+    )?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1", "--pipeline", "vote"])
+        .assert()
+        .success()
+        .stdout(contains("[review] lint failed"))
+        .stdout(contains("majority consensus passed: 2 of 3 stages passed"));
+
+    let verdicts: Value = serde_json::from_slice(&fs::read(
+        single_run_dir(temp.path())?.join("review/verdicts.json"),
+    )?)?;
+    assert_eq!(verdicts["decision"]["passed"], true);
+
+    // A fail_on veto overrides the majority.
+    let config = fs::read_to_string(temp.path().join("alisa.toml"))?;
+    fs::write(
+        temp.path().join("alisa.toml"),
+        config.replace(
+            "consensus = \"majority\"\n",
+            "consensus = \"majority\"\nfail_on = [\"lint\"]\n",
+        ),
+    )?;
+    alisa(temp.path())
+        .args(["run", "A-1", "--pipeline", "vote"])
+        .assert()
+        .code(1)
+        .stderr(contains("vetoed by failing fail_on stages: lint"));
+
+    Ok(())
+}
+
+fn write_limits_config(root: &Path, limits: &str) -> Result<(), Box<dyn std::error::Error>> {
+    write_config(root, RUNNER_SCRIPT)?;
+    fs::write(root.join("coder.sh"), DIFF_SCRIPT)?;
+    let config = fs::read_to_string(root.join("alisa.toml"))?;
+    fs::write(
+        root.join("alisa.toml"),
+        format!(
+            r#"{config}
+[runners.coder]
+cmd = "sh"
+args = ["coder.sh"]
+
+[profiles.small.roles]
+code = "coder"
+
+{limits}
+"#
+        ),
+    )?;
+    Ok(())
+}
+
+#[test]
+fn run_rejects_change_exceeding_profile_limits() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_limits_config(
+        temp.path(),
+        "[limits]\nmax_files = 10\nmax_changed_lines = 3\n\n[profiles.small.limits]\nmax_files = 1\n",
+    )?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1", "--profile", "small"])
+        .assert()
+        .code(1)
+        .stdout(contains("[code] change: 2 file(s), 4 changed line(s)"))
+        .stderr(contains("change exceeds limits of profile 'small':"))
+        .stderr(contains("max_files: 2 (limit 1)"))
+        .stderr(contains("max_changed_lines: 4 (limit 3)"));
+
+    let run_dir = single_run_dir(temp.path())?;
+    assert!(!run_dir.join("review").exists(), "review must not run");
+    let report: Value = serde_json::from_slice(&fs::read(run_dir.join("code/limits.json"))?)?;
+    assert_eq!(report["measured"]["files"], 2);
+    assert_eq!(report["violations"].as_array().map(Vec::len), Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn run_applies_patch_after_confirmation() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_limits_config(temp.path(), "[profiles.small.limits]\nmax_files = 2\n")?;
+    fs::create_dir_all(temp.path().join("src"))?;
+    fs::write(temp.path().join("src/a.rs"), "old\n")?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1", "--profile", "small"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(contains("+new"))
+        .stdout(contains("  M src/a.rs (+1 -1)"))
+        .stdout(contains("  A src/b.rs (+2 -0)"))
+        .stdout(contains("[apply] applied to 2 file(s)"));
+
+    assert_eq!(fs::read_to_string(temp.path().join("src/a.rs"))?, "new\n");
+    assert_eq!(
+        fs::read_to_string(temp.path().join("src/b.rs"))?,
+        "one\ntwo\n"
+    );
+    let run_dir = single_run_dir(temp.path())?;
+    assert!(run_dir.join("code/patch.diff").exists());
+    let summary: Value = serde_json::from_slice(&fs::read(run_dir.join("run.json"))?)?;
+    assert_eq!(summary["apply"]["status"], "applied");
+
+    Ok(())
+}
+
+#[test]
+fn run_leaves_tree_alone_when_apply_is_declined() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_limits_config(temp.path(), "")?;
+    fs::create_dir_all(temp.path().join("src"))?;
+    fs::write(temp.path().join("src/a.rs"), "old\n")?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1", "--profile", "small"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(contains("[apply] skipped; patch kept at"));
+
+    assert_eq!(fs::read_to_string(temp.path().join("src/a.rs"))?, "old\n");
+    assert!(!temp.path().join("src/b.rs").exists());
+
+    Ok(())
+}
+
+#[test]
+fn run_rolls_back_patch_that_does_not_apply() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    write_limits_config(temp.path(), "")?;
+    fs::create_dir_all(temp.path().join("src"))?;
+    fs::write(temp.path().join("src/a.rs"), "something else\n")?;
+    init(temp.path());
+
+    alisa(temp.path())
+        .args(["run", "A-1", "--profile", "small", "--yes"])
+        .assert()
+        .code(1)
+        .stderr(contains(
+            "patch could not be applied: src/a.rs: hunk #1 (expected at line 1) does not apply",
+        ));
+
+    assert_eq!(
+        fs::read_to_string(temp.path().join("src/a.rs"))?,
+        "something else\n"
+    );
+    assert!(!temp.path().join("src/b.rs").exists());
+    let run_dir = single_run_dir(temp.path())?;
+    assert!(run_dir.join("code/patch.diff").exists());
+    let summary: Value = serde_json::from_slice(&fs::read(run_dir.join("run.json"))?)?;
+    assert_eq!(summary["apply"]["status"], "failed");
+
+    Ok(())
+}
+
+#[test]
+fn run_injects_retrieved_docs_into_plan_and_code_prompts() -> Result<(), Box<dyn std::error::Error>>
+{
+    let temp = tempdir()?;
+    write_config(temp.path(), RUNNER_SCRIPT)?;
+    fs::create_dir_all(temp.path().join("docs"))?;
+    fs::write(
+        temp.path().join("docs/auth.md"),
+        "# Login form\nThe login form posts to /session.\n\n# Billing\nInvoices are monthly.\n",
+    )?;
+    init(temp.path());
+    alisa(temp.path())
+        .args(["rag", "index"])
+        .assert()
+        .success();
+
+    alisa(temp.path())
+        .args(["run", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains("[rag] 1 doc chunk(s) retrieved"));
+
+    let run_dir = single_run_dir(temp.path())?;
+    for stage in ["plan", "code"] {
+        let prompt = fs::read_to_string(run_dir.join(stage).join("prompt.md"))?;
+        assert!(prompt.contains("## Reference documentation\n\n### docs/auth.md:1-2\n"));
+        assert!(!prompt.contains("Invoices"));
+        let result: Value =
+            serde_json::from_slice(&fs::read(run_dir.join(stage).join("result.json"))?)?;
+        assert_eq!(result["docs"][0], "docs/auth.md:1-2");
+    }
+    let review_prompt = fs::read_to_string(run_dir.join("review/prompt.md"))?;
+    assert!(!review_prompt.contains("Reference documentation"));
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+#![cfg(unix)]
+
+use predicates::str::contains;
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+use time::{Duration, OffsetDateTime, UtcOffset, format_description::well_known::Rfc3339};
+
+mod common;
+
+use common::{FAILING_SCRIPT, RUNNER_SCRIPT, alisa, init, write_config};
+
+fn setup(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    write_config(root, RUNNER_SCRIPT)?;
+    init(root);
+    Ok(())
+}
+
+fn list_ids(root: &Path, args: &[&str]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let output = alisa(root)
+        .args(["runs", "list", "--json"])
+        .args(args)
+        .output()?;
+    assert!(output.status.success());
+    let runs: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(runs
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|run| run["task_id"].as_str().unwrap().to_string())
+        .collect())
+}
+
+#[test]
+fn runs_list_filters_history() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    alisa(temp.path()).args(["run", "A-1"]).assert().success();
+    fs::write(temp.path().join("review.sh"), FAILING_SCRIPT)?;
+    alisa(temp.path()).args(["run", "B-1"]).assert().code(1);
+
+    assert_eq!(list_ids(temp.path(), &[])?, ["B-1", "A-1"]);
+    assert_eq!(list_ids(temp.path(), &["--task", "A-1"])?, ["A-1"]);
+    assert_eq!(list_ids(temp.path(), &["--success", "false"])?, ["B-1"]);
+    assert_eq!(
+        list_ids(temp.path(), &["--stage", "code", "--success", "true"])?,
+        ["B-1", "A-1"]
+    );
+    assert_eq!(
+        list_ids(temp.path(), &["--model", "large"])?,
+        ["B-1", "A-1"]
+    );
+    assert!(list_ids(temp.path(), &["--model", "other"])?.is_empty());
+    assert!(list_ids(temp.path(), &["--until", "2000-01-01"])?.is_empty());
+    assert_eq!(list_ids(temp.path(), &["--since", "2000-01-01"])?.len(), 2);
+
+    // An hour before the runs, written with an offset that makes the text
+    // sort after their UTC timestamps.
+    let runs: Value = serde_json::from_slice(
+        &alisa(temp.path())
+            .args(["runs", "list", "--json"])
+            .output()?
+            .stdout,
+    )?;
+    let latest = OffsetDateTime::parse(runs[0]["started_at"].as_str().unwrap(), &Rfc3339)?;
+    let bound = (latest - Duration::hours(1))
+        .to_offset(UtcOffset::from_hms(2, 0, 0)?)
+        .format(&Rfc3339)?;
+    assert!(bound.ends_with("+02:00"));
+    assert_eq!(list_ids(temp.path(), &["--since", &bound])?.len(), 2);
+    assert!(list_ids(temp.path(), &["--until", &bound])?.is_empty());
+
+    alisa(temp.path())
+        .args(["runs", "list", "--task", "B-1"])
+        .assert()
+        .success()
+        .stdout(contains("B-1  "))
+        .stdout(contains("failed   plan:ok code:ok review:failed"));
+    alisa(temp.path())
+        .args(["runs", "list", "--since", "yesterday"])
+        .assert()
+        .code(2)
+        .stderr(contains("invalid date 'yesterday'"));
+
+    Ok(())
+}
+
+#[test]
+fn runs_list_reports_the_consensus_of_a_majority_pass() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+    let config = fs::read_to_string(temp.path().join("alisa.toml"))?;
+    fs::write(
+        temp.path().join("alisa.toml"),
+        format!(
+            r#"{config}
+[review.pipelines.vote]
+stages = ["build", "lint", "docs"]
+consensus = "majority"
+
+[review.stages.build]
+type = "exec"
+cmd = ["true"]
+
+[review.stages.lint]
+type = "exec"
+cmd = ["false"]
+
+[review.stages.docs]
+type = "exec"
+cmd = ["true"]
+"#
+        ),
+    )?;
+    alisa(temp.path())
+        .args(["run", "A-1", "--pipeline", "vote"])
+        .assert()
+        .success();
+
+    assert_eq!(list_ids(temp.path(), &["--success", "true"])?, ["A-1"]);
+    assert!(list_ids(temp.path(), &["--success", "false"])?.is_empty());
+    assert_eq!(
+        list_ids(temp.path(), &["--stage", "review:lint", "--success", "false"])?,
+        ["A-1"]
+    );
+    alisa(temp.path())
+        .args(["runs", "list"])
+        .assert()
+        .success()
+        .stdout(contains("ok       plan:ok code:ok review:ok"));
+
+    let runs: Value = serde_json::from_slice(
+        &alisa(temp.path())
+            .args(["runs", "list", "--json"])
+            .output()?
+            .stdout,
+    )?;
+    assert_eq!(runs[0]["success"], true);
+    let run_id = runs[0]["run_id"].as_str().unwrap();
+    alisa(temp.path())
+        .args(["runs", "show", run_id])
+        .assert()
+        .success()
+        .stdout(contains("status:    ok\n"));
+    let run: Value = serde_json::from_slice(
+        &alisa(temp.path())
+            .args(["runs", "show", run_id, "--json"])
+            .output()?
+            .stdout,
+    )?;
+    assert_eq!(run["success"], true);
+
+    Ok(())
+}
+
+#[test]
+fn runs_show_prints_stages_and_artifacts() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+    alisa(temp.path()).args(["run", "A-1"]).assert().success();
+
+    let output = alisa(temp.path())
+        .args(["runs", "list", "--json"])
+        .output()?;
+    let runs: Value = serde_json::from_slice(&output.stdout)?;
+    let run_id = runs[0]["run_id"].as_str().unwrap().to_string();
+
+    alisa(temp.path())
+        .args(["runs", "show", &run_id])
+        .assert()
+        .success()
+        .stdout(contains(format!("run:       {run_id}\ntask:      A-1\n")))
+        .stdout(contains(
+            "runners:   code=local, plan=local, review=reviewer\n",
+        ))
+        .stdout(contains("apply:     none\n"))
+        .stdout(contains("model large"))
+        .stdout(contains(format!(
+            ".alisa/state/runs/{run_id}/code/stdout.log"
+        )));
+
+    let output = alisa(temp.path())
+        .args(["runs", "show", &run_id, "--json"])
+        .output()?;
+    let run: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(run["success"], true);
+    assert_eq!(run["runners"]["review"], "reviewer");
+    let stages: Vec<&str> = run["stages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|stage| stage["stage"].as_str().unwrap())
+        .collect();
+    assert_eq!(stages, ["plan", "code", "review"]);
+    assert!(run["stages"][0]["duration_ms"].is_number());
+    assert!(run["tokens"]["in"].as_u64().unwrap() > 0);
+    assert_eq!(run["artifacts"].as_array().unwrap().len(), 13);
+
+    alisa(temp.path())
+        .args(["runs", "show", "run_missing"])
+        .assert()
+        .code(1)
+        .stderr(contains("run 'run_missing' not found"));
+
+    Ok(())
+}
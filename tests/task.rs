@@ -0,0 +1,335 @@
+use fs2::FileExt;
+use predicates::str::contains;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+
+mod common;
+
+use common::{alisa, init};
+
+const CONFIG: &str = r#"
+[runners.local]
+cmd = "sh"
+
+[roles]
+plan = "local"
+code = "local"
+review = "local"
+"#;
+
+const TASKS: &str = r#"version = 1
+
+# Authentication work
+[[tasks]]
+id = "A-1"
+title = "Add login"
+status = "doing"
+lang = "rust"
+tags = ["auth", "ui"]
+acceptance = ["form validates email"]
+
+[[tasks]]
+id = "A-2"
+title = "Add logout"   # follow-up
+tags = ["auth"]
+depends_on = ["A-1"]
+
+[[tasks]]
+id = "B-1"
+title = "Docs"
+status = "done"
+lang = "md"
+"#;
+
+fn setup(root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(root.join("alisa.toml"), CONFIG)?;
+    fs::write(root.join("tasks.toml"), TASKS)?;
+    init(root);
+    Ok(())
+}
+
+#[test]
+fn task_list_filters_by_status_tag_and_lang() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    let output = alisa(temp.path()).args(["task", "list"]).output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(
+        stdout,
+        "A-1  doing    Add login  [auth, ui]\nA-2  todo     Add logout  [auth]\nB-1  done     Docs\n"
+    );
+
+    alisa(temp.path())
+        .args(["task", "list", "--tag", "auth", "--status", "todo"])
+        .assert()
+        .success()
+        .stdout("A-2  todo     Add logout  [auth]\n");
+
+    alisa(temp.path())
+        .args(["task", "list", "--lang", "md"])
+        .assert()
+        .success()
+        .stdout("B-1  done     Docs\n");
+
+    alisa(temp.path())
+        .args(["task", "list", "--status", "later"])
+        .assert()
+        .code(2)
+        .stderr(contains("unknown task status 'later'"));
+
+    Ok(())
+}
+
+#[test]
+fn task_show_prints_details() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    alisa(temp.path())
+        .args(["task", "show", "A-1"])
+        .assert()
+        .success()
+        .stdout(contains("A-1: Add login\nstatus:      doing\n"))
+        .stdout(contains("tags:        auth, ui\n"))
+        .stdout(contains("Acceptance criteria:\n  - form validates email\n"));
+
+    alisa(temp.path())
+        .args(["task", "show", "Z-9"])
+        .assert()
+        .code(1)
+        .stderr(contains("task 'Z-9' not found"));
+
+    Ok(())
+}
+
+#[test]
+fn task_add_appends_entry_preserving_file() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    alisa(temp.path())
+        .args([
+            "task",
+            "add",
+            "A-3",
+            "Reset password",
+            "--tag",
+            "auth",
+            "--depends-on",
+            "A-1",
+            "--acceptance",
+            "email is sent",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("[ok] Added task A-3"));
+
+    let written = fs::read_to_string(temp.path().join("tasks.toml"))?;
+    assert!(written.starts_with(TASKS), "existing content must be kept");
+    assert!(written.ends_with(
+        "\n[[tasks]]\nid = \"A-3\"\ntitle = \"Reset password\"\nstatus = \"todo\"\ntags = [\"auth\"]\ndepends_on = [\"A-1\"]\nacceptance = [\"email is sent\"]\n"
+    ));
+
+    alisa(temp.path())
+        .args(["task", "add", "A-1", "Again"])
+        .assert()
+        .code(1)
+        .stderr(contains("task 'A-1' already exists"));
+
+    Ok(())
+}
+
+#[test]
+fn task_set_status_rewrites_only_status() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    alisa(temp.path())
+        .args(["task", "set-status", "A-2", "blocked"])
+        .assert()
+        .success()
+        .stdout("[ok] Task A-2 is now blocked\n");
+    alisa(temp.path())
+        .args(["task", "set-status", "A-1", "done"])
+        .assert()
+        .success();
+
+    let written = fs::read_to_string(temp.path().join("tasks.toml"))?;
+    let expected = TASKS
+        .replace("status = \"doing\"", "status = \"done\"")
+        .replace(
+            "depends_on = [\"A-1\"]\n",
+            "depends_on = [\"A-1\"]\nstatus = \"blocked\"\n",
+        );
+    assert_eq!(written, expected);
+
+    Ok(())
+}
+
+#[test]
+fn task_writes_require_workspace_lock() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    let lock_path = temp.path().join(".alisa/locks/workspace.lock");
+    fs::create_dir_all(lock_path.parent().unwrap())?;
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    lock.lock_exclusive()?;
+
+    alisa(temp.path())
+        .args(["task", "set-status", "A-1", "done"])
+        .assert()
+        .code(3)
+        .stderr(contains("is held by another process"));
+    // Reads do not need the lock.
+    alisa(temp.path()).args(["task", "list"]).assert().success();
+
+    lock.unlock()?;
+    assert_eq!(fs::read_to_string(temp.path().join("tasks.toml"))?, TASKS);
+    Ok(())
+}
+
+#[test]
+fn task_next_prints_most_urgent_ready_task() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    // A-2 waits for A-1, which is still in progress.
+    alisa(temp.path())
+        .args(["task", "next"])
+        .assert()
+        .success()
+        .stdout("No ready tasks.\n");
+
+    alisa(temp.path())
+        .args(["task", "set-status", "A-1", "done"])
+        .assert()
+        .success();
+    alisa(temp.path())
+        .args(["task", "add", "C-1", "Fix crash", "--priority", "high"])
+        .assert()
+        .success();
+    alisa(temp.path())
+        .args(["task", "next"])
+        .assert()
+        .success()
+        .stdout("C-1  todo     Fix crash  (priority: high)\n");
+
+    Ok(())
+}
+
+#[test]
+fn task_add_rejects_dependency_cycles() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    alisa(temp.path())
+        .args(["task", "add", "A-3", "Loop", "--depends-on", "A-3"])
+        .assert()
+        .code(1)
+        .stderr(contains("dependency cycle: A-3 -> A-3"));
+    alisa(temp.path())
+        .args(["task", "add", "A-3", "Orphan", "--depends-on", "Z-1"])
+        .assert()
+        .code(1)
+        .stderr(contains("task 'A-3' depends on unknown task 'Z-1'"));
+    assert_eq!(fs::read_to_string(temp.path().join("tasks.toml"))?, TASKS);
+
+    Ok(())
+}
+
+#[test]
+fn task_sync_mirrors_tasks_into_registry() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+
+    alisa(temp.path())
+        .args(["task", "sync"])
+        .assert()
+        .success()
+        .stdout("[ok] Synced 3 task(s) into the registry: 3 added, 0 updated, 0 archived\n");
+    alisa(temp.path())
+        .args(["task", "sync"])
+        .assert()
+        .success()
+        .stdout("[ok] Synced 3 task(s) into the registry: 0 added, 0 updated, 0 archived\n");
+
+    // Edits through `alisa task` keep the registry in step.
+    alisa(temp.path())
+        .args(["task", "set-status", "A-2", "doing"])
+        .assert()
+        .success();
+    let conn = rusqlite::Connection::open(temp.path().join(".alisa/state/registry.sqlite"))?;
+    let status: String = conn.query_row("SELECT status FROM tasks WHERE id = 'A-2'", [], |r| {
+        r.get(0)
+    })?;
+    assert_eq!(status, "doing");
+
+    fs::write(
+        temp.path().join("tasks.toml"),
+        TASKS
+            .replace("depends_on = [\"A-1\"]\n", "")
+            .replace("id = \"A-1\"", "id = \"A-0\""),
+    )?;
+    alisa(temp.path())
+        .args(["task", "sync"])
+        .assert()
+        .success()
+        .stdout(contains("1 added, 1 updated, 1 archived"));
+    let status: String = conn.query_row("SELECT status FROM tasks WHERE id = 'A-1'", [], |r| {
+        r.get(0)
+    })?;
+    assert_eq!(status, "archived");
+
+    Ok(())
+}
+
+#[test]
+fn task_search_ranks_and_filters_matches() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = tempdir()?;
+    setup(temp.path())?;
+    fs::write(
+        temp.path().join("tasks.toml"),
+        format!(
+            "{TASKS}\n[[tasks]]\nid = \"C-1\"\ntitle = \"Session cleanup\"\ndescription = \"Expire stale sessions after logout\"\ntags = [\"auth\"]\n"
+        ),
+    )?;
+
+    // The index is refreshed from tasks.toml before searching.
+    alisa(temp.path())
+        .args(["task", "search", "logout"])
+        .assert()
+        .success()
+        .stdout(contains("A-2  todo      Add logout"))
+        .stdout(contains("    Expire stale sessions after **logout**"));
+
+    let output = alisa(temp.path())
+        .args(["task", "search", "logout", "--tag", "auth", "--json"])
+        .output()?;
+    let hits: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let ids: Vec<&str> = hits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["A-2", "C-1"], "title matches rank first");
+
+    alisa(temp.path())
+        .args(["task", "search", "logout", "--status", "done"])
+        .assert()
+        .success()
+        .stdout("No matching tasks.\n");
+    alisa(temp.path())
+        .args(["task", "search", "logout AND"])
+        .assert()
+        .code(1)
+        .stderr(contains("Invalid search query 'logout AND'"));
+
+    Ok(())
+}